@@ -0,0 +1,73 @@
+"""Throughput of `BamReader` iteration against the number of bgzf threads.
+
+Usage: python benchmarks/bench_threads.py [path/to/file.bam]
+
+Without an argument a synthetic BAM of random 150 bp reads is written to a
+temporary directory first. Each thread count is timed over a full
+iteration (best of `REPEATS`), and the speedup over a single thread is
+reported.
+"""
+
+import os
+import random
+import sys
+import tempfile
+import time
+from pathlib import Path
+
+sys.path.insert(0, str(Path(__file__).resolve().parent.parent / "tests"))
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+N_RECORDS = 300_000
+READ_LEN = 150
+CHUNK_SIZE = 10_000
+REPEATS = 3
+
+
+def synthetic_bam(path):
+    rng = random.Random(0)
+    records = []
+    for i in range(N_RECORDS):
+        seq = "".join(rng.choice("ACGT") for _ in range(READ_LEN))
+        qual = [rng.randrange(2, 41) for _ in range(READ_LEN)]
+        records.append(
+            encode_record(
+                f"read{i}", rid=0, pos=i * 10, cigar=[("M", READ_LEN)], seq=seq, qual=qual
+            )
+        )
+    write_bam(path, [("chr1", N_RECORDS * 10 + READ_LEN)], records)
+
+
+def time_iteration(path, threads):
+    best = float("inf")
+    n = 0
+    for _ in range(REPEATS):
+        start = time.perf_counter()
+        n = sum(len(chunk) for chunk in lb.BamReader(str(path), chunk_size=CHUNK_SIZE, threads=threads))
+        best = min(best, time.perf_counter() - start)
+    return n, best
+
+
+def run(path):
+    size_mb = os.path.getsize(path) / 1e6
+    print(f"{path} ({size_mb:.1f} MB)")
+    baseline = None
+    for threads in (1, 2, 4, 8):
+        n, elapsed = time_iteration(path, threads)
+        baseline = baseline or elapsed
+        print(
+            f"threads={threads}: {n / elapsed:,.0f} records/s, "
+            f"{size_mb / elapsed:.1f} MB/s, {baseline / elapsed:.2f}x"
+        )
+
+
+if __name__ == "__main__":
+    if len(sys.argv) > 1:
+        run(Path(sys.argv[1]))
+    else:
+        with tempfile.TemporaryDirectory() as tmp:
+            path = Path(tmp) / "synthetic.bam"
+            synthetic_bam(path)
+            run(path)
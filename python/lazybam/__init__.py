@@ -1,16 +1,29 @@
 from .lazybam import (
     BamReader,
+    CramReader,
+    BamWriter,
+    BamInterleaver,
     write_chunk_py,
     merge_chunks_py,
     PyBamRecord,
     RecordOverride,
     PyRecordBuf,
     write_recordbuf_chunk_py,
+    build_consensus_read,
+    merge_pair,
+    IntervalSet,
+    transform,
+    tag_all,
+    deduplicate,
+    downsample_bam,
 )
 from .header import BamHeader
 
 __all__ = [
     "BamReader",
+    "CramReader",
+    "BamWriter",
+    "BamInterleaver",
     "write_chunk_py",
     "merge_chunks_py",
     "BamHeader",
@@ -18,6 +31,13 @@ __all__ = [
     "RecordOverride",
     "PyRecordBuf",
     "write_recordbuf_chunk_py",
+    "build_consensus_read",
+    "merge_pair",
+    "IntervalSet",
+    "transform",
+    "tag_all",
+    "deduplicate",
+    "downsample_bam",
 ]
 
 
@@ -28,5 +48,6 @@ def _get_header(self: BamReader):
 
 # property としてクラスに追加
 BamReader.header = property(_get_header)  #  type: ignore
+CramReader.header = property(_get_header)  #  type: ignore
 
 __doc__ = lazybam.__doc__
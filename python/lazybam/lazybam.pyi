@@ -1,6 +1,6 @@
 from __future__ import annotations
 
-from typing import Any, List, Optional, Tuple
+from typing import Any, BinaryIO, Callable, Dict, Iterator, List, Optional, Set, Tuple, Union
 
 import numpy as np  # type: ignore
 
@@ -72,21 +72,117 @@ class PyBamRecord:
     qname: str
     flag: int
     pos: int
-    len: int  # template length
+    len: int  # absolute template length
     mapq: int
     rid: int
 
     # ── getters (read-only properties) ----------------------------------
     @property
+    def is_paired(self) -> bool: ...
+    @property
+    def is_proper_pair(self) -> bool: ...
+    @property
+    def is_unmapped(self) -> bool: ...
+    @property
+    def is_mate_unmapped(self) -> bool: ...
+    @property
+    def is_reverse(self) -> bool: ...
+    @property
+    def is_mate_reverse(self) -> bool: ...
+    @property
+    def is_read1(self) -> bool: ...
+    @property
+    def is_read2(self) -> bool: ...
+    @property
+    def is_secondary(self) -> bool: ...
+    @property
+    def is_qcfail(self) -> bool: ...
+    @property
+    def is_duplicate(self) -> bool: ...
+    @property
+    def is_supplementary(self) -> bool: ...
+    @property
+    def rname(self) -> Optional[str]: ...
+    @property
+    def template_length(self) -> int: ...
+    @property
+    def mate_pos(self) -> int: ...
+    @property
+    def mate_rname(self) -> Optional[str]: ...
+    @property
     def seq(self) -> str: ...
     @property
+    def seq_forward(self) -> str: ...
+    @property
+    def seq_array(self) -> np.ndarray: ...
+    @property
+    def reference_length(self) -> int: ...
+    @property
+    def alignment_end(self) -> int: ...
+    @property
+    def reference_end(self) -> int: ...
+    @property
+    def query_length(self) -> int: ...
+    @property
     def qual(self) -> List[int]: ...
     @property
+    def qual_array(self) -> np.ndarray: ...
+    # (kind, length) pairs; kind is the BAM op code (MIDNSHP=X -> 0..8)
+    @property
     def cigar(self) -> List[Tuple[int, int]]: ...
     @property
+    def cigar_strict(self) -> List[Tuple[int, int]]: ...
+    @property
     def tags(self) -> List[Tuple[str, Any]]: ...
+    def reference_n_count(self, reference_fasta: str) -> int: ...
+    def aligned_sequences(self, reference_fasta: str) -> Tuple[str, str]: ...
+    def local_gc(self, window: int) -> np.ndarray: ...
+    def alignment_events(
+        self, reference_fasta: str
+    ) -> List[
+        Tuple[
+            Optional[int], Optional[int], str, Optional[str], Optional[str], Optional[int]
+        ]
+    ]: ...
+    def novel_junctions(
+        self, known_junctions: Set[Tuple[int, int, int]]
+    ) -> List[Tuple[int, int]]: ...
+    @property
+    def gap_summary(self) -> Dict[str, int]: ...
+    @property
+    def exon_contributions(self) -> List[Tuple[int, int, int]]: ...
+    @property
+    def modified_bases(self) -> Dict[Tuple[str, int, str], np.ndarray]: ...
+    @property
+    def original_quality(self) -> np.ndarray: ...
+    @property
+    def query_alignment_qualities(self) -> np.ndarray: ...
+    @property
+    def signature(self) -> int: ...
+    @property
+    def extends_past_contig(self) -> bool: ...
+    @property
+    def mate_is_reverse(self) -> bool: ...
+    @property
+    def mate_reference_name(self) -> Optional[str]: ...
+    @property
+    def pair_orientation(self) -> Optional[str]: ...
+    @property
+    def fragment_midpoint(self) -> Optional[int]: ...
+    @property
+    def sequence_segments(self) -> List[Tuple[str, int, int, str]]: ...
+    @property
+    def optical_coordinates(self) -> Optional[Dict[str, Any]]: ...
     def set_record_override(self, record_override: RecordOverride) -> None: ...
     def get_field_by_tag(self, tag: str) -> Any: ...
+    def get_tag(self, tag: str) -> Optional[Any]: ...
+    def aligned_pairs(self, include_soft_clips: bool = False) -> np.ndarray: ...
+    def get_reference_sequence(self) -> str: ...
+    def to_sam_line(self) -> str: ...
+    def set_tag(self, tag: str, value: Union[int, float, str, bytes, List[int], List[float]]) -> None: ...
+    def query_to_reference(self, query_pos: int) -> Optional[int]: ...
+    def reference_to_query(self, ref_pos: int) -> Optional[int]: ...
+    def alignment_stats(self, reference_fasta: Optional[str] = None) -> Dict[str, int]: ...
 
 class PyRecordBuf:
     def __init__(
@@ -101,10 +197,109 @@ class PyRecordBuf:
         tags: Optional[List[Tuple[str, Any]]] = None,
     ) -> None: ...
 
+class IntervalSet:
+    def __init__(self, features: List[Tuple[Any, ...]]) -> None: ...
+    def __len__(self) -> int: ...
+    def nearest_feature(self, record: PyBamRecord) -> Optional[Tuple[str, int]]: ...
+
+class SortWindowIterator:
+    def __iter__(self) -> SortWindowIterator: ...
+    def __next__(self) -> PyBamRecord: ...
+
+class SlidingIterator:
+    def __iter__(self) -> SlidingIterator: ...
+    def __next__(self) -> Tuple[PyBamRecord, List[PyBamRecord]]: ...
+
+class RecordIterator:
+    def __iter__(self) -> RecordIterator: ...
+    def __next__(self) -> PyBamRecord: ...
+
+class RegionIterator:
+    def __iter__(self) -> RegionIterator: ...
+    def __next__(self) -> PyBamRecord: ...
+
+class ArrowBatchIterator:
+    def __iter__(self) -> ArrowBatchIterator: ...
+    def __next__(self) -> Any: ...  # pyarrow.RecordBatch
+
+class SamLinesIterator:
+    def __iter__(self) -> SamLinesIterator: ...
+    def __next__(self) -> str: ...
+
+class BamWriter:
+    def __init__(
+        self, path: str, header_bytes: bytes, block_records: Optional[int] = None
+    ) -> None: ...
+    def write(self, record: PyBamRecord) -> None: ...
+    def close(self) -> None: ...
+    def __enter__(self) -> BamWriter: ...
+    def __exit__(self, exc_type: Any, exc_val: Any, trace: Any) -> None: ...
+
+class BamInterleaver:
+    def __init__(self, paths: List[str]) -> None: ...
+    def __iter__(self) -> BamInterleaver: ...
+    def __next__(self) -> PyBamRecord: ...
+
+class CramReader:
+    def __init__(
+        self,
+        path: str,
+        reference_path: str,
+        chunk_size: Optional[int] = None,
+        tag_conversion: str = "python",
+    ) -> None: ...
+    def __enter__(self) -> CramReader: ...
+    def __exit__(self, exc_type: Any, exc_val: Any, traceback: Any) -> None: ...
+    def __iter__(self) -> CramReader: ...
+    def __next__(self) -> List[PyBamRecord]: ...
+    @property
+    def _header(self) -> bytes: ...
+    @property
+    def header(self) -> BamHeader: ...
+
 class BamReader:
     def __init__(
-        self, path: str, chunk_size: Optional[int] = None, region: Optional[str] = None
+        self,
+        path: str,
+        chunk_size: Optional[int] = None,
+        region: Optional[str] = None,
+        tag_conversion: str = "python",
+        max_record_size: Optional[int] = None,
+        min_query_length: Optional[int] = None,
+        max_query_length: Optional[int] = None,
+        min_mapq: Optional[int] = None,
+        exclude_flags: Optional[int] = None,
+        require_flags: Optional[int] = None,
+        threads: Optional[int] = None,
     ) -> None: ...
+    @staticmethod
+    def from_url(
+        url: str,
+        index_url: Optional[str] = None,
+        headers: Optional[Dict[str, str]] = None,
+        chunk_size: Optional[int] = None,
+        tag_conversion: str = "python",
+        max_record_size: Optional[int] = None,
+        min_query_length: Optional[int] = None,
+        max_query_length: Optional[int] = None,
+        min_mapq: Optional[int] = None,
+        exclude_flags: Optional[int] = None,
+        require_flags: Optional[int] = None,
+        threads: Optional[int] = None,
+    ) -> BamReader: ...
+    @staticmethod
+    def from_fileobj(
+        obj: BinaryIO,
+        chunk_size: Optional[int] = None,
+        tag_conversion: str = "python",
+        max_record_size: Optional[int] = None,
+        min_query_length: Optional[int] = None,
+        max_query_length: Optional[int] = None,
+        min_mapq: Optional[int] = None,
+        exclude_flags: Optional[int] = None,
+        require_flags: Optional[int] = None,
+        threads: Optional[int] = None,
+    ) -> BamReader: ...
 
     # ── context‑manager --------------------------------------------------
     def __enter__(self) -> BamReader: ...
@@ -118,12 +313,103 @@ class BamReader:
     # ── iterator ---------------------------------------------------------
     def __iter__(self) -> BamReader: ...
     def __next__(self) -> List[PyBamRecord]: ...
+    def next_namedtuples(
+        self, n: int, fields: Optional[List[str]] = None
+    ) -> List[Tuple[Any, ...]]: ...
+    def next_tag_columns(self, names: List[str], n: int) -> Dict[str, np.ma.MaskedArray]: ...
+    def next_batch(self) -> Dict[str, Union[np.ndarray, List[str]]]: ...
+    def next_struct(self) -> np.ndarray: ...
+    def sort_window(self, window_size: int) -> SortWindowIterator: ...
+    def sliding(self, n_before: int) -> SlidingIterator: ...
+    def records(self) -> RecordIterator: ...
+    def count(
+        self,
+        exclude_flags: Optional[int] = None,
+        require_flags: Optional[int] = None,
+        min_mapq: Optional[int] = None,
+    ) -> int: ...
+    def sam_lines(self) -> SamLinesIterator: ...
 
     # ── other properties -------------------------------------------------
     @property
     def _header(self) -> bytes: ...
     @property
     def header(self) -> BamHeader: ...
+    @property
+    def references(self) -> List[Tuple[str, int]]: ...
+    @property
+    def read_groups(self) -> List[str]: ...
+    @property
+    def virtual_position(self) -> int: ...
+    def seek(self, virtual_offset: int) -> None: ...
+
+    # ── region queries (require a .bai index) ----------------------------
+    def read_clusters(
+        self, reference_name: str, start: int, end: int, gap: int = 0
+    ) -> List[Tuple[List[PyBamRecord], int, int]]: ...
+    def fragment_coverage(
+        self, reference_name: str, start: int, end: int, max_insert_size: int = 1000
+    ) -> np.ndarray: ...
+    def indel_profile(
+        self, reference_name: str, start: int, end: int
+    ) -> Tuple[np.ndarray, np.ndarray]: ...
+    def reads_at_breakpoint(
+        self, reference_name: str, position: int, window: int
+    ) -> Dict[str, List[PyBamRecord]]: ...
+    def methylation_profile(
+        self, reference_name: str, start: int, end: int, min_prob: float = 0.5
+    ) -> Tuple[np.ndarray, np.ndarray]: ...
+    def normalized_coverage(
+        self,
+        reference_name: str,
+        start: int,
+        end: int,
+        scale: Union[str, float] = "cpm",
+    ) -> np.ndarray: ...
+    def allele_depths(
+        self,
+        loci: List[Tuple[str, int]],
+        min_base_qual: int = 0,
+        min_mapq: int = 0,
+    ) -> List[Dict[str, int]]: ...
+    def fetch(self, contig: str, start: int, end: int) -> RegionIterator: ...
+    def coverage(self, contig: str, start: int, end: int) -> np.ndarray: ...
+    def pileup_tensor(
+        self, reference_name: str, start: int, end: int, max_depth: int
+    ) -> np.ndarray: ...
+    def region_payload(
+        self, reference_name: str, start: int, end: int, max_depth: int
+    ) -> bytes: ...
+    def coverage_uniformity(
+        self, regions: List[Tuple[str, int, int]]
+    ) -> Dict[str, Optional[float]]: ...
+
+    # ── whole-file scans -------------------------------------------------
+    def reference_counts(self) -> Tuple[np.ndarray, int]: ...
+    def idxstats(self) -> List[Tuple[str, int, int, int]]: ...
+    def checksum(self, algorithm: str = "crc32") -> int: ...
+    def duplicate_stats(self) -> Dict[str, Any]: ...
+    def quality_yield(self, threshold: int = 30) -> Dict[str, Any]: ...
+    def insert_size_percentiles(
+        self, percentiles: List[float] = [10, 50, 90], max_size: int = 5000
+    ) -> List[int]: ...
+    def write_fasta(self, output_path: str, reverse_complement: bool = True) -> int: ...
+    def to_arrow_batches(self, columns: List[str]) -> ArrowBatchIterator: ...
+    def write_parquet(
+        self,
+        output_path: str,
+        fields: Optional[List[str]] = None,
+        tags: Optional[List[str]] = None,
+    ) -> int: ...
+    def quality_base_matrix(self, max_read_len: int, n_qual_bins: int) -> np.ndarray: ...
+    def verify_sorted(self, order: str = "coordinate") -> Optional[Tuple[int, str]]: ...
+    def coverage_summary(self) -> Dict[str, Dict[str, float]]: ...
+    def covered_bases(
+        self, reference_name: Optional[str] = None, min_depth: int = 1
+    ) -> int: ...
+    def clip_breakpoints(
+        self, min_clip_len: int = 10, min_support: int = 3
+    ) -> List[Tuple[str, int, str, int]]: ...
 
 # Writing functions
 def write_chunk_py(
@@ -131,6 +417,7 @@ def write_chunk_py(
     records: List[PyBamRecord],
     out_bam: str,
     sort: bool,
+    block_records: Optional[int] = None,
 ) -> None: ...
 def merge_chunks_py(
     header_bytes: bytes,
@@ -143,4 +430,38 @@ def write_recordbuf_chunk_py(
     records: List[PyRecordBuf],
     out_bam: str,
     sort: bool,
+    block_records: Optional[int] = None,
 ) -> None: ...
+def transform(
+    input_path: str,
+    output_path: str,
+    func: Callable[[PyBamRecord], Union[PyBamRecord, List[PyBamRecord], None]],
+    chunk_size: Optional[int] = None,
+    max_record_size: Optional[int] = None,
+) -> int: ...
+def tag_all(
+    input_path: str,
+    output_path: str,
+    tag_name: str,
+    value: Any,
+    max_record_size: Optional[int] = None,
+) -> int: ...
+def deduplicate(
+    input_path: str,
+    output_path: str,
+    umi_source: str = "RX",
+    remove: bool = True,
+    max_record_size: Optional[int] = None,
+) -> Tuple[int, int]: ...
+def downsample_bam(
+    input_path: str,
+    output_path: str,
+    fraction: float,
+    seed: int = 0,
+    keep_pairs: bool = True,
+    max_record_size: Optional[int] = None,
+) -> int: ...
+
+# Consensus
+def build_consensus_read(records: List[PyBamRecord]) -> PyBamRecord: ...
+def merge_pair(read1: PyBamRecord, read2: PyBamRecord) -> Tuple[str, List[int]]: ...
@@ -0,0 +1,350 @@
+//! CIGAR / MD walking helpers shared by the per-record methods.
+//!
+//! Everything here works on already-decoded CIGAR ops so the callers can
+//! decide how to treat malformed input.
+
+use noodles::bam;
+use noodles::sam::alignment::record::cigar::op::{Kind, Op};
+use noodles::sam::alignment::Record as _;
+
+/// Reference span of a mapped record as 0-based, half-open coordinates.
+pub fn reference_span(rec: &bam::Record) -> Option<(i64, i64)> {
+    if rec.flags().is_unmapped() {
+        return None;
+    }
+    let start = rec.alignment_start()?.ok()?;
+    let end = rec.alignment_end()?.ok()?;
+    Some((usize::from(start) as i64 - 1, usize::from(end) as i64))
+}
+
+/// Base counts per alignment event for a single read.
+///
+/// `unresolved` holds `M` bases whose match/mismatch status is not encoded
+/// in the CIGAR itself (`=`/`X` are counted directly).
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AlignmentCounts {
+    pub matches: usize,
+    pub mismatches: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+    pub soft_clips: usize,
+    pub hard_clips: usize,
+    pub unresolved: usize,
+}
+
+/// Tally CIGAR op lengths by kind.
+pub fn count_ops(ops: &[Op]) -> AlignmentCounts {
+    let mut counts = AlignmentCounts::default();
+    for op in ops {
+        match op.kind() {
+            Kind::Match => counts.unresolved += op.len(),
+            Kind::SequenceMatch => counts.matches += op.len(),
+            Kind::SequenceMismatch => counts.mismatches += op.len(),
+            Kind::Insertion => counts.insertions += op.len(),
+            Kind::Deletion => counts.deletions += op.len(),
+            Kind::SoftClip => counts.soft_clips += op.len(),
+            Kind::HardClip => counts.hard_clips += op.len(),
+            Kind::Skip | Kind::Pad => {}
+        }
+    }
+    counts
+}
+
+/// Number of mismatched bases recorded in an MD string.
+///
+/// Letters following `^` are deleted reference bases and are not counted.
+pub fn md_mismatch_count(md: &str) -> usize {
+    let mut n = 0;
+    let mut in_deletion = false;
+    for c in md.chars() {
+        if c == '^' {
+            in_deletion = true;
+        } else if c.is_ascii_digit() {
+            in_deletion = false;
+        } else if !in_deletion {
+            n += 1;
+        }
+    }
+    n
+}
+
+/// Reference bases spanned by the aligned read, rebuilt from the read
+/// sequence and the MD string.
+///
+/// `M`/`=`/`X` bases copy the read base unless MD names a mismatching
+/// reference base; MD `^` runs supply deleted bases. `N` skips are not
+/// described by MD and contribute nothing. Fails when MD and CIGAR
+/// disagree on the number of aligned bases.
+pub fn md_reference(ops: &[Op], query: &[u8], md: &str) -> Result<Vec<u8>, String> {
+    // M/=/X に並ぶリード塩基
+    let mut aligned = Vec::new();
+    let mut q = 0;
+    for op in ops {
+        let len = op.len();
+        match op.kind() {
+            Kind::Match | Kind::SequenceMatch | Kind::SequenceMismatch => {
+                let bases = query
+                    .get(q..q + len)
+                    .ok_or("CIGAR is longer than the read sequence")?;
+                aligned.extend_from_slice(bases);
+                q += len;
+            }
+            Kind::Insertion | Kind::SoftClip => q += len,
+            Kind::Deletion | Kind::Skip | Kind::HardClip | Kind::Pad => {}
+        }
+    }
+
+    let mut reference = Vec::with_capacity(aligned.len());
+    let mut a = 0;
+    let mut run = 0usize;
+    let mut in_deletion = false;
+    let take_matches = |run: &mut usize, a: &mut usize, reference: &mut Vec<u8>| {
+        let bases = aligned
+            .get(*a..*a + *run)
+            .ok_or_else(|| format!("MD tag {md:?} covers more bases than the CIGAR"))?;
+        reference.extend_from_slice(bases);
+        *a += *run;
+        *run = 0;
+        Ok::<(), String>(())
+    };
+    for c in md.bytes() {
+        if c.is_ascii_digit() {
+            run = run * 10 + usize::from(c - b'0');
+            in_deletion = false;
+        } else if c == b'^' {
+            take_matches(&mut run, &mut a, &mut reference)?;
+            in_deletion = true;
+        } else if c.is_ascii_alphabetic() {
+            take_matches(&mut run, &mut a, &mut reference)?;
+            reference.push(c.to_ascii_uppercase());
+            if !in_deletion {
+                // 不一致: リード塩基を 1 つ読み飛ばす
+                if a >= aligned.len() {
+                    return Err(format!("MD tag {md:?} covers more bases than the CIGAR"));
+                }
+                a += 1;
+            }
+        } else {
+            return Err(format!("invalid character {:?} in MD tag", c as char));
+        }
+    }
+    take_matches(&mut run, &mut a, &mut reference)?;
+    if a != aligned.len() {
+        return Err(format!("MD tag {md:?} covers fewer bases than the CIGAR"));
+    }
+    Ok(reference)
+}
+
+/// Count mismatching bases inside `M` ops by comparing the read against
+/// `reference`, which must start at the record's alignment start.
+pub fn count_match_op_mismatches(ops: &[Op], query: &[u8], reference: &[u8]) -> usize {
+    let mut q = 0;
+    let mut r = 0;
+    let mut mismatches = 0;
+    for op in ops {
+        let len = op.len();
+        match op.kind() {
+            Kind::Match => {
+                for i in 0..len {
+                    match (query.get(q + i), reference.get(r + i)) {
+                        (Some(a), Some(b)) if !a.eq_ignore_ascii_case(b) => mismatches += 1,
+                        _ => {}
+                    }
+                }
+                q += len;
+                r += len;
+            }
+            Kind::SequenceMatch | Kind::SequenceMismatch => {
+                q += len;
+                r += len;
+            }
+            Kind::Insertion | Kind::SoftClip => q += len,
+            Kind::Deletion | Kind::Skip => r += len,
+            Kind::HardClip | Kind::Pad => {}
+        }
+    }
+    mismatches
+}
+
+/// Reference position (0-based) aligned to the 0-based read offset
+/// `query_pos`, or `None` if that base is inserted / soft-clipped or past
+/// the end of the read.
+pub fn query_to_reference(ops: &[Op], ref_start: i64, query_pos: usize) -> Option<i64> {
+    let mut q = 0usize;
+    let mut r = ref_start;
+    for op in ops {
+        let len = op.len();
+        match op.kind() {
+            Kind::Match | Kind::SequenceMatch | Kind::SequenceMismatch => {
+                if query_pos < q + len {
+                    return Some(r + (query_pos - q) as i64);
+                }
+                q += len;
+                r += len as i64;
+            }
+            Kind::Insertion | Kind::SoftClip => {
+                if query_pos < q + len {
+                    return None;
+                }
+                q += len;
+            }
+            Kind::Deletion | Kind::Skip => r += len as i64,
+            Kind::HardClip | Kind::Pad => {}
+        }
+    }
+    None
+}
+
+/// `(query_pos, ref_pos)` for every CIGAR position, as pysam's
+/// `get_aligned_pairs`; `-1` marks the side without a counterpart
+/// (insertions, deletions, skips). Soft-clipped bases are listed as
+/// `(query_pos, -1)` only when `include_soft_clips` is set.
+pub fn aligned_pairs(ops: &[Op], ref_start: i64, include_soft_clips: bool) -> Vec<(i64, i64)> {
+    let mut pairs = Vec::new();
+    let mut q = 0i64;
+    let mut r = ref_start;
+    for op in ops {
+        let len = op.len() as i64;
+        match op.kind() {
+            Kind::Match | Kind::SequenceMatch | Kind::SequenceMismatch => {
+                pairs.extend((0..len).map(|i| (q + i, r + i)));
+                q += len;
+                r += len;
+            }
+            Kind::Insertion => {
+                pairs.extend((q..q + len).map(|qi| (qi, -1)));
+                q += len;
+            }
+            Kind::SoftClip => {
+                if include_soft_clips {
+                    pairs.extend((q..q + len).map(|qi| (qi, -1)));
+                }
+                q += len;
+            }
+            Kind::Deletion | Kind::Skip => {
+                pairs.extend((r..r + len).map(|ri| (-1, ri)));
+                r += len;
+            }
+            Kind::HardClip | Kind::Pad => {}
+        }
+    }
+    pairs
+}
+
+/// 0-based read offset aligned to the 0-based reference position
+/// `ref_pos`, or `None` if that position is deleted / skipped or outside
+/// the aligned span.
+pub fn reference_to_query(ops: &[Op], ref_start: i64, ref_pos: i64) -> Option<usize> {
+    if ref_pos < ref_start {
+        return None;
+    }
+    let mut q = 0usize;
+    let mut r = ref_start;
+    for op in ops {
+        let len = op.len();
+        match op.kind() {
+            Kind::Match | Kind::SequenceMatch | Kind::SequenceMismatch => {
+                if ref_pos < r + len as i64 {
+                    return Some(q + (ref_pos - r) as usize);
+                }
+                q += len;
+                r += len as i64;
+            }
+            Kind::Deletion | Kind::Skip => {
+                if ref_pos < r + len as i64 {
+                    return None;
+                }
+                r += len as i64;
+            }
+            Kind::Insertion | Kind::SoftClip => q += len,
+            Kind::HardClip | Kind::Pad => {}
+        }
+    }
+    None
+}
+
+/// Total clipped length (soft + hard) at the left and right ends.
+pub fn terminal_clips(ops: &[Op]) -> (usize, usize) {
+    let is_clip = |op: &&Op| matches!(op.kind(), Kind::SoftClip | Kind::HardClip);
+    let left = ops.iter().take_while(is_clip).map(|op| op.len()).sum();
+    let right = if ops.iter().all(|op| is_clip(&op)) {
+        0
+    } else {
+        ops.iter()
+            .rev()
+            .take_while(is_clip)
+            .map(|op| op.len())
+            .sum()
+    };
+    (left, right)
+}
+
+/// Aligned (`M`/`=`/`X`) blocks on the reference as 0-based, half-open
+/// intervals. Adjacent blocks are not merged.
+pub fn aligned_blocks(ops: &[Op], ref_start: i64) -> Vec<(i64, i64)> {
+    let mut blocks = Vec::new();
+    let mut r = ref_start;
+    for op in ops {
+        let len = op.len() as i64;
+        match op.kind() {
+            Kind::Match | Kind::SequenceMatch | Kind::SequenceMismatch => {
+                blocks.push((r, r + len));
+                r += len;
+            }
+            Kind::Deletion | Kind::Skip => r += len,
+            _ => {}
+        }
+    }
+    blocks
+}
+
+/// Reference segments between `N` skips as 0-based, half-open
+/// `(start, end, aligned_bases)`. Deletions widen a segment but are not
+/// counted in `aligned_bases` (`M`/`=`/`X` only).
+pub fn exon_blocks(ops: &[Op], ref_start: i64) -> Vec<(i64, i64, usize)> {
+    let mut exons = Vec::new();
+    let mut r = ref_start;
+    let (mut exon_start, mut aligned) = (ref_start, 0usize);
+    for op in ops {
+        let len = op.len() as i64;
+        match op.kind() {
+            Kind::Match | Kind::SequenceMatch | Kind::SequenceMismatch => {
+                aligned += op.len();
+                r += len;
+            }
+            Kind::Deletion => r += len,
+            Kind::Skip => {
+                if r > exon_start {
+                    exons.push((exon_start, r, aligned));
+                }
+                r += len;
+                exon_start = r;
+                aligned = 0;
+            }
+            _ => {}
+        }
+    }
+    if r > exon_start {
+        exons.push((exon_start, r, aligned));
+    }
+    exons
+}
+
+/// Reference skips (`N` ops, i.e. introns) as 0-based, half-open
+/// `(donor, acceptor)` intervals.
+pub fn introns(ops: &[Op], ref_start: i64) -> Vec<(i64, i64)> {
+    let mut introns = Vec::new();
+    let mut r = ref_start;
+    for op in ops {
+        let len = op.len() as i64;
+        match op.kind() {
+            Kind::Skip => {
+                introns.push((r, r + len));
+                r += len;
+            }
+            Kind::Match | Kind::SequenceMatch | Kind::SequenceMismatch | Kind::Deletion => r += len,
+            _ => {}
+        }
+    }
+    introns
+}
@@ -0,0 +1,107 @@
+//! Streaming of records as Arrow record batches.
+//!
+//! Batches are built with arrow-rs and handed to pyarrow through the Arrow
+//! C data interface, so no per-record Python objects are created. Missing
+//! values (`*` in SAM terms) become nulls rather than sentinels.
+
+use arrow_array::ffi::{to_ffi, FFI_ArrowArray, FFI_ArrowSchema};
+use arrow_array::{Array, ArrayRef, RecordBatch, StructArray};
+use arrow_schema::Schema;
+use noodles::{bam, sam};
+use pyo3::prelude::*;
+use std::sync::{Arc, Mutex};
+
+use crate::columns::{Missing, RecordColumn};
+use crate::iterator::{read_error_to_py, read_record_limited};
+use crate::source::BamStream;
+
+/// RecordBatch を C data interface 経由で pyarrow.RecordBatch にする
+fn export_batch(py: Python<'_>, batch: RecordBatch) -> PyResult<PyObject> {
+    let data = StructArray::from(batch).into_data();
+    let (array, schema): (FFI_ArrowArray, FFI_ArrowSchema) = to_ffi(&data)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+    // pyarrow が release コールバックごと中身を引き取る
+    let array = Box::new(array);
+    let schema = Box::new(schema);
+    let batch = py.import("pyarrow")?.getattr("RecordBatch")?.call_method1(
+        "_import_from_c",
+        (
+            &*array as *const FFI_ArrowArray as usize,
+            &*schema as *const FFI_ArrowSchema as usize,
+        ),
+    )?;
+    Ok(batch.unbind())
+}
+
+/// Yields one `pyarrow.RecordBatch` per chunk of records.
+#[pyclass]
+pub struct ArrowBatchIterator {
+    reader: Mutex<BamStream>,
+    header: Arc<sam::Header>,
+    columns: Vec<RecordColumn>,
+    schema: Arc<Schema>,
+    batch_size: usize,
+    max_record_size: usize,
+}
+
+impl ArrowBatchIterator {
+    pub fn new(
+        reader: BamStream,
+        header: Arc<sam::Header>,
+        columns: Vec<RecordColumn>,
+        batch_size: usize,
+        max_record_size: usize,
+    ) -> Self {
+        let schema = Arc::new(Schema::new(
+            columns
+                .iter()
+                .map(|c| c.field(Missing::Null))
+                .collect::<Vec<_>>(),
+        ));
+        Self {
+            reader: Mutex::new(reader),
+            header,
+            columns,
+            schema,
+            batch_size: batch_size.max(1),
+            max_record_size,
+        }
+    }
+}
+
+#[pymethods]
+impl ArrowBatchIterator {
+    fn __iter__(slf: PyRefMut<'_, Self>) -> PyRefMut<'_, Self> {
+        slf
+    }
+
+    fn __next__(mut slf: PyRefMut<'_, Self>, py: Python<'_>) -> PyResult<Option<PyObject>> {
+        let this = &mut *slf;
+        let batch = py.allow_threads(|| -> PyResult<Option<RecordBatch>> {
+            let mut reader = this.reader.lock().unwrap();
+            let mut records = Vec::with_capacity(this.batch_size);
+            while records.len() < this.batch_size {
+                let mut rec = bam::Record::default();
+                if read_record_limited(&mut *reader, &mut rec, this.max_record_size)
+                    .map_err(read_error_to_py)?
+                    == 0
+                {
+                    break;
+                }
+                records.push(rec);
+            }
+            if records.is_empty() {
+                return Ok(None);
+            }
+            let columns: Vec<ArrayRef> = this
+                .columns
+                .iter()
+                .map(|c| c.column(&records, &this.header, Missing::Null))
+                .collect();
+            RecordBatch::try_new(this.schema.clone(), columns)
+                .map(Some)
+                .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+        })?;
+        batch.map(|batch| export_batch(py, batch)).transpose()
+    }
+}
@@ -0,0 +1,182 @@
+//! Per-record scalar columns shared by the Arrow and Parquet exports.
+//!
+//! Values mirror the `PyBamRecord` getters (`pos` is 1-based). How a
+//! missing value (`*` in SAM terms) is written is chosen per export with
+//! [`Missing`].
+
+use arrow_array::builder::{PrimitiveBuilder, StringBuilder};
+use arrow_array::types::{ArrowPrimitiveType, Int32Type, Int64Type, UInt16Type, UInt8Type};
+use arrow_array::ArrayRef;
+use arrow_schema::{DataType, Field};
+use noodles::{bam, sam};
+use std::sync::Arc;
+
+/// BAM の op コード順 (`Kind` の並びと同じ)
+const CIGAR_OPS: &[u8; 9] = b"MIDNSHP=X";
+
+/// 欠損値の書き方
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Missing {
+    /// Arrow の null (列は nullable)
+    Null,
+    /// getter と同じ番兵値: rid / pos は -1、mapq は 255、文字列は空 (列は non-null)
+    Sentinel,
+}
+
+/// 書き出せるスカラー列
+#[derive(Debug, Clone, Copy)]
+pub enum RecordColumn {
+    Qname,
+    Flag,
+    Rid,
+    Rname,
+    Pos,
+    Mapq,
+    Len,
+    CigarString,
+    Seq,
+    Tlen,
+}
+
+impl RecordColumn {
+    pub const NAMES: [&'static str; 10] = [
+        "qname",
+        "flag",
+        "rid",
+        "rname",
+        "pos",
+        "mapq",
+        "len",
+        "cigar_string",
+        "seq",
+        "tlen",
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "qname" => Some(RecordColumn::Qname),
+            "flag" => Some(RecordColumn::Flag),
+            "rid" => Some(RecordColumn::Rid),
+            "rname" => Some(RecordColumn::Rname),
+            "pos" => Some(RecordColumn::Pos),
+            "mapq" => Some(RecordColumn::Mapq),
+            "len" => Some(RecordColumn::Len),
+            "cigar_string" => Some(RecordColumn::CigarString),
+            "seq" => Some(RecordColumn::Seq),
+            "tlen" => Some(RecordColumn::Tlen),
+            _ => None,
+        }
+    }
+
+    pub fn field(self, missing: Missing) -> Field {
+        let (name, data_type, optional) = match self {
+            RecordColumn::Qname => ("qname", DataType::Utf8, true),
+            RecordColumn::Flag => ("flag", DataType::UInt16, false),
+            RecordColumn::Rid => ("rid", DataType::Int32, true),
+            RecordColumn::Rname => ("rname", DataType::Utf8, true),
+            RecordColumn::Pos => ("pos", DataType::Int64, true),
+            RecordColumn::Mapq => ("mapq", DataType::UInt8, true),
+            RecordColumn::Len => ("len", DataType::Int64, false),
+            RecordColumn::CigarString => ("cigar_string", DataType::Utf8, true),
+            RecordColumn::Seq => ("seq", DataType::Utf8, true),
+            RecordColumn::Tlen => ("tlen", DataType::Int32, false),
+        };
+        Field::new(name, data_type, optional && missing == Missing::Null)
+    }
+
+    pub fn column(
+        self,
+        records: &[bam::Record],
+        header: &sam::Header,
+        missing: Missing,
+    ) -> ArrayRef {
+        match self {
+            RecordColumn::Qname => text_column(records, missing, |rec| {
+                rec.name().map(|n| String::from_utf8_lossy(n).into_owned())
+            }),
+            RecordColumn::Flag => primitive_column::<UInt16Type>(records, missing, 0, |rec| {
+                Some(u16::from(rec.flags()))
+            }),
+            RecordColumn::Rid => {
+                primitive_column::<Int32Type>(records, missing, -1, |rec| {
+                    match rec.reference_sequence_id() {
+                        Some(Ok(rid)) => Some(rid as i32),
+                        _ => None,
+                    }
+                })
+            }
+            RecordColumn::Rname => {
+                text_column(records, missing, |rec| match rec.reference_sequence_id() {
+                    Some(Ok(rid)) => header
+                        .reference_sequences()
+                        .get_index(rid)
+                        .map(|(name, _)| String::from_utf8_lossy(name).into_owned()),
+                    _ => None,
+                })
+            }
+            RecordColumn::Pos => {
+                primitive_column::<Int64Type>(records, missing, -1, |rec| {
+                    match rec.alignment_start() {
+                        Some(Ok(pos)) => Some(usize::from(pos) as i64),
+                        _ => None,
+                    }
+                })
+            }
+            RecordColumn::Mapq => primitive_column::<UInt8Type>(records, missing, 255, |rec| {
+                rec.mapping_quality().map(u8::from)
+            }),
+            RecordColumn::Len => primitive_column::<Int64Type>(records, missing, 0, |rec| {
+                Some((rec.template_length() as i64).abs())
+            }),
+            RecordColumn::CigarString => text_column(records, missing, |rec| {
+                let cigar: String = rec
+                    .cigar()
+                    .iter()
+                    .filter_map(Result::ok)
+                    .map(|op| format!("{}{}", op.len(), CIGAR_OPS[op.kind() as usize] as char))
+                    .collect();
+                (!cigar.is_empty()).then_some(cigar)
+            }),
+            RecordColumn::Seq => text_column(records, missing, |rec| {
+                let seq: String = rec.sequence().iter().map(char::from).collect();
+                (!seq.is_empty()).then_some(seq)
+            }),
+            RecordColumn::Tlen => primitive_column::<Int32Type>(records, missing, 0, |rec| {
+                Some(rec.template_length())
+            }),
+        }
+    }
+}
+
+fn primitive_column<T: ArrowPrimitiveType>(
+    records: &[bam::Record],
+    missing: Missing,
+    sentinel: T::Native,
+    value: impl Fn(&bam::Record) -> Option<T::Native>,
+) -> ArrayRef {
+    let mut b = PrimitiveBuilder::<T>::with_capacity(records.len());
+    for rec in records {
+        match (value(rec), missing) {
+            (Some(v), _) => b.append_value(v),
+            (None, Missing::Null) => b.append_null(),
+            (None, Missing::Sentinel) => b.append_value(sentinel),
+        }
+    }
+    Arc::new(b.finish())
+}
+
+fn text_column(
+    records: &[bam::Record],
+    missing: Missing,
+    value: impl Fn(&bam::Record) -> Option<String>,
+) -> ArrayRef {
+    let mut b = StringBuilder::new();
+    for rec in records {
+        match (value(rec), missing) {
+            (Some(v), _) => b.append_value(v),
+            (None, Missing::Null) => b.append_null(),
+            (None, Missing::Sentinel) => b.append_value(""),
+        }
+    }
+    Arc::new(b.finish())
+}
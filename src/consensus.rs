@@ -0,0 +1,223 @@
+//! Consensus building from reads that share a UMI and position, and
+//! merging of overlapping read pairs into one fragment sequence.
+
+use noodles::bam;
+use noodles::sam;
+use noodles::sam::alignment::record::cigar::op::{Kind, Op};
+use noodles::sam::alignment::record::data::field::Tag;
+use noodles::sam::alignment::record_buf::data::field::Value;
+use noodles::sam::alignment::record_buf::{QualityScores, Sequence};
+use pyo3::prelude::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::alignment;
+use crate::record::PyBamRecord;
+
+/// Highest phred score emitted for a consensus base.
+const MAX_CONSENSUS_QUAL: u32 = 93;
+/// Phred score for consensus bases with no net support.
+const MIN_CONSENSUS_QUAL: u32 = 2;
+
+/// Collapse duplicate reads into a single consensus read.
+///
+/// Bases are voted column-wise by read offset, weighted by base quality.
+/// The consensus quality is the summed quality of agreeing bases minus that
+/// of disagreeing bases, clamped to `2..=93`. Position, CIGAR, flags and
+/// tags come from the first read; a `cD` tag records the number of reads.
+#[pyfunction]
+pub fn build_consensus_read(records: Vec<PyRef<PyBamRecord>>) -> PyResult<PyBamRecord> {
+    let Some(template) = records.first() else {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "records must not be empty",
+        ));
+    };
+
+    let reads: Vec<(Vec<u8>, Vec<u8>)> = records
+        .iter()
+        .map(|r| {
+            let rec = r.record();
+            (
+                rec.sequence().iter().collect(),
+                rec.quality_scores().as_ref().to_vec(),
+            )
+        })
+        .collect();
+    let len = reads[0].0.len();
+
+    let mut bases = Vec::with_capacity(len);
+    let mut quals = Vec::with_capacity(len);
+    for i in 0..len {
+        // A, C, G, T, N ごとの品質スコア合計
+        let mut weights = [0u32; 5];
+        for (seq, qual) in &reads {
+            let Some(&base) = seq.get(i) else { continue };
+            let slot = match base.to_ascii_uppercase() {
+                b'A' => 0,
+                b'C' => 1,
+                b'G' => 2,
+                b'T' => 3,
+                _ => 4,
+            };
+            weights[slot] += u32::from(qual.get(i).copied().unwrap_or(0));
+        }
+        let (best, &best_weight) = weights[..4]
+            .iter()
+            .enumerate()
+            .max_by_key(|&(_, w)| *w)
+            .unwrap();
+        let others: u32 = weights.iter().sum::<u32>() - best_weight;
+        if best_weight == 0 {
+            bases.push(b'N');
+            quals.push(MIN_CONSENSUS_QUAL as u8);
+            continue;
+        }
+        bases.push(b"ACGT"[best]);
+        let q = best_weight
+            .saturating_sub(others)
+            .clamp(MIN_CONSENSUS_QUAL, MAX_CONSENSUS_QUAL);
+        quals.push(q as u8);
+    }
+
+    let mut buf = template
+        .to_record_buf()
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+    *buf.sequence_mut() = Sequence::from(bases);
+    *buf.quality_scores_mut() = QualityScores::from(quals);
+    buf.data_mut()
+        .insert(Tag::new(b'c', b'D'), Value::from(records.len() as i32));
+
+    let header = template
+        .header()
+        .cloned()
+        .unwrap_or_else(|| Arc::new(sam::Header::default()));
+    PyBamRecord::from_record_buf(header, &buf)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+}
+
+/// `(base, quality)` of a single read base.
+type Base = (u8, u8);
+
+/// A read laid out on the reference.
+///
+/// Each reference position maps to the aligned base (`None` for deletions
+/// and skips) plus any bases inserted right after it.
+struct ReadLayout {
+    start: i64,
+    end: i64,
+    left_clip: Vec<Base>,
+    columns: BTreeMap<i64, (Option<Base>, Vec<Base>)>,
+    right_clip: Vec<Base>,
+}
+
+impl ReadLayout {
+    fn new(rec: &bam::Record) -> PyResult<Self> {
+        let (start, end) = alignment::reference_span(rec).ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>("both reads must be mapped")
+        })?;
+        let bases: Vec<Base> = rec
+            .sequence()
+            .iter()
+            .zip(rec.quality_scores().as_ref().iter().copied())
+            .collect();
+
+        let mut layout = ReadLayout {
+            start,
+            end,
+            left_clip: Vec::new(),
+            columns: BTreeMap::new(),
+            right_clip: Vec::new(),
+        };
+        let mut q = 0usize;
+        let mut r = start;
+        let ops: Vec<Op> = rec.cigar().iter().filter_map(Result::ok).collect();
+        for op in ops {
+            let len = op.len();
+            let end_q = (q + len).min(bases.len());
+            match op.kind() {
+                Kind::Match | Kind::SequenceMatch | Kind::SequenceMismatch => {
+                    for (i, &base) in bases[q.min(end_q)..end_q].iter().enumerate() {
+                        layout
+                            .columns
+                            .insert(r + i as i64, (Some(base), Vec::new()));
+                    }
+                    q += len;
+                    r += len as i64;
+                }
+                Kind::Deletion | Kind::Skip => {
+                    for i in 0..len as i64 {
+                        layout.columns.insert(r + i, (None, Vec::new()));
+                    }
+                    r += len as i64;
+                }
+                Kind::Insertion | Kind::SoftClip => {
+                    let segment = &bases[q.min(end_q)..end_q];
+                    match layout.columns.last_entry() {
+                        None => layout.left_clip.extend_from_slice(segment),
+                        Some(_) if op.kind() == Kind::SoftClip => {
+                            layout.right_clip.extend_from_slice(segment)
+                        }
+                        Some(mut last) => last.get_mut().1.extend_from_slice(segment),
+                    }
+                    q += len;
+                }
+                Kind::HardClip | Kind::Pad => {}
+            }
+        }
+        Ok(layout)
+    }
+}
+
+/// Merge the two mates of a pair into the full fragment sequence.
+///
+/// Reads are laid out on the reference; where both cover a position the
+/// base (and any insertion after it) of the higher-quality mate wins, ties
+/// going to `read1`. Positions covered by neither mate are filled with
+/// `N` at quality 0. The leading soft clip of the leftmost mate and the
+/// trailing soft clip of the rightmost mate are kept. Returns
+/// `(sequence, qualities)`.
+#[pyfunction]
+pub fn merge_pair(
+    read1: PyRef<PyBamRecord>,
+    read2: PyRef<PyBamRecord>,
+) -> PyResult<(String, Vec<usize>)> {
+    let (rec1, rec2) = (read1.record(), read2.record());
+    if rec1.reference_sequence_id().and_then(Result::ok)
+        != rec2.reference_sequence_id().and_then(Result::ok)
+    {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+            "reads are on different reference sequences",
+        ));
+    }
+    let a = ReadLayout::new(rec1)?;
+    let b = ReadLayout::new(rec2)?;
+
+    let mut merged: Vec<Base> = Vec::new();
+    let leftmost = if b.start < a.start { &b } else { &a };
+    merged.extend_from_slice(&leftmost.left_clip);
+    for pos in a.start.min(b.start)..a.end.max(b.end) {
+        let column = match (a.columns.get(&pos), b.columns.get(&pos)) {
+            (Some(x), Some(y)) => {
+                let qual = |c: &(Option<Base>, Vec<Base>)| c.0.map(|(_, q)| q);
+                if qual(y) > qual(x) {
+                    y
+                } else {
+                    x
+                }
+            }
+            (Some(x), None) | (None, Some(x)) => x,
+            (None, None) => {
+                merged.push((b'N', 0));
+                continue;
+            }
+        };
+        merged.extend(column.0);
+        merged.extend_from_slice(&column.1);
+    }
+    let rightmost = if b.end > a.end { &b } else { &a };
+    merged.extend_from_slice(&rightmost.right_clip);
+
+    let seq = merged.iter().map(|&(base, _)| base as char).collect();
+    let qual = merged.iter().map(|&(_, q)| q as usize).collect();
+    Ok((seq, qual))
+}
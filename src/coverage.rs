@@ -0,0 +1,66 @@
+//! Streaming depth computation over coordinate-sorted alignments.
+//!
+//! Depth changes are kept as a sparse event map and flushed as soon as
+//! every read that could still touch them has been seen, so memory is
+//! bounded by the span of overlapping reads rather than the contig length.
+
+use noodles::sam::alignment::record::Flags;
+use std::collections::BTreeMap;
+
+/// Whether a record contributes to depth (mirrors `samtools depth`
+/// defaults: unmapped, secondary, QC-fail and duplicate reads are skipped).
+pub fn counts_toward_depth(flags: Flags) -> bool {
+    !(flags.is_unmapped() || flags.is_secondary() || flags.is_qc_fail() || flags.is_duplicate())
+}
+
+/// Sweep-line depth accumulator for a single reference sequence.
+#[derive(Debug, Default)]
+pub struct DepthSweep {
+    events: BTreeMap<i64, i64>,
+    depth: i64,
+    last_pos: i64,
+}
+
+impl DepthSweep {
+    /// Add a covered interval (0-based, half-open).
+    pub fn add_block(&mut self, start: i64, end: i64) {
+        if end <= start {
+            return;
+        }
+        *self.events.entry(start).or_default() += 1;
+        *self.events.entry(end).or_default() -= 1;
+    }
+
+    /// Emit constant-depth segments `(start, end, depth)` that end at or
+    /// before `pos`. Call with each read's start once it is known no later
+    /// read begins before it.
+    pub fn advance<F>(&mut self, pos: i64, mut f: F)
+    where
+        F: FnMut(i64, i64, i64),
+    {
+        while let Some((&at, &delta)) = self.events.first_key_value() {
+            if at > pos {
+                break;
+            }
+            if at > self.last_pos {
+                f(self.last_pos, at, self.depth);
+            }
+            self.depth += delta;
+            self.last_pos = at;
+            self.events.pop_first();
+        }
+    }
+
+    /// Flush every remaining segment.
+    pub fn finish<F>(&mut self, f: F)
+    where
+        F: FnMut(i64, i64, i64),
+    {
+        self.advance(i64::MAX, f);
+    }
+
+    /// Position up to which depth has been finalised.
+    pub fn flushed_to(&self) -> i64 {
+        self.last_pos
+    }
+}
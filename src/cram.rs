@@ -0,0 +1,143 @@
+//! CRAM input yielding the same record objects as `BamReader`.
+//!
+//! CRAM records are decoded on a background thread (the noodles record
+//! iterator borrows the reader, so it cannot be stored in the pyclass),
+//! re-encoded as BAM records and handed over through a bounded channel.
+
+use noodles::{bam, cram, fasta, sam};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::io;
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::record::{encode_bam_record, PyBamRecord};
+use crate::tag_value::TagConversion;
+
+/// デコード済みで Python 側に渡していないレコードの上限
+const CHANNEL_CAPACITY: usize = 4096;
+
+fn io_err(e: io::Error) -> PyErr {
+    PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string())
+}
+
+/// Chunked iteration over a CRAM file.
+///
+/// `reference_path` is an indexed FASTA (`.fai` next to it) used to
+/// reconstruct read sequences. Records are `PyBamRecord`s, so `seq` and
+/// the other getters work as for BAM input.
+#[pyclass]
+pub struct CramReader {
+    header: Arc<sam::Header>,
+    chunk_size: usize,
+    tag_conversion: TagConversion,
+    /// デコードスレッドからの受信側 (終端で None)
+    records: Mutex<Option<Receiver<io::Result<bam::Record>>>>,
+}
+
+#[pymethods]
+impl CramReader {
+    #[new]
+    #[pyo3(signature = (path, reference_path, chunk_size=None, tag_conversion="python"))]
+    fn new(
+        path: &str,
+        reference_path: &str,
+        chunk_size: Option<usize>,
+        tag_conversion: &str,
+    ) -> PyResult<Self> {
+        let tag_conversion = tag_conversion
+            .parse::<TagConversion>()
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+        let repository = fasta::io::indexed_reader::Builder::default()
+            .build_from_path(reference_path)
+            .map(fasta::repository::adapters::IndexedReader::new)
+            .map(fasta::Repository::new)
+            .map_err(io_err)?;
+        let mut reader = cram::io::reader::Builder::default()
+            .set_reference_sequence_repository(repository)
+            .build_from_path(path)
+            .map_err(io_err)?;
+        let header = Arc::new(reader.read_header().map_err(io_err)?);
+
+        let (tx, rx) = mpsc::sync_channel(CHANNEL_CAPACITY);
+        let thread_header = header.clone();
+        thread::spawn(move || {
+            for result in reader.records(&thread_header) {
+                let rec = result.and_then(|rec| encode_bam_record(&thread_header, &rec));
+                let failed = rec.is_err();
+                // 受信側が破棄されたら終了
+                if tx.send(rec).is_err() || failed {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            header,
+            chunk_size: chunk_size.unwrap_or(1),
+            tag_conversion,
+            records: Mutex::new(Some(rx)),
+        })
+    }
+
+    #[getter]
+    fn _header<'py>(&self, py: Python<'py>) -> PyResult<Py<PyBytes>> {
+        let mut buf = Vec::new();
+        let mut w = sam::io::Writer::new(&mut buf);
+        w.write_header(&self.header).map_err(io_err)?;
+        Ok(PyBytes::new(py, &buf).into())
+    }
+
+    fn __enter__(slf: PyRefMut<'_, Self>) -> PyRefMut<'_, Self> {
+        slf
+    }
+
+    fn __exit__(&self, _exc_type: PyObject, _exc_val: PyObject, _trace: PyObject) -> PyResult<()> {
+        // 受信側を閉じるとデコードスレッドも止まる
+        self.records.lock().unwrap().take();
+        Ok(())
+    }
+
+    fn __iter__(slf: PyRefMut<'_, Self>) -> PyRefMut<'_, Self> {
+        slf
+    }
+
+    /// chunk_size ごとにレコードを返す
+    fn __next__(&self, py: Python<'_>) -> PyResult<Option<Vec<PyBamRecord>>> {
+        let n = self.chunk_size;
+        let recs = py.allow_threads(|| -> PyResult<Vec<bam::Record>> {
+            let mut guard = self.records.lock().unwrap();
+            let mut v = Vec::with_capacity(n);
+            while v.len() < n {
+                let Some(rx) = guard.as_ref() else {
+                    break;
+                };
+                match rx.recv() {
+                    Ok(Ok(rec)) => v.push(rec),
+                    Ok(Err(e)) => {
+                        guard.take();
+                        return Err(io_err(e));
+                    }
+                    // スレッドが読み終えた
+                    Err(_) => {
+                        guard.take();
+                    }
+                }
+            }
+            Ok(v)
+        })?;
+        if recs.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(
+            recs.into_iter()
+                .map(|rec| {
+                    PyBamRecord::from_record(rec)
+                        .with_header(self.header.clone())
+                        .with_tag_conversion(self.tag_conversion)
+                })
+                .collect(),
+        ))
+    }
+}
@@ -0,0 +1,162 @@
+//! UMI-aware duplicate marking / removal.
+
+use noodles::sam::alignment::io::Write as _;
+use noodles::sam::alignment::record::cigar::op::Op;
+use noodles::sam::alignment::record::data::field::{Tag, Value as BamValue};
+use noodles::sam::alignment::record::Flags;
+use noodles::sam::alignment::RecordBuf;
+use noodles::{bam, sam};
+use pyo3::prelude::*;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+
+use crate::alignment;
+use crate::iterator::{read_error_to_py, read_record_limited, DEFAULT_MAX_RECORD_SIZE};
+use crate::record_override;
+
+/// Where the UMI of a read comes from.
+enum UmiSource {
+    Tag(Tag),
+    /// 名前の最後の `_` 以降 (umi_tools の既定形式)
+    Qname,
+}
+
+impl UmiSource {
+    fn umi(&self, rec: &bam::Record) -> Option<Vec<u8>> {
+        match self {
+            UmiSource::Tag(tag) => match rec.data().get(tag) {
+                Some(Ok(BamValue::String(s))) => Some(s.to_vec()),
+                _ => None,
+            },
+            UmiSource::Qname => {
+                let name: &[u8] = rec.name()?.as_ref();
+                let sep = name.iter().rposition(|&b| b == b'_')?;
+                Some(name[sep + 1..].to_vec())
+            }
+        }
+    }
+}
+
+/// (参照 ID, クリップ込みの 5' 位置, 逆鎖か, UMI)
+type DedupKey = (usize, i64, bool, Vec<u8>);
+
+/// Grouping key of `rec`, or `None` for reads that are never duplicates
+/// (unmapped, secondary, supplementary or without a UMI).
+fn dedup_key(rec: &bam::Record, source: &UmiSource) -> Option<DedupKey> {
+    let flags = rec.flags();
+    if flags.is_unmapped() || flags.is_secondary() || flags.is_supplementary() {
+        return None;
+    }
+    let rid = rec.reference_sequence_id()?.ok()?;
+    let (start, end) = alignment::reference_span(rec)?;
+    let ops: Vec<Op> = rec.cigar().iter().filter_map(Result::ok).collect();
+    let (left, right) = alignment::terminal_clips(&ops);
+    let reverse = flags.is_reverse_complemented();
+    let five_prime = if reverse {
+        end + right as i64
+    } else {
+        start - left as i64
+    };
+    Some((rid, five_prime, reverse, source.umi(rec)?))
+}
+
+fn base_quality_sum(rec: &bam::Record) -> u64 {
+    rec.quality_scores()
+        .as_ref()
+        .iter()
+        .filter(|&&q| q != 0xff)
+        .map(|&q| u64::from(q))
+        .sum()
+}
+
+fn run(
+    input_path: &str,
+    output_path: &str,
+    source: &UmiSource,
+    remove: bool,
+    max_record_size: usize,
+) -> io::Result<(u64, u64)> {
+    // ── 1. グループごとに代表リード (品質合計が最大、同点なら先頭) を決める
+    let mut reader = File::open(input_path).map(bam::io::Reader::new)?;
+    let header: sam::Header = reader.read_header()?;
+    let mut best: HashMap<DedupKey, (u64, u64)> = HashMap::new();
+    let mut rec = bam::Record::default();
+    let mut index = 0u64;
+    while read_record_limited(&mut reader, &mut rec, max_record_size)? != 0 {
+        if let Some(key) = dedup_key(&rec, source) {
+            let score = base_quality_sum(&rec);
+            best.entry(key)
+                .and_modify(|e| {
+                    if score > e.1 {
+                        *e = (index, score);
+                    }
+                })
+                .or_insert((index, score));
+        }
+        index += 1;
+    }
+
+    // ── 2. 代表以外を除去またはフラグ付けして書き出す
+    let mut reader = File::open(input_path).map(bam::io::Reader::new)?;
+    reader.read_header()?;
+    let mut writer = File::create(output_path).map(bam::io::Writer::new)?;
+    writer.write_header(&header)?;
+    let mut index = 0u64;
+    let mut written = 0u64;
+    let mut duplicates = 0u64;
+    while read_record_limited(&mut reader, &mut rec, max_record_size)? != 0 {
+        let is_duplicate = dedup_key(&rec, source)
+            .and_then(|key| best.get(&key))
+            .is_some_and(|&(keep, _)| keep != index);
+        index += 1;
+        if is_duplicate {
+            duplicates += 1;
+            if remove {
+                continue;
+            }
+            let mut buf = RecordBuf::try_from_alignment_record(&header, &rec)?;
+            buf.flags_mut().insert(Flags::DUPLICATE);
+            writer.write_alignment_record(&header, &buf)?;
+        } else {
+            writer.write_alignment_record(&header, &rec)?;
+        }
+        written += 1;
+    }
+    writer.try_finish()?;
+    Ok((written, duplicates))
+}
+
+/// Collapse reads sharing `(5' position, strand, UMI)`.
+///
+/// The 5' position includes clipped bases, so reads from the same molecule
+/// group together regardless of clipping. `umi_source` is a tag name
+/// (default `RX`) or `"qname"` for a UMI appended to the read name after
+/// the last `_`. Within a group the read with the highest summed base
+/// quality is kept; the others are dropped when `remove` is true and
+/// flagged as duplicates (0x400) otherwise. Unmapped, secondary and
+/// supplementary reads and reads without a UMI are passed through.
+/// Returns `(records_written, duplicates)`. A record larger than
+/// `max_record_size` raises `ValueError`.
+#[pyfunction]
+#[pyo3(signature = (input_path, output_path, umi_source="RX", remove=true, max_record_size=None))]
+pub fn deduplicate(
+    py: Python<'_>,
+    input_path: &str,
+    output_path: &str,
+    umi_source: &str,
+    remove: bool,
+    max_record_size: Option<usize>,
+) -> PyResult<(u64, u64)> {
+    let source = if umi_source == "qname" {
+        UmiSource::Qname
+    } else {
+        UmiSource::Tag(
+            record_override::convert_string_to_tag(umi_source.to_string())
+                .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?,
+        )
+    };
+    let max_record_size = max_record_size.unwrap_or(DEFAULT_MAX_RECORD_SIZE);
+    py.allow_threads(|| run(input_path, output_path, &source, remove, max_record_size))
+        .map_err(read_error_to_py)
+}
@@ -0,0 +1,86 @@
+//! Reproducible, pair-aware random subsampling.
+
+use noodles::bam;
+use noodles::sam::alignment::io::Write as _;
+use pyo3::prelude::*;
+use std::fs::File;
+use std::io;
+
+use crate::iterator::{read_error_to_py, read_record_limited, DEFAULT_MAX_RECORD_SIZE};
+use crate::record::fnv1a64;
+
+/// `rec` を残すかを (seed, 名前[, 入力順]) のハッシュで決める
+fn keep(rec: &bam::Record, index: u64, seed: u64, fraction: f64, keep_pairs: bool) -> bool {
+    let name = rec.name().unwrap_or_default();
+    let hash = if keep_pairs {
+        fnv1a64(&[&seed.to_le_bytes(), name])
+    } else {
+        fnv1a64(&[&seed.to_le_bytes(), name, &index.to_le_bytes()])
+    };
+    (hash as f64 / u64::MAX as f64) < fraction
+}
+
+fn run(
+    input_path: &str,
+    output_path: &str,
+    fraction: f64,
+    seed: u64,
+    keep_pairs: bool,
+    max_record_size: usize,
+) -> io::Result<u64> {
+    let mut reader = File::open(input_path).map(bam::io::Reader::new)?;
+    let header = reader.read_header()?;
+    let mut writer = File::create(output_path).map(bam::io::Writer::new)?;
+    writer.write_header(&header)?;
+    let mut rec = bam::Record::default();
+    let mut index = 0u64;
+    let mut written = 0u64;
+    while read_record_limited(&mut reader, &mut rec, max_record_size)? != 0 {
+        if keep(&rec, index, seed, fraction, keep_pairs) {
+            writer.write_alignment_record(&header, &rec)?;
+            written += 1;
+        }
+        index += 1;
+    }
+    writer.try_finish()?;
+    Ok(written)
+}
+
+/// Write a random `fraction` of `input_path` to `output_path`
+/// (`samtools view -s`).
+///
+/// Selection hashes the read name with `seed`, so the same seed always
+/// gives the same subset. With `keep_pairs` every record sharing a name
+/// (both mates, secondary and supplementary alignments) is kept or dropped
+/// together. Returns the number of records written. A record larger than
+/// `max_record_size` raises `ValueError`.
+#[pyfunction]
+#[pyo3(signature = (input_path, output_path, fraction, seed=0, keep_pairs=true, max_record_size=None))]
+pub fn downsample_bam(
+    py: Python<'_>,
+    input_path: &str,
+    output_path: &str,
+    fraction: f64,
+    seed: u64,
+    keep_pairs: bool,
+    max_record_size: Option<usize>,
+) -> PyResult<u64> {
+    if !(0.0..=1.0).contains(&fraction) {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "fraction must be between 0 and 1, got {}",
+            fraction
+        )));
+    }
+    let max_record_size = max_record_size.unwrap_or(DEFAULT_MAX_RECORD_SIZE);
+    py.allow_threads(|| {
+        run(
+            input_path,
+            output_path,
+            fraction,
+            seed,
+            keep_pairs,
+            max_record_size,
+        )
+    })
+    .map_err(read_error_to_py)
+}
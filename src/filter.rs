@@ -0,0 +1,29 @@
+//! Record filters applied inside the reader before records reach Python.
+
+use noodles::bam;
+
+/// Criteria a record has to meet to be yielded by `BamReader`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RecordFilter {
+    pub min_query_length: Option<usize>,
+    pub max_query_length: Option<usize>,
+    /// MAPQ 不明 (255) は 255 として比較する
+    pub min_mapq: Option<u8>,
+    /// いずれかのビットが立っていれば除外
+    pub exclude_flags: Option<u16>,
+    /// すべてのビットが立っている必要がある
+    pub require_flags: Option<u16>,
+}
+
+impl RecordFilter {
+    pub fn accepts(&self, rec: &bam::Record) -> bool {
+        let len = rec.sequence().len();
+        let mapq = rec.mapping_quality().map(u8::from).unwrap_or(255);
+        let flags = u16::from(rec.flags());
+        self.min_query_length.is_none_or(|min| len >= min)
+            && self.max_query_length.is_none_or(|max| len <= max)
+            && self.min_mapq.is_none_or(|min| mapq >= min)
+            && self.exclude_flags.is_none_or(|mask| flags & mask == 0)
+            && self.require_flags.is_none_or(|mask| flags & mask == mask)
+    }
+}
@@ -0,0 +1,154 @@
+//! Genomic feature sets for annotating reads.
+
+use pyo3::prelude::*;
+use pyo3::types::PyTuple;
+use std::collections::HashMap;
+
+use crate::alignment;
+use crate::record::PyBamRecord;
+
+#[derive(Debug, Clone)]
+struct Feature {
+    start: i64,
+    end: i64,
+    label: String,
+    reverse: bool,
+}
+
+/// 1 本の参照配列上の feature (開始位置順) と、先頭からの end の最大値
+#[derive(Debug, Default)]
+struct ContigFeatures {
+    features: Vec<Feature>,
+    /// `max_end[i]` = `features[..=i]` の中で end が最大のもののインデックス
+    max_end: Vec<usize>,
+}
+
+impl ContigFeatures {
+    fn build(mut features: Vec<Feature>) -> Self {
+        features.sort_by_key(|f| (f.start, f.end));
+        let mut max_end: Vec<usize> = Vec::with_capacity(features.len());
+        for (i, f) in features.iter().enumerate() {
+            let best = match max_end.last() {
+                Some(&j) if features[j].end >= f.end => j,
+                _ => i,
+            };
+            max_end.push(best);
+        }
+        Self { features, max_end }
+    }
+
+    /// `pos` に最も近い feature と、`pos` からその feature までの距離
+    /// (0 は feature 内)
+    fn nearest(&self, pos: i64) -> Option<(&Feature, i64)> {
+        let idx = self.features.partition_point(|f| f.start <= pos);
+        let left = idx
+            .checked_sub(1)
+            .map(|i| &self.features[self.max_end[i]])
+            .map(|f| (f, (pos - (f.end - 1)).max(0)));
+        let right = self.features.get(idx).map(|f| (f, f.start - pos));
+        match (left, right) {
+            (Some(l), Some(r)) if r.1 < l.1 => Some(r),
+            (Some(l), _) => Some(l),
+            (None, r) => r,
+        }
+    }
+}
+
+/// Labelled genomic intervals (0-based, half-open) grouped by reference.
+#[pyclass]
+pub struct IntervalSet {
+    contigs: HashMap<String, ContigFeatures>,
+}
+
+#[pymethods]
+impl IntervalSet {
+    /// `features` holds `(reference_name, start, end, label)` tuples with
+    /// an optional fifth `strand` element (`"+"` or `"-"`, default `"+"`).
+    #[new]
+    fn new(features: Vec<Bound<'_, PyTuple>>) -> PyResult<Self> {
+        let mut by_contig: HashMap<String, Vec<Feature>> = HashMap::new();
+        for t in features {
+            if !(4..=5).contains(&t.len()) {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                    "features must be (reference_name, start, end, label[, strand]) tuples",
+                ));
+            }
+            let name: String = t.get_item(0)?.extract()?;
+            let start: i64 = t.get_item(1)?.extract()?;
+            let end: i64 = t.get_item(2)?.extract()?;
+            let label: String = t.get_item(3)?.extract()?;
+            let reverse = if t.len() == 5 {
+                let strand: String = t.get_item(4)?.extract()?;
+                match strand.as_str() {
+                    "+" => false,
+                    "-" => true,
+                    _ => {
+                        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                            "strand must be '+' or '-', got '{}'",
+                            strand
+                        )))
+                    }
+                }
+            } else {
+                false
+            };
+            if start < 0 || end <= start {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "invalid interval: {}-{}",
+                    start, end
+                )));
+            }
+            by_contig.entry(name).or_default().push(Feature {
+                start,
+                end,
+                label,
+                reverse,
+            });
+        }
+        Ok(Self {
+            contigs: by_contig
+                .into_iter()
+                .map(|(name, features)| (name, ContigFeatures::build(features)))
+                .collect(),
+        })
+    }
+
+    fn __len__(&self) -> usize {
+        self.contigs.values().map(|c| c.features.len()).sum()
+    }
+
+    /// リードの 5' 末端に最も近い feature を返す
+    ///
+    /// Returns `(label, distance)` or `None` for unmapped reads and reads on
+    /// references without features. `distance` is measured from the read's
+    /// 5' base to the nearest edge of the feature, 0 inside it, and is
+    /// negative when the read lies upstream relative to the feature strand.
+    /// The record needs a header (records from `BamReader` carry one).
+    fn nearest_feature(&self, record: PyRef<'_, PyBamRecord>) -> PyResult<Option<(String, i64)>> {
+        let Some((start, end)) = alignment::reference_span(record.record()) else {
+            return Ok(None);
+        };
+        let Some(name) = record.reference_name() else {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "record has no header to resolve its reference name",
+            ));
+        };
+        let Some(contig) = self.contigs.get(&name) else {
+            return Ok(None);
+        };
+        let five_prime = if record.record().flags().is_reverse_complemented() {
+            end - 1
+        } else {
+            start
+        };
+        Ok(contig.nearest(five_prime).map(|(f, dist)| {
+            let upstream = if f.reverse {
+                five_prime >= f.end
+            } else {
+                five_prime < f.start
+            };
+            let signed = if upstream { -dist } else { dist };
+            (f.label.clone(), signed)
+        }))
+    }
+}
@@ -1,21 +1,208 @@
-use noodles::bgzf;
 use noodles::core::region::Region;
-use noodles::{bam, sam};
+use noodles::core::Position;
+use noodles::csi::BinningIndex;
+use noodles::sam::alignment::record::cigar::op::{Kind, Op};
+use noodles::sam::alignment::record::data::field::{Tag, Value as BamValue};
+use noodles::{bam, bgzf, sam};
+use numpy::{PyArray1, PyArray3, PyArrayMethods};
 use pyo3::prelude::*;
-use pyo3::types::PyBytes;
-use std::fs::File;
+use pyo3::types::{PyBytes, PyDict, PyTuple};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{self, BufRead, Write};
 use std::str::FromStr;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, OnceLock};
 
-use crate::record::PyBamRecord;
+use crate::alignment;
+use crate::arrow_export::ArrowBatchIterator;
+use crate::columns::RecordColumn;
+use crate::coverage::{self, DepthSweep};
+use crate::filter::RecordFilter;
+use crate::modifications;
+use crate::parquet_export::{self, TagKind, TagScalar};
+use crate::record::{iupac_complement, PyBamRecord};
+use crate::record_override;
+use crate::records::RecordIterator;
+use crate::region_query::{RegionIterator, RegionQuery};
+use crate::sam_lines::SamLinesIterator;
+use crate::sliding::SlidingIterator;
+use crate::sort_window::SortWindowIterator;
+use crate::source::{BamIndex, BamStream, Location};
+use crate::struct_row::StructRow;
+use crate::tag_value::TagConversion;
+
+/// 0-based half-open 座標から noodles の Region を作る
+fn make_region(reference_name: &str, start: i64, end: i64) -> PyResult<Region> {
+    if start < 0 || end <= start {
+        return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "invalid interval: {}-{}",
+            start, end
+        )));
+    }
+    let start = Position::try_from(start as usize + 1)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+    let end = Position::try_from(end as usize)
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+    Ok(Region::new(reference_name, start..=end))
+}
+
+/// `max_record_size` の既定値 (8 MiB)
+pub(crate) const DEFAULT_MAX_RECORD_SIZE: usize = 8 << 20;
+
+/// `allele_depths` で 1 回のクエリにまとめる座位間の最大距離
+const LOCUS_MERGE_GAP: i64 = 10_000;
+
+/// `read_clusters` の 1 クラスタ: `(records, start, end)`
+type ReadCluster = (Vec<Py<PyBamRecord>>, i64, i64);
+
+/// 位置ごとの 2 系列のカウント (`indel_profile` / `methylation_profile`)
+type CountArrays<'py> = (Bound<'py, PyArray1<i32>>, Bound<'py, PyArray1<i32>>);
+
+/// 宣言されたブロックサイズが上限を超えるレコード
+#[derive(Debug)]
+struct RecordTooLarge {
+    size: usize,
+    max: usize,
+}
+
+impl fmt::Display for RecordTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "record block size {} exceeds max_record_size {}",
+            self.size, self.max
+        )
+    }
+}
+
+impl std::error::Error for RecordTooLarge {}
+
+fn check_record_size(size: usize, max: usize) -> io::Result<()> {
+    if size > max {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            RecordTooLarge { size, max },
+        ));
+    }
+    Ok(())
+}
+
+/// 宣言されたブロックサイズを確かめてからレコードを読む
+///
+/// The size prefix is peeked from the decompressed bgzf buffer so an
+/// implausible size is rejected before noodles allocates for it. When the
+/// prefix straddles a bgzf block the record is read here and decoded
+/// from memory instead.
+pub(crate) fn read_record_limited<R: BufRead>(
+    reader: &mut bam::io::Reader<R>,
+    rec: &mut bam::Record,
+    max_record_size: usize,
+) -> io::Result<usize> {
+    let buf = reader.get_mut().fill_buf()?;
+    if buf.is_empty() {
+        return Ok(0);
+    }
+    if let Some(prefix) = buf.get(..4) {
+        let size = u32::from_le_bytes(prefix.try_into().unwrap()) as usize;
+        check_record_size(size, max_record_size)?;
+        return reader.read_record(rec);
+    }
+
+    // サイズがブロック境界をまたぐ場合
+    let mut prefix = [0u8; 4];
+    reader.get_mut().read_exact(&mut prefix)?;
+    let size = u32::from_le_bytes(prefix) as usize;
+    check_record_size(size, max_record_size)?;
+    let mut raw = vec![0u8; 4 + size];
+    raw[..4].copy_from_slice(&prefix);
+    reader.get_mut().read_exact(&mut raw[4..])?;
+    bam::io::Reader::from(&raw[..]).read_record(rec)
+}
+
+fn is_record_too_large(e: &io::Error) -> bool {
+    e.get_ref()
+        .is_some_and(|inner| inner.is::<RecordTooLarge>())
+}
+
+/// 読み出しエラーを Python 例外に変換する (サイズ超過は ValueError)
+pub(crate) fn read_error_to_py(e: io::Error) -> PyErr {
+    if is_record_too_large(&e) {
+        PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string())
+    } else {
+        PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string())
+    }
+}
+
+/// インデックスのメタデータ (疑似ビン) から参照配列ごとの
+/// `(mapped, unmapped)` と配置なしリード数を取り出す
+///
+/// Returns `None` when the index lacks the unplaced count, i.e. was
+/// written without metadata. References the index omits, or that have no
+/// metadata because no reads were placed on them, count as zero.
+fn index_counts(index: &dyn BinningIndex, n_refs: usize) -> Option<(Vec<(u64, u64)>, u64)> {
+    let unplaced = index.unplaced_unmapped_record_count()?;
+    let mut counts = vec![(0u64, 0u64); n_refs];
+    for (slot, rs) in counts.iter_mut().zip(index.reference_sequences()) {
+        if let Some(metadata) = rs.metadata() {
+            *slot = (
+                metadata.mapped_record_count(),
+                metadata.unmapped_record_count(),
+            );
+        }
+    }
+    Some((counts, unplaced))
+}
+
+/// Picard の Lander-Waterman 式でライブラリサイズを推定する
+///
+/// `examined` molecules were seen of which `unique` were distinct. Returns
+/// `None` when the estimate is undefined (no duplicates or no reads).
+fn estimate_library_size(examined: u64, unique: u64) -> Option<f64> {
+    let (n, c) = (examined as f64, unique as f64);
+    let f = |x: f64| c / x - 1.0 + (-n / x).exp();
+    if unique == 0 || unique >= examined || f(c) < 0.0 {
+        return None;
+    }
+    let (mut lo, mut hi) = (1.0f64, 100.0f64);
+    while f(hi * c) > 0.0 {
+        hi *= 10.0;
+    }
+    for _ in 0..40 {
+        let mid = (lo + hi) / 2.0;
+        let u = f(mid * c);
+        if u == 0.0 {
+            break;
+        } else if u > 0.0 {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    Some(c * (lo + hi) / 2.0)
+}
 
 #[pyclass]
 pub struct BamReader {
-    header: sam::Header,
+    location: Location,
+    header: Arc<sam::Header>,
     chunk_size: usize,
+    /// タグ値を Python オブジェクトに変換する方式
+    tag_conversion: TagConversion,
+    /// 1 レコードに許すブロックサイズの上限 (バイト)
+    max_record_size: usize,
+    /// イテレーション時に適用するフィルタ
+    filter: RecordFilter,
+    /// bgzf 展開に使うスレッド数 (1 ならシングルスレッド)
+    threads: usize,
+    /// `normalized_coverage` の CPM 計算用キャッシュ
+    depth_read_total: OnceLock<u64>,
+    /// `_header` が返す SAM ヘッダーテキスト (初回アクセス時に作る)
+    header_bytes: OnceLock<Py<PyBytes>>,
+    /// 領域クエリに使うインデックス (初回のクエリで読み込む)
+    index: OnceLock<BamIndex>,
 
     /// シーケンシャル読み出し用
-    reader: Option<Arc<Mutex<bam::io::reader::Reader<bgzf::io::reader::Reader<File>>>>>,
+    reader: Option<Arc<Mutex<BamStream>>>,
 
     /// region モード時に全レコードを保持
     region_records: Option<Arc<Vec<bam::Record>>>,
@@ -24,30 +211,377 @@ pub struct BamReader {
     region_pos: usize,
 }
 
+impl BamReader {
+    /// シーケンシャル読み出しモードで開く
+    fn open_sequential(location: Location, chunk_size: usize, threads: usize) -> PyResult<Self> {
+        let mut reader = location
+            .open_bam(threads)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        let header = reader
+            .read_header()
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+
+        Ok(BamReader {
+            location,
+            header: Arc::new(header),
+            chunk_size,
+            tag_conversion: TagConversion::default(),
+            max_record_size: DEFAULT_MAX_RECORD_SIZE,
+            filter: RecordFilter::default(),
+            threads,
+            depth_read_total: OnceLock::new(),
+            header_bytes: OnceLock::new(),
+            index: OnceLock::new(),
+            reader: Some(Arc::new(Mutex::new(reader))),
+            region_records: None,
+            region_pos: 0,
+        })
+    }
+
+    /// 入力を開き直す操作の前に、非シーク可能なストリームでないか確かめる
+    fn require_rereadable(&self, what: &str) -> PyResult<()> {
+        if self.location.is_rereadable() {
+            return Ok(());
+        }
+        Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+            "{what} needs a seekable file object; a non-seekable stream can only be iterated once"
+        )))
+    }
+
+    /// イテレーションとは独立したシーケンシャルリーダーを開く
+    ///
+    /// Full-file scans re-open the location, so they raise `ValueError`
+    /// for non-seekable file objects.
+    fn open_scan_reader(&self) -> PyResult<BamStream> {
+        self.require_rereadable("scanning the whole file")?;
+        let mut reader = self
+            .location
+            .open_bam(self.threads)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        reader
+            .read_header()
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        Ok(reader)
+    }
+
+    /// ファイル全体を GIL なしで 1 回走査し、各レコードを `f` に渡す
+    fn scan<F>(&self, py: Python<'_>, mut f: F) -> PyResult<()>
+    where
+        F: FnMut(&bam::Record) + Send,
+    {
+        let mut reader = self.open_scan_reader()?;
+        let max_record_size = self.max_record_size;
+        py.allow_threads(move || -> std::io::Result<()> {
+            let mut rec = bam::Record::default();
+            while read_record_limited(&mut reader, &mut rec, max_record_size)? != 0 {
+                f(&rec);
+            }
+            Ok(())
+        })
+        .map_err(read_error_to_py)
+    }
+
+    /// `scan` と同じだが、`f` が `false` を返した時点で走査を打ち切る
+    fn scan_while<F>(&self, py: Python<'_>, mut f: F) -> PyResult<()>
+    where
+        F: FnMut(&bam::Record) -> bool + Send,
+    {
+        let mut reader = self.open_scan_reader()?;
+        let max_record_size = self.max_record_size;
+        py.allow_threads(move || -> std::io::Result<()> {
+            let mut rec = bam::Record::default();
+            while read_record_limited(&mut reader, &mut rec, max_record_size)? != 0 {
+                if !f(&rec) {
+                    break;
+                }
+            }
+            Ok(())
+        })
+        .map_err(read_error_to_py)
+    }
+
+    /// `.bai` / `.csi` インデックス (初回に読み込み、以後はキャッシュを返す)
+    fn index(&self) -> io::Result<&BamIndex> {
+        if let Some(index) = self.index.get() {
+            return Ok(index);
+        }
+        let index = self.location.read_index()?;
+        Ok(self.index.get_or_init(|| index))
+    }
+
+    /// インデックスを使って領域に重なるレコードを読むクエリを開く
+    ///
+    /// Records are read through `read_record_limited`, so `max_record_size`
+    /// applies as in sequential reads.
+    fn open_region_query(&self, region: &Region) -> PyResult<RegionQuery> {
+        let Some(rid) = self
+            .header
+            .reference_sequences()
+            .get_index_of(region.name())
+        else {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "unknown reference sequence: {}",
+                region.name()
+            )));
+        };
+        let chunks = self
+            .loaded_index()?
+            .as_binning_index()
+            .query(rid, region.interval())
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        // インデックス付きクエリは bgzf の仮想オフセットでシークするので
+        // シングルスレッドの bgzf リーダーを使う
+        let source = self
+            .location
+            .open()
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        Ok(RegionQuery::new(
+            source,
+            chunks,
+            rid,
+            region.interval(),
+            self.max_record_size,
+        ))
+    }
+
+    /// 末尾の unmapped レコードを読むクエリ (`region="*"`)
+    fn open_unmapped_query(&self) -> PyResult<RegionQuery> {
+        let io_err = |e: io::Error| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string());
+        let start = match self
+            .loaded_index()?
+            .as_binning_index()
+            .last_first_record_start_position()
+        {
+            Some(pos) => pos,
+            // mapped レコードがなければヘッダー直後から読む
+            None => {
+                let mut reader = bam::io::Reader::new(self.location.open().map_err(io_err)?);
+                reader.read_header().map_err(io_err)?;
+                reader.get_ref().virtual_position()
+            }
+        };
+        let source = self.location.open().map_err(io_err)?;
+        Ok(RegionQuery::unmapped(source, start, self.max_record_size))
+    }
+
+    /// インデックスを読み込み、見つからなければ FileNotFoundError にする
+    fn loaded_index(&self) -> PyResult<&BamIndex> {
+        self.index().map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => {
+                PyErr::new::<pyo3::exceptions::PyFileNotFoundError, _>(e.to_string())
+            }
+            _ => PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()),
+        })
+    }
+
+    /// インデックスを使って領域に重なるレコードを取得する
+    fn query_region(&self, py: Python<'_>, region: &Region) -> PyResult<Vec<bam::Record>> {
+        let query = self.open_region_query(region)?;
+        py.allow_threads(move || query.collect::<io::Result<Vec<_>>>())
+            .map_err(read_error_to_py)
+    }
+
+    /// シーケンシャルモードのリーダー (region モードでは ValueError)
+    fn sequential_reader(&self, what: &str) -> PyResult<&Arc<Mutex<BamStream>>> {
+        self.reader.as_ref().ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "{what} is unavailable for a reader opened with a region"
+            ))
+        })
+    }
+
+    /// 深さに数えるリードの総数 (CPM 正規化用、初回のみ走査)
+    fn depth_read_total(&self, py: Python<'_>) -> PyResult<u64> {
+        if let Some(&total) = self.depth_read_total.get() {
+            return Ok(total);
+        }
+        let mut total = 0u64;
+        self.scan(py, |rec| {
+            if coverage::counts_toward_depth(rec.flags()) && !rec.flags().is_supplementary() {
+                total += 1;
+            }
+        })?;
+        Ok(*self.depth_read_total.get_or_init(|| total))
+    }
+
+    /// 領域内の塩基ごとの深さ (0-based half-open, `covered_bases` と同じ規則)
+    ///
+    /// Reads rejected by `filter` are skipped as well.
+    fn region_depth(
+        &self,
+        py: Python<'_>,
+        reference_name: &str,
+        start: i64,
+        end: i64,
+        filter: RecordFilter,
+    ) -> PyResult<Vec<i64>> {
+        let region = make_region(reference_name, start, end)?;
+        let records = self.query_region(py, &region)?;
+        let mut diff = vec![0i64; (end - start) as usize + 1];
+        for rec in &records {
+            if !coverage::counts_toward_depth(rec.flags()) || !filter.accepts(rec) {
+                continue;
+            }
+            let Some((rec_start, _)) = alignment::reference_span(rec) else {
+                continue;
+            };
+            let ops: Vec<Op> = rec.cigar().iter().filter_map(Result::ok).collect();
+            for (block_start, block_end) in alignment::aligned_blocks(&ops, rec_start) {
+                let s = block_start.clamp(start, end) - start;
+                let e = block_end.clamp(start, end) - start;
+                if s < e {
+                    diff[s as usize] += 1;
+                    diff[e as usize] -= 1;
+                }
+            }
+        }
+
+        let mut depth = 0i64;
+        Ok(diff[..diff.len() - 1]
+            .iter()
+            .map(|d| {
+                depth += d;
+                depth
+            })
+            .collect())
+    }
+
+    /// 参照配列名を ID に変換する (`None` はそのまま)
+    fn resolve_reference_id(&self, reference_name: Option<&str>) -> PyResult<Option<usize>> {
+        reference_name
+            .map(|name| {
+                self.header
+                    .reference_sequences()
+                    .get_index_of(name.as_bytes())
+                    .ok_or_else(|| {
+                        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                            "unknown reference sequence: {}",
+                            name
+                        ))
+                    })
+            })
+            .transpose()
+    }
+
+    /// コンストラクタ共通のオプション (タグ変換・サイズ上限・フィルタ) を設定する
+    fn with_options(
+        mut self,
+        tag_conversion: &str,
+        max_record_size: Option<usize>,
+        filter: RecordFilter,
+    ) -> PyResult<Self> {
+        self.tag_conversion = tag_conversion
+            .parse::<TagConversion>()
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+        self.max_record_size = max_record_size.unwrap_or(DEFAULT_MAX_RECORD_SIZE);
+        self.filter = filter;
+        Ok(self)
+    }
+
+    /// bam::Record を Python オブジェクトに包む
+    fn wrap_records(
+        &self,
+        py: Python<'_>,
+        records: Vec<bam::Record>,
+    ) -> PyResult<Vec<Py<PyBamRecord>>> {
+        records
+            .into_iter()
+            .map(|rec| Py::new(py, self.make_record(rec)))
+            .collect()
+    }
+
+    /// リーダーの設定 (ヘッダ・タグ変換) を引き継いだ PyBamRecord を作る
+    pub(crate) fn make_record(&self, rec: bam::Record) -> PyBamRecord {
+        PyBamRecord::from_record(rec)
+            .with_header(self.header.clone())
+            .with_tag_conversion(self.tag_conversion)
+    }
+
+    /// 次の最大 `n` 件のレコードを取り出す (region / シーケンシャル共通)
+    ///
+    /// Records rejected by the reader's filter are skipped. A record whose
+    /// declared size exceeds `max_record_size` raises `ValueError`; other
+    /// read errors (truncated or corrupt data) raise `IOError`.
+    pub(crate) fn next_raw(&mut self, py: Python<'_>, n: usize) -> PyResult<Vec<bam::Record>> {
+        let filter = self.filter;
+        if let Some(records) = &self.region_records {
+            let mut v = Vec::with_capacity(n);
+            while v.len() < n && self.region_pos < records.len() {
+                let rec = &records[self.region_pos];
+                self.region_pos += 1;
+                if filter.accepts(rec) {
+                    v.push(rec.clone());
+                }
+            }
+            return Ok(v);
+        }
+
+        // シーケンシャルモード
+        let reader_arc = self.reader.as_ref().unwrap().clone();
+        let max_record_size = self.max_record_size;
+        py.allow_threads(move || {
+            let mut guard = reader_arc.lock().unwrap();
+            let mut v = Vec::with_capacity(n);
+            while v.len() < n {
+                let mut rec = bam::Record::default();
+                // 読み出しエラーは EOF 扱いにせず例外にする
+                if read_record_limited(&mut *guard, &mut rec, max_record_size)
+                    .map_err(read_error_to_py)?
+                    == 0
+                {
+                    break;
+                }
+                if filter.accepts(&rec) {
+                    v.push(rec);
+                }
+            }
+            Ok(v)
+        })
+    }
+}
+
 #[pymethods]
 impl BamReader {
     /// path, chunk_size, region を受け取るように変更
+    ///
+    /// `threads > 1` で bgzf ブロックの展開をワーカースレッドに分散する
     #[new]
-    #[pyo3(signature = (path, chunk_size=None, region=None))]
-    fn new(path: &str, chunk_size: Option<usize>, region: Option<&str>) -> PyResult<Self> {
+    #[pyo3(signature = (path, chunk_size=None, region=None, tag_conversion="python", max_record_size=None, min_query_length=None, max_query_length=None, min_mapq=None, exclude_flags=None, require_flags=None, threads=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        py: Python<'_>,
+        path: &str,
+        chunk_size: Option<usize>,
+        region: Option<&str>,
+        tag_conversion: &str,
+        max_record_size: Option<usize>,
+        min_query_length: Option<usize>,
+        max_query_length: Option<usize>,
+        min_mapq: Option<u8>,
+        exclude_flags: Option<u16>,
+        require_flags: Option<u16>,
+        threads: Option<usize>,
+    ) -> PyResult<Self> {
         let chunk_size = chunk_size.unwrap_or(1);
+        let filter = RecordFilter {
+            min_query_length,
+            max_query_length,
+            min_mapq,
+            exclude_flags,
+            require_flags,
+        };
 
+        let mut bam_reader = Self::open_sequential(
+            Location::Path(path.to_string()),
+            chunk_size,
+            threads.unwrap_or(1),
+        )?
+        .with_options(tag_conversion, max_record_size, filter)?;
         if let Some(raw_region) = region {
-            // ── indexed_reader で開いて領域クエリ
-            let mut indexed = bam::io::indexed_reader::Builder::default()
-                .build_from_path(path)
-                .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
-            let header = indexed
-                .read_header()
-                .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
-
+            // ── fetch と同じインデックスクエリで領域のレコードを先に読む
             // "*" は unmapped クエリ
-            let records: Vec<_> = if raw_region == "*" {
-                indexed
-                    .query_unmapped()
-                    .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?
-                    .map(|r| r.map_err(|e| e).unwrap())
-                    .collect()
+            let query = if raw_region == "*" {
+                bam_reader.open_unmapped_query()?
             } else {
                 let region =
                     raw_region
@@ -55,46 +589,1738 @@ impl BamReader {
                         .map_err(|e: <Region as FromStr>::Err| {
                             PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string())
                         })?;
-                indexed
-                    .query(&header, &region)
-                    .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?
-                    .map(|r| r.map_err(|e| e).unwrap())
-                    .collect()
-            };
-
-            Ok(BamReader {
-                header,
-                chunk_size,
-                reader: None,
-                region_records: Some(Arc::new(records)),
-                region_pos: 0,
-            })
-        } else {
-            // ── 従来のシーケンシャル読み出し
-            let mut reader = bam::io::reader::Builder::default()
-                .build_from_path(path)
-                .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
-            let header = reader
-                .read_header()
-                .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
-
-            Ok(BamReader {
-                header,
-                chunk_size,
-                reader: Some(Arc::new(Mutex::new(reader))),
-                region_records: None,
-                region_pos: 0,
-            })
+                bam_reader.open_region_query(&region)?
+            };
+            let records = py
+                .allow_threads(move || query.collect::<io::Result<Vec<_>>>())
+                .map_err(read_error_to_py)?;
+            bam_reader.reader = None;
+            bam_reader.region_records = Some(Arc::new(records));
         }
+        Ok(bam_reader)
+    }
+
+    /// HTTP(S) 上の BAM を Range リクエストで読む
+    ///
+    /// Without `index_url`, `url + ".csi"` is tried first and `url + ".bai"`
+    /// if the server answers 404, as for local files. `headers` are sent
+    /// with every request (e.g. authorization). Region queries only
+    /// download the bgzf chunks listed in the index; full scans stream
+    /// sequentially. The remaining options behave as in the constructor.
+    #[staticmethod]
+    #[pyo3(signature = (url, index_url=None, headers=None, chunk_size=None, tag_conversion="python", max_record_size=None, min_query_length=None, max_query_length=None, min_mapq=None, exclude_flags=None, require_flags=None, threads=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn from_url(
+        url: &str,
+        index_url: Option<String>,
+        headers: Option<HashMap<String, String>>,
+        chunk_size: Option<usize>,
+        tag_conversion: &str,
+        max_record_size: Option<usize>,
+        min_query_length: Option<usize>,
+        max_query_length: Option<usize>,
+        min_mapq: Option<u8>,
+        exclude_flags: Option<u16>,
+        require_flags: Option<u16>,
+        threads: Option<usize>,
+    ) -> PyResult<Self> {
+        let location = Location::Url {
+            url: url.to_string(),
+            index_url,
+            headers: headers.unwrap_or_default(),
+        };
+        let filter = RecordFilter {
+            min_query_length,
+            max_query_length,
+            min_mapq,
+            exclude_flags,
+            require_flags,
+        };
+        Self::open_sequential(location, chunk_size.unwrap_or(1), threads.unwrap_or(1))?
+            .with_options(tag_conversion, max_record_size, filter)
     }
 
+    /// `read(n)` を持つ Python のファイルオブジェクトから読む
+    ///
+    /// `obj` may return fewer bytes than requested from `read`. Seekable
+    /// objects are read from offset 0 and support the full-scan methods;
+    /// non-seekable streams are read once, from their current position, and
+    /// raise `ValueError` from `seek` and the full-scan methods. Region
+    /// queries are unavailable as there is no index. `threads` must be 1:
+    /// `read` needs the GIL, which a decompression thread cannot wait for
+    /// safely. The remaining options behave as in the constructor.
+    #[staticmethod]
+    #[pyo3(signature = (obj, chunk_size=None, tag_conversion="python", max_record_size=None, min_query_length=None, max_query_length=None, min_mapq=None, exclude_flags=None, require_flags=None, threads=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn from_fileobj(
+        obj: PyObject,
+        chunk_size: Option<usize>,
+        tag_conversion: &str,
+        max_record_size: Option<usize>,
+        min_query_length: Option<usize>,
+        max_query_length: Option<usize>,
+        min_mapq: Option<u8>,
+        exclude_flags: Option<u16>,
+        require_flags: Option<u16>,
+        threads: Option<usize>,
+    ) -> PyResult<Self> {
+        // read() を呼ぶリーダースレッドが GIL 待ちでデッドロックしうる
+        if threads.is_some_and(|n| n > 1) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "from_fileobj does not support threads > 1",
+            ));
+        }
+        let location = Location::FileObj(Arc::new(obj));
+        let filter = RecordFilter {
+            min_query_length,
+            max_query_length,
+            min_mapq,
+            exclude_flags,
+            require_flags,
+        };
+        Self::open_sequential(location, chunk_size.unwrap_or(1), threads.unwrap_or(1))?
+            .with_options(tag_conversion, max_record_size, filter)
+    }
+
+    /// SAM text of the header. Serialised once; later accesses return the
+    /// same `bytes` object.
     #[getter]
     fn _header<'py>(&self, py: Python<'py>) -> PyResult<Py<PyBytes>> {
+        if let Some(bytes) = self.header_bytes.get() {
+            return Ok(bytes.clone_ref(py));
+        }
         let mut buf = Vec::new();
         let mut w = sam::io::Writer::new(&mut buf);
         w.write_header(&self.header)
             .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
-        Ok(PyBytes::new(py, &buf).into())
+        let bytes = self
+            .header_bytes
+            .get_or_init(|| PyBytes::new(py, &buf).into());
+        Ok(bytes.clone_ref(py))
+    }
+
+    /// `(name, length)` of each `@SQ` line, indexed by `reference_sequence_id`
+    #[getter]
+    fn references(&self) -> Vec<(String, usize)> {
+        self.header
+            .reference_sequences()
+            .iter()
+            .map(|(name, rs)| (name.to_string(), rs.length().get()))
+            .collect()
+    }
+
+    /// `@RG` の ID (ヘッダー順)
+    #[getter]
+    fn read_groups(&self) -> Vec<String> {
+        self.header
+            .read_groups()
+            .keys()
+            .map(|id| id.to_string())
+            .collect()
+    }
+
+    /// 次に読むレコードの bgzf 仮想オフセット
+    ///
+    /// `compressed block offset << 16 | offset within the block`, as used
+    /// by BAI/CSI. Read between chunks (e.g. with `chunk_size=1` or
+    /// `records()`) to get the offset of each record; filtered-out records
+    /// still advance it.
+    #[getter]
+    fn virtual_position(&self, py: Python<'_>) -> PyResult<u64> {
+        let reader = self.sequential_reader("virtual_position")?;
+        // 別スレッドの読み出しが GIL を待っていることがあるので GIL を外してロックする
+        Ok(py.allow_threads(|| u64::from(reader.lock().unwrap().get_ref().virtual_position())))
+    }
+
+    /// `virtual_position` で得た仮想オフセットから読み直す
+    ///
+    /// The offset must point at a record boundary; iteration (chunked or
+    /// `records()`) resumes with the record stored there. Needs a seekable
+    /// source.
+    fn seek(&self, py: Python<'_>, virtual_offset: u64) -> PyResult<()> {
+        let reader = self.sequential_reader("seek")?.clone();
+        self.require_rereadable("seek")?;
+        py.allow_threads(move || {
+            let mut guard = reader.lock().unwrap();
+            guard
+                .get_mut()
+                .seek(bgzf::VirtualPosition::from(virtual_offset))
+                .map(|_| ())
+        })
+        .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))
+    }
+
+    /// ほぼソート済みの入力を座標順に並べ替えながら 1 件ずつ返す
+    ///
+    /// Reads are buffered until every read within `window_size` bases
+    /// downstream has been seen. A read starting more than `window_size`
+    /// bases before the furthest position seen so far raises `ValueError`.
+    /// Iteration is independent of the reader's own position.
+    fn sort_window(&self, window_size: i64) -> PyResult<SortWindowIterator> {
+        if window_size < 0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "window_size must not be negative",
+            ));
+        }
+        Ok(SortWindowIterator::new(
+            self.open_scan_reader()?,
+            self.header.clone(),
+            self.tag_conversion,
+            self.max_record_size,
+            window_size,
+        ))
+    }
+
+    /// 直前の `n_before` 件と組にしてレコードを 1 件ずつ返す
+    ///
+    /// Yields `(record, previous)` where `previous` holds up to `n_before`
+    /// preceding records in file order (oldest first). Iteration is
+    /// independent of the reader's own position.
+    fn sliding(&self, n_before: usize) -> PyResult<SlidingIterator> {
+        Ok(SlidingIterator::new(
+            self.open_scan_reader()?,
+            self.header.clone(),
+            self.tag_conversion,
+            self.max_record_size,
+            n_before,
+        ))
+    }
+
+    /// レコードをリストに包まず 1 件ずつ返すイテレータ
+    ///
+    /// Continues from the reader's current position and applies its
+    /// filter, like chunked iteration does.
+    fn records(slf: Py<Self>) -> RecordIterator {
+        RecordIterator::new(slf)
+    }
+
+    /// ヘッダー行に続けてレコードを SAM テキスト行として 1 行ずつ返す
+    ///
+    /// Lines carry no trailing newline and records are rendered with
+    /// reference names, as `samtools view -h` would print them. Iteration
+    /// is independent of the reader's own position.
+    fn sam_lines(&self) -> PyResult<SamLinesIterator> {
+        SamLinesIterator::new(
+            self.open_scan_reader()?,
+            self.header.clone(),
+            self.max_record_size,
+        )
+    }
+
+    /// 条件に合うレコード数を PyBamRecord を作らずに数える
+    ///
+    /// A record counts if `mapq >= min_mapq`, `flag & exclude_flags == 0`
+    /// and `flag & require_flags == require_flags`; unset criteria always
+    /// pass. Readers opened with a region count that region, others scan
+    /// the whole file. Independent of the reader's position and of its
+    /// constructor filters.
+    #[pyo3(signature = (exclude_flags=None, require_flags=None, min_mapq=None))]
+    fn count(
+        &self,
+        py: Python<'_>,
+        exclude_flags: Option<u16>,
+        require_flags: Option<u16>,
+        min_mapq: Option<u8>,
+    ) -> PyResult<u64> {
+        let filter = RecordFilter {
+            min_mapq,
+            exclude_flags,
+            require_flags,
+            ..RecordFilter::default()
+        };
+        if let Some(records) = &self.region_records {
+            let records = records.clone();
+            return Ok(py.allow_threads(move || {
+                records.iter().filter(|rec| filter.accepts(rec)).count() as u64
+            }));
+        }
+        let mut n = 0u64;
+        self.scan(py, |rec| {
+            if filter.accepts(rec) {
+                n += 1;
+            }
+        })?;
+        Ok(n)
+    }
+
+    /// 参照配列ごとのリード数をインデックスなしで数える
+    ///
+    /// Returns `(counts, unplaced)` where `counts[rid]` is the number of
+    /// records placed on reference `rid` and `unplaced` counts records
+    /// without a reference. Only the fixed-length fields are decoded.
+    fn reference_counts<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyArray1<i64>>, i64)> {
+        let mut counts = vec![0i64; self.header.reference_sequences().len()];
+        let mut unplaced = 0i64;
+        self.scan(py, |rec| match rec.reference_sequence_id() {
+            Some(Ok(rid)) if rid < counts.len() => counts[rid] += 1,
+            _ => unplaced += 1,
+        })?;
+        Ok((PyArray1::from_vec(py, counts), unplaced))
+    }
+
+    /// `samtools idxstats` と同じ参照配列ごとの集計
+    ///
+    /// Returns `(reference_name, length, mapped, unmapped)` per `@SQ` line
+    /// followed by `("*", 0, 0, unplaced)` for unmapped reads without a
+    /// reference. Counts come from the index metadata when a `.csi`/`.bai`
+    /// carrying it is present, otherwise from a full scan.
+    fn idxstats(&self, py: Python<'_>) -> PyResult<Vec<(String, usize, u64, u64)>> {
+        let n_refs = self.header.reference_sequences().len();
+        let from_index = match self.index() {
+            Ok(index) => index_counts(index.as_binning_index(), n_refs),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => return Err(PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string())),
+        };
+        let (counts, unplaced) = match from_index {
+            Some(counts) => counts,
+            None => {
+                let mut counts = vec![(0u64, 0u64); n_refs];
+                let mut unplaced = 0u64;
+                self.scan(py, |rec| match rec.reference_sequence_id() {
+                    Some(Ok(rid)) if rid < n_refs => {
+                        if rec.flags().is_unmapped() {
+                            counts[rid].1 += 1;
+                        } else {
+                            counts[rid].0 += 1;
+                        }
+                    }
+                    _ => unplaced += 1,
+                })?;
+                (counts, unplaced)
+            }
+        };
+
+        let mut stats: Vec<(String, usize, u64, u64)> = self
+            .header
+            .reference_sequences()
+            .iter()
+            .zip(counts)
+            .map(|((name, rs), (mapped, unmapped))| {
+                (
+                    String::from_utf8_lossy(name).into_owned(),
+                    usize::from(rs.length()),
+                    mapped,
+                    unmapped,
+                )
+            })
+            .collect();
+        stats.push(("*".to_string(), 0, 0, unplaced));
+        Ok(stats)
+    }
+
+    /// 領域内のリードを重なり (または `gap` bp 以内) でクラスタにまとめる
+    ///
+    /// Coordinates are 0-based, half-open. Returns a list of
+    /// `(records, start, end)` per cluster in coordinate order.
+    #[pyo3(signature = (reference_name, start, end, gap=0))]
+    fn read_clusters(
+        &self,
+        py: Python<'_>,
+        reference_name: &str,
+        start: i64,
+        end: i64,
+        gap: i64,
+    ) -> PyResult<Vec<ReadCluster>> {
+        let region = make_region(reference_name, start, end)?;
+        let records = self.query_region(py, &region)?;
+
+        let mut clusters: Vec<(Vec<bam::Record>, i64, i64)> = Vec::new();
+        for rec in records {
+            let Some((rec_start, rec_end)) = alignment::reference_span(&rec) else {
+                continue;
+            };
+            match clusters.last_mut() {
+                Some((members, _, cluster_end)) if rec_start - *cluster_end <= gap => {
+                    *cluster_end = (*cluster_end).max(rec_end);
+                    members.push(rec);
+                }
+                _ => clusters.push((vec![rec], rec_start, rec_end)),
+            }
+        }
+
+        clusters
+            .into_iter()
+            .map(|(members, s, e)| Ok((self.wrap_records(py, members)?, s, e)))
+            .collect()
+    }
+
+    /// `.bai` インデックスを使って領域に重なるレコードを返す
+    ///
+    /// Coordinates are 0-based, half-open as in pysam's `fetch`. Returns an
+    /// iterator over the overlapping records; the reader's own position is
+    /// unaffected. A `<path>.csi` index is used when present, `<path>.bai`
+    /// otherwise; `FileNotFoundError` is raised when neither exists.
+    fn fetch(&self, contig: &str, start: i64, end: i64) -> PyResult<RegionIterator> {
+        let region = make_region(contig, start, end)?;
+        Ok(RegionIterator::new(
+            self.open_region_query(&region)?,
+            self.header.clone(),
+            self.tag_conversion,
+            self.filter,
+        ))
+    }
+
+    /// 領域の塩基ごとの深さを `.bai` インデックスから求める
+    ///
+    /// Coordinates are 0-based, half-open; returns a `uint32` array of
+    /// length `end - start`. Only `M`/`=`/`X` bases count, and reads only
+    /// contribute inside the region. Reads not counted toward depth
+    /// (unmapped, secondary, QC-fail, duplicate) and reads rejected by the
+    /// reader's filter are skipped.
+    fn coverage<'py>(
+        &self,
+        py: Python<'py>,
+        contig: &str,
+        start: i64,
+        end: i64,
+    ) -> PyResult<Bound<'py, PyArray1<u32>>> {
+        let depths = self.region_depth(py, contig, start, end, self.filter)?;
+        Ok(PyArray1::from_vec(
+            py,
+            depths.into_iter().map(|d| d as u32).collect(),
+        ))
+    }
+
+    /// 既知の座位ごとに塩基別のリード数を返す
+    ///
+    /// `loci` holds `(reference_name, position)` pairs with 0-based
+    /// positions. Nearby loci on the same reference are fetched with one
+    /// index query. Reads not counted toward depth, reads with MAPQ below
+    /// `min_mapq` and bases with quality below `min_base_qual` are skipped;
+    /// deleted or skipped positions are not counted. Returns one
+    /// `{base: count}` dict per locus, in input order.
+    #[pyo3(signature = (loci, min_base_qual=0, min_mapq=0))]
+    fn allele_depths<'py>(
+        &self,
+        py: Python<'py>,
+        loci: Vec<(String, i64)>,
+        min_base_qual: u8,
+        min_mapq: u8,
+    ) -> PyResult<Vec<Bound<'py, PyDict>>> {
+        let mut counts: Vec<HashMap<char, u64>> = vec![HashMap::new(); loci.len()];
+
+        // 参照配列ごとに位置順に並べ、近い座位を 1 つの領域にまとめる
+        let mut by_contig: HashMap<&str, Vec<(i64, usize)>> = HashMap::new();
+        for (i, (name, pos)) in loci.iter().enumerate() {
+            if *pos < 0 {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "invalid position: {}",
+                    pos
+                )));
+            }
+            by_contig.entry(name.as_str()).or_default().push((*pos, i));
+        }
+        for (name, mut sites) in by_contig {
+            sites.sort_unstable();
+            let mut group_start = 0;
+            while group_start < sites.len() {
+                let mut group_end = group_start + 1;
+                while group_end < sites.len()
+                    && sites[group_end].0 - sites[group_end - 1].0 <= LOCUS_MERGE_GAP
+                {
+                    group_end += 1;
+                }
+                let group = &sites[group_start..group_end];
+                let region = make_region(name, group[0].0, group[group.len() - 1].0 + 1)?;
+                for rec in self.query_region(py, &region)? {
+                    if !coverage::counts_toward_depth(rec.flags())
+                        || rec.mapping_quality().map_or(255, u8::from) < min_mapq
+                    {
+                        continue;
+                    }
+                    let Some((start, end)) = alignment::reference_span(&rec) else {
+                        continue;
+                    };
+                    let ops: Vec<Op> = rec.cigar().iter().filter_map(Result::ok).collect();
+                    let seq: Vec<u8> = rec.sequence().iter().collect();
+                    let quals = rec.quality_scores();
+                    let quals = quals.as_ref();
+                    let first = group.partition_point(|&(pos, _)| pos < start);
+                    for &(pos, i) in group[first..].iter().take_while(|&&(pos, _)| pos < end) {
+                        let Some(q) = alignment::reference_to_query(&ops, start, pos) else {
+                            continue;
+                        };
+                        let qual = quals.get(q).copied().unwrap_or(0xff);
+                        if qual != 0xff && qual < min_base_qual {
+                            continue;
+                        }
+                        if let Some(base) = seq.get(q) {
+                            *counts[i]
+                                .entry(base.to_ascii_uppercase() as char)
+                                .or_default() += 1;
+                        }
+                    }
+                }
+                group_start = group_end;
+            }
+        }
+
+        counts
+            .into_iter()
+            .map(|c| {
+                let dict = PyDict::new(py);
+                for (base, n) in c {
+                    dict.set_item(base, n)?;
+                }
+                Ok(dict)
+            })
+            .collect()
+    }
+
+    /// ペアを 1 フラグメントとして数えたカバレッジを返す
+    ///
+    /// Coordinates are 0-based, half-open; the result has one depth value
+    /// per position of `start..end`. Properly paired reads contribute the
+    /// outer fragment span (from `TLEN`) once, taken from read1, or from
+    /// read2 when read1 does not overlap the region. Other reads count as
+    /// their own aligned span. Reads are fetched from `max_insert_size` bp
+    /// either side of the region, so fragments whose mates both lie outside
+    /// it are still counted as long as the fragment is no longer than that.
+    #[pyo3(signature = (reference_name, start, end, max_insert_size=1000))]
+    fn fragment_coverage<'py>(
+        &self,
+        py: Python<'py>,
+        reference_name: &str,
+        start: i64,
+        end: i64,
+        max_insert_size: i64,
+    ) -> PyResult<Bound<'py, PyArray1<i32>>> {
+        make_region(reference_name, start, end)?;
+        if max_insert_size < 0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "max_insert_size must not be negative",
+            ));
+        }
+        // 両メイトとも領域外のフラグメントも拾えるよう広げて読む
+        let region = make_region(
+            reference_name,
+            (start - max_insert_size).max(0),
+            end + max_insert_size,
+        )?;
+        let records = self.query_region(py, &region)?;
+
+        let is_pair = |rec: &bam::Record| {
+            let flags = rec.flags();
+            flags.is_segmented() && flags.is_properly_segmented() && rec.template_length() != 0
+        };
+        let read1_names: HashSet<Vec<u8>> = records
+            .iter()
+            .filter(|rec| is_pair(rec) && rec.flags().is_first_segment())
+            .filter_map(|rec| rec.name().map(|n| n.to_vec()))
+            .collect();
+
+        let mut diff = vec![0i32; (end - start) as usize + 1];
+        for rec in &records {
+            if !coverage::counts_toward_depth(rec.flags()) || rec.flags().is_supplementary() {
+                continue;
+            }
+            let Some((rec_start, rec_end)) = alignment::reference_span(rec) else {
+                continue;
+            };
+            let (frag_start, frag_end) = if is_pair(rec) {
+                let name = rec.name().map(|n| n.to_vec()).unwrap_or_default();
+                if !rec.flags().is_first_segment() && read1_names.contains(&name) {
+                    continue;
+                }
+                let tlen = rec.template_length() as i64;
+                if tlen > 0 {
+                    (rec_start, rec_start + tlen)
+                } else {
+                    (rec_end + tlen, rec_end)
+                }
+            } else {
+                (rec_start, rec_end)
+            };
+            let s = frag_start.clamp(start, end) - start;
+            let e = frag_end.clamp(start, end) - start;
+            if s < e {
+                diff[s as usize] += 1;
+                diff[e as usize] -= 1;
+            }
+        }
+
+        let mut depth = 0i32;
+        let coverage = diff[..diff.len() - 1]
+            .iter()
+            .map(|d| {
+                depth += d;
+                depth
+            })
+            .collect();
+        Ok(PyArray1::from_vec(py, coverage))
+    }
+
+    /// 領域内の位置ごとの挿入・欠失リード数を返す
+    ///
+    /// Coordinates are 0-based, half-open. Returns `(insertions, deletions)`
+    /// with one count per position of `start..end`. An insertion is counted
+    /// at the reference base it follows; a deletion at every deleted base.
+    /// Reads skipped by depth calculations (unmapped, secondary, QC-fail,
+    /// duplicate) are ignored.
+    fn indel_profile<'py>(
+        &self,
+        py: Python<'py>,
+        reference_name: &str,
+        start: i64,
+        end: i64,
+    ) -> PyResult<CountArrays<'py>> {
+        let region = make_region(reference_name, start, end)?;
+        let records = self.query_region(py, &region)?;
+
+        let len = (end - start) as usize;
+        let mut insertions = vec![0i32; len];
+        let mut deletions = vec![0i32; len];
+        let slot = |pos: i64| (start..end).contains(&pos).then(|| (pos - start) as usize);
+        for rec in &records {
+            if !coverage::counts_toward_depth(rec.flags()) {
+                continue;
+            }
+            let Some((mut r, _)) = alignment::reference_span(rec) else {
+                continue;
+            };
+            for op in rec.cigar().iter().filter_map(Result::ok) {
+                let op_len = op.len() as i64;
+                match op.kind() {
+                    Kind::Insertion => {
+                        if let Some(i) = slot(r - 1) {
+                            insertions[i] += 1;
+                        }
+                    }
+                    Kind::Deletion => {
+                        for pos in r..r + op_len {
+                            if let Some(i) = slot(pos) {
+                                deletions[i] += 1;
+                            }
+                        }
+                        r += op_len;
+                    }
+                    Kind::Match | Kind::SequenceMatch | Kind::SequenceMismatch | Kind::Skip => {
+                        r += op_len
+                    }
+                    Kind::SoftClip | Kind::HardClip | Kind::Pad => {}
+                }
+            }
+        }
+        Ok((
+            PyArray1::from_vec(py, insertions),
+            PyArray1::from_vec(py, deletions),
+        ))
+    }
+
+    /// 領域内の位置ごとの 5mC コール数 (methylated, total) を返す
+    ///
+    /// Coordinates are 0-based, half-open. `m` calls from each read's
+    /// `MM`/`ML` tags are mapped to the reference position of the called
+    /// base; calls on inserted or clipped bases are dropped. A call counts
+    /// toward `total` when its probability of being modified or unmodified
+    /// is at least `min_prob`, and toward `methylated` when the former is.
+    /// Bases skipped in `.` mode count as confident unmodified calls.
+    /// Minus-strand CpGs are reported at the G position.
+    #[pyo3(signature = (reference_name, start, end, min_prob=0.5))]
+    fn methylation_profile<'py>(
+        &self,
+        py: Python<'py>,
+        reference_name: &str,
+        start: i64,
+        end: i64,
+        min_prob: f64,
+    ) -> PyResult<CountArrays<'py>> {
+        let region = make_region(reference_name, start, end)?;
+        let records = self.query_region(py, &region)?;
+
+        let len = (end - start) as usize;
+        let mut methylated = vec![0i32; len];
+        let mut total = vec![0i32; len];
+        for rec in &records {
+            if !coverage::counts_toward_depth(rec.flags()) {
+                continue;
+            }
+            let Some((rec_start, _)) = alignment::reference_span(rec) else {
+                continue;
+            };
+            let calls = modifications::base_modifications(rec)
+                .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+            let ops: Vec<Op> = rec.cigar().iter().filter_map(Result::ok).collect();
+            for call in calls.iter().filter(|c| c.code == "m") {
+                let Some(ref_pos) = alignment::query_to_reference(&ops, rec_start, call.query_pos)
+                else {
+                    continue;
+                };
+                if !(start..end).contains(&ref_pos) {
+                    continue;
+                }
+                // ML の値 n は確率 [n/256, (n+1)/256) を表す
+                let p = (call.prob as f64 + 0.5) / 256.0;
+                let i = (ref_pos - start) as usize;
+                if call.explicit && p >= min_prob {
+                    methylated[i] += 1;
+                    total[i] += 1;
+                } else if !call.explicit || 1.0 - p >= min_prob {
+                    total[i] += 1;
+                }
+            }
+        }
+        Ok((
+            PyArray1::from_vec(py, methylated),
+            PyArray1::from_vec(py, total),
+        ))
+    }
+
+    /// 正規化したカバレッジ (bigWig 用シグナル) を返す
+    ///
+    /// Coordinates are 0-based, half-open. Depth follows the same rules as
+    /// `covered_bases`. `scale` is `"cpm"` (counts per million reads counted
+    /// toward depth genome-wide; the total is computed once per reader) or
+    /// a number the raw depth is multiplied by.
+    #[pyo3(signature = (reference_name, start, end, scale=None))]
+    fn normalized_coverage<'py>(
+        &self,
+        py: Python<'py>,
+        reference_name: &str,
+        start: i64,
+        end: i64,
+        scale: Option<&Bound<'py, PyAny>>,
+    ) -> PyResult<Bound<'py, PyArray1<f64>>> {
+        let factor = match scale {
+            None => None,
+            Some(s) if s.extract::<&str>().is_ok_and(|s| s == "cpm") => None,
+            Some(s) => Some(s.extract::<f64>().map_err(|_| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>("scale must be 'cpm' or a number")
+            })?),
+        };
+        let factor = match factor {
+            Some(f) => f,
+            None => {
+                let total = self.depth_read_total(py)?;
+                if total == 0 {
+                    0.0
+                } else {
+                    1e6 / total as f64
+                }
+            }
+        };
+
+        let signal = self
+            .region_depth(py, reference_name, start, end, RecordFilter::default())?
+            .into_iter()
+            .map(|d| d as f64 * factor)
+            .collect();
+        Ok(PyArray1::from_vec(py, signal))
+    }
+
+    /// ターゲット領域のカバレッジ均一性 (平均, CV, Fold-80) を返す
+    ///
+    /// `regions` holds `(reference_name, start, end)` targets (0-based,
+    /// half-open); bases of overlapping targets are counted once per target.
+    /// Returns a dict with `mean`, `cv` (population standard deviation over
+    /// mean) and `fold_80_penalty` (mean over the 20th percentile depth of
+    /// covered bases, as in Picard `HsMetrics`). `cv` and `fold_80_penalty`
+    /// are `None` when no target base is covered.
+    fn coverage_uniformity<'py>(
+        &self,
+        py: Python<'py>,
+        regions: Vec<(String, i64, i64)>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let mut depths: Vec<i64> = Vec::new();
+        for (name, start, end) in &regions {
+            depths.extend(self.region_depth(py, name, *start, *end, RecordFilter::default())?);
+        }
+        if depths.is_empty() {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "regions must cover at least one base",
+            ));
+        }
+
+        let n = depths.len() as f64;
+        let mean = depths.iter().sum::<i64>() as f64 / n;
+        let variance = depths
+            .iter()
+            .map(|&d| (d as f64 - mean).powi(2))
+            .sum::<f64>()
+            / n;
+        let mut covered: Vec<i64> = depths.into_iter().filter(|&d| d > 0).collect();
+        covered.sort_unstable();
+        // nearest-rank の 20 パーセンタイル
+        let p20 = covered
+            .get(((0.2 * covered.len() as f64).ceil() as usize).max(1) - 1)
+            .copied();
+
+        let dict = PyDict::new(py);
+        dict.set_item("mean", mean)?;
+        dict.set_item("cv", (mean > 0.0).then(|| variance.sqrt() / mean))?;
+        dict.set_item("fold_80_penalty", p20.map(|p| mean / p as f64))?;
+        Ok(dict)
+    }
+
+    /// 領域のパイルアップを画像テンソルとして返す (DeepVariant 風)
+    ///
+    /// Returns a `uint8` array of shape `(5, max_depth, end - start)`:
+    /// channels 0-3 are one-hot `A`, `C`, `G`, `T` and channel 4 the base
+    /// quality. Each read counted toward depth gets its own row, in
+    /// alignment start order, up to `max_depth` reads. Deleted and skipped
+    /// positions are left zero; insertions are not represented.
+    /// Coordinates are 0-based, half-open.
+    fn pileup_tensor<'py>(
+        &self,
+        py: Python<'py>,
+        reference_name: &str,
+        start: i64,
+        end: i64,
+        max_depth: usize,
+    ) -> PyResult<Bound<'py, PyArray3<u8>>> {
+        const N_CHANNELS: usize = 5;
+        let region = make_region(reference_name, start, end)?;
+        let records = self.query_region(py, &region)?;
+        let width = (end - start) as usize;
+        let mut tensor = vec![0u8; N_CHANNELS * max_depth * width];
+
+        let reads = records
+            .iter()
+            .filter(|rec| coverage::counts_toward_depth(rec.flags()))
+            .filter_map(|rec| alignment::reference_span(rec).map(|(s, _)| (rec, s)))
+            .take(max_depth);
+        for (row, (rec, rec_start)) in reads.enumerate() {
+            let seq: Vec<u8> = rec.sequence().iter().collect();
+            let quals = rec.quality_scores();
+            let quals = quals.as_ref();
+            let (mut r, mut q) = (rec_start, 0usize);
+            for op in rec.cigar().iter().filter_map(Result::ok) {
+                let len = op.len();
+                match op.kind() {
+                    Kind::Match | Kind::SequenceMatch | Kind::SequenceMismatch => {
+                        for i in 0..len {
+                            let pos = r + i as i64;
+                            if pos < start || pos >= end {
+                                continue;
+                            }
+                            let col = (pos - start) as usize;
+                            let cell = |channel: usize| (channel * max_depth + row) * width + col;
+                            let channel = match seq.get(q + i).map(u8::to_ascii_uppercase) {
+                                Some(b'A') => Some(0),
+                                Some(b'C') => Some(1),
+                                Some(b'G') => Some(2),
+                                Some(b'T') => Some(3),
+                                _ => None,
+                            };
+                            if let Some(channel) = channel {
+                                tensor[cell(channel)] = 1;
+                            }
+                            // 0xff は品質なし
+                            tensor[cell(4)] = match quals.get(q + i) {
+                                Some(&b) if b != 0xff => b,
+                                _ => 0,
+                            };
+                        }
+                        r += len as i64;
+                        q += len;
+                    }
+                    Kind::Insertion | Kind::SoftClip => q += len,
+                    Kind::Deletion | Kind::Skip => r += len as i64,
+                    Kind::HardClip | Kind::Pad => {}
+                }
+            }
+        }
+        PyArray1::from_vec(py, tensor).reshape([N_CHANNELS, max_depth, width])
+    }
+
+    /// 領域のリードをブラウザ向けの軽量バイナリにまとめる
+    ///
+    /// Reads counted toward depth are written in alignment start order, up
+    /// to `max_depth` reads. All integers are little-endian:
+    ///
+    /// - `u32` read count, then per read:
+    /// - `i32` alignment start (0-based), `u16` flags, `u16` CIGAR op count
+    /// - CIGAR ops as `u32` (`len << 4 | op`, BAM encoding, `MIDNSHP=X`)
+    /// - `u32` sequence length, then the sequence packed two bases per byte
+    ///   (high nibble first, BAM 4-bit codes `=ACMGRSVTWYHKDBN`)
+    ///
+    /// Coordinates are 0-based, half-open.
+    fn region_payload<'py>(
+        &self,
+        py: Python<'py>,
+        reference_name: &str,
+        start: i64,
+        end: i64,
+        max_depth: usize,
+    ) -> PyResult<Bound<'py, PyBytes>> {
+        const CODES: &[u8; 16] = b"=ACMGRSVTWYHKDBN";
+        let region = make_region(reference_name, start, end)?;
+        let records = self.query_region(py, &region)?;
+        let reads: Vec<(&bam::Record, i64)> = records
+            .iter()
+            .filter(|rec| coverage::counts_toward_depth(rec.flags()))
+            .filter_map(|rec| alignment::reference_span(rec).map(|(s, _)| (rec, s)))
+            .take(max_depth)
+            .collect();
+
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(reads.len() as u32).to_le_bytes());
+        for (rec, rec_start) in reads {
+            let cigar = rec.cigar();
+            let cigar = cigar.as_ref();
+            buf.extend_from_slice(&(rec_start as i32).to_le_bytes());
+            buf.extend_from_slice(&u16::from(rec.flags()).to_le_bytes());
+            buf.extend_from_slice(&((cigar.len() / 4) as u16).to_le_bytes());
+            buf.extend_from_slice(cigar);
+
+            let seq: Vec<u8> = rec.sequence().iter().collect();
+            buf.extend_from_slice(&(seq.len() as u32).to_le_bytes());
+            let code = |b: u8| {
+                CODES
+                    .iter()
+                    .position(|&c| c == b.to_ascii_uppercase())
+                    .unwrap_or(15) as u8
+            };
+            for pair in seq.chunks(2) {
+                let lo = pair.get(1).map_or(0, |&b| code(b));
+                buf.push(code(pair[0]) << 4 | lo);
+            }
+        }
+        Ok(PyBytes::new(py, &buf))
+    }
+
+    /// ブレークポイント周辺のリードを spanning / split / flanking に分類する
+    ///
+    /// Fetches reads overlapping `position ± window` (0-based). A read is
+    /// `"split"` when a clip starts exactly at the breakpoint (left clip with
+    /// the alignment starting at `position`, or right clip with the
+    /// alignment ending there), `"spanning"` when its alignment covers bases
+    /// on both sides of `position`, and `"flanking"` otherwise. Returns a
+    /// dict mapping each class to its records.
+    fn reads_at_breakpoint<'py>(
+        &self,
+        py: Python<'py>,
+        reference_name: &str,
+        position: i64,
+        window: i64,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let region = make_region(
+            reference_name,
+            (position - window).max(0),
+            position + window.max(1),
+        )?;
+        let records = self.query_region(py, &region)?;
+
+        let mut spanning = Vec::new();
+        let mut split = Vec::new();
+        let mut flanking = Vec::new();
+        for rec in records {
+            let Some((start, end)) = alignment::reference_span(&rec) else {
+                continue;
+            };
+            let ops: Vec<Op> = rec.cigar().iter().filter_map(Result::ok).collect();
+            let (left, right) = alignment::terminal_clips(&ops);
+            if (left > 0 && start == position) || (right > 0 && end == position) {
+                split.push(rec);
+            } else if start < position && end > position {
+                spanning.push(rec);
+            } else {
+                flanking.push(rec);
+            }
+        }
+
+        let dict = PyDict::new(py);
+        dict.set_item("spanning", self.wrap_records(py, spanning)?)?;
+        dict.set_item("split", self.wrap_records(py, split)?)?;
+        dict.set_item("flanking", self.wrap_records(py, flanking)?)?;
+        Ok(dict)
+    }
+
+    /// レコード内容のチェックサムを計算する
+    ///
+    /// The hash covers qname, flag, reference id, position, mapq, CIGAR,
+    /// sequence, qualities and tags of every record in file order, so it
+    /// does not depend on bgzf block layout or compression level.
+    #[pyo3(signature = (algorithm="crc32"))]
+    fn checksum(&self, py: Python<'_>, algorithm: &str) -> PyResult<u32> {
+        if algorithm != "crc32" {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "unsupported checksum algorithm: {}",
+                algorithm
+            )));
+        }
+        let mut hasher = crc32fast::Hasher::new();
+        self.scan(py, |rec| {
+            let mut update = |field: &[u8]| {
+                hasher.update(&(field.len() as u32).to_le_bytes());
+                hasher.update(field);
+            };
+            update(rec.name().unwrap_or_default());
+            update(&u16::from(rec.flags()).to_le_bytes());
+            let rid = match rec.reference_sequence_id() {
+                Some(Ok(rid)) => rid as i64,
+                _ => -1,
+            };
+            update(&rid.to_le_bytes());
+            let pos = match rec.alignment_start() {
+                Some(Ok(pos)) => usize::from(pos) as i64,
+                _ => -1,
+            };
+            update(&pos.to_le_bytes());
+            let mapq = rec.mapping_quality().map(u8::from).unwrap_or(255);
+            update(&[mapq]);
+            update(rec.cigar().as_ref());
+            update(rec.sequence().as_ref());
+            update(rec.quality_scores().as_ref());
+            update(rec.data().as_ref());
+        })?;
+        Ok(hasher.finalize())
+    }
+
+    /// 品質 `threshold` 以上の塩基の割合を Picard QualityYieldMetrics 風に返す
+    ///
+    /// Secondary and supplementary records are skipped. Returns a dict with
+    /// `total_reads`, `total_bases`, `bases_above_threshold` (bases with
+    /// quality >= `threshold`) and `fraction` of the two. Reads without
+    /// qualities count toward `total_bases` only.
+    #[pyo3(signature = (threshold=30))]
+    fn quality_yield<'py>(&self, py: Python<'py>, threshold: u8) -> PyResult<Bound<'py, PyDict>> {
+        let mut reads = 0u64;
+        let mut total = 0u64;
+        let mut above = 0u64;
+        self.scan(py, |rec| {
+            let flags = rec.flags();
+            if flags.is_secondary() || flags.is_supplementary() {
+                return;
+            }
+            reads += 1;
+            let quals = rec.quality_scores();
+            let quals = quals.as_ref();
+            total += rec.sequence().len() as u64;
+            if quals.first().is_some_and(|&q| q != 0xff) {
+                above += quals.iter().filter(|&&q| q >= threshold).count() as u64;
+            }
+        })?;
+
+        let dict = PyDict::new(py);
+        dict.set_item("total_reads", reads)?;
+        dict.set_item("total_bases", total)?;
+        dict.set_item("bases_above_threshold", above)?;
+        let fraction = if total == 0 {
+            0.0
+        } else {
+            above as f64 / total as f64
+        };
+        dict.set_item("fraction", fraction)?;
+        Ok(dict)
+    }
+
+    /// 重複フラグ (0x400) の集計を Picard MarkDuplicates 風に返す
+    ///
+    /// Counts primary mapped records. Keys: `total_reads`, `duplicates`,
+    /// `optical_duplicates` (duplicates tagged `DT:Z:SQ`),
+    /// `duplication_rate` and `estimated_library_size` (Lander-Waterman,
+    /// computed on read1 only for paired data; `None` when undefined).
+    fn duplicate_stats<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let optical_tag = Tag::new(b'D', b'T');
+        let mut total = 0u64;
+        let mut duplicates = 0u64;
+        let mut optical = 0u64;
+        // ライブラリサイズ推定用 (ペアは read1 のみ数える)
+        let mut examined = 0u64;
+        let mut examined_duplicates = 0u64;
+        self.scan(py, |rec| {
+            let flags = rec.flags();
+            if flags.is_unmapped() || flags.is_secondary() || flags.is_supplementary() {
+                return;
+            }
+            total += 1;
+            let counted = !flags.is_segmented() || flags.is_first_segment();
+            if counted {
+                examined += 1;
+            }
+            if flags.is_duplicate() {
+                duplicates += 1;
+                if counted {
+                    examined_duplicates += 1;
+                }
+                if matches!(
+                    rec.data().get(&optical_tag),
+                    Some(Ok(BamValue::String(s))) if s == b"SQ"
+                ) {
+                    optical += 1;
+                }
+            }
+        })?;
+
+        let dict = PyDict::new(py);
+        dict.set_item("total_reads", total)?;
+        dict.set_item("duplicates", duplicates)?;
+        dict.set_item("optical_duplicates", optical)?;
+        let rate = if total == 0 {
+            0.0
+        } else {
+            duplicates as f64 / total as f64
+        };
+        dict.set_item("duplication_rate", rate)?;
+        dict.set_item(
+            "estimated_library_size",
+            estimate_library_size(examined, examined - examined_duplicates),
+        )?;
+        Ok(dict)
+    }
+
+    /// 挿入長 (|TLEN|) のパーセンタイルをヒストグラムから求める
+    ///
+    /// Only properly-paired primary read1 records are counted, and
+    /// fragments longer than `max_size` or with a TLEN of 0 are ignored.
+    /// Each percentile is the smallest insert size whose cumulative share
+    /// reaches it (nearest-rank). Raises `ValueError` when no fragment is
+    /// counted.
+    #[pyo3(signature = (percentiles=vec![10.0, 50.0, 90.0], max_size=5000))]
+    fn insert_size_percentiles(
+        &self,
+        py: Python<'_>,
+        percentiles: Vec<f64>,
+        max_size: usize,
+    ) -> PyResult<Vec<i64>> {
+        if let Some(p) = percentiles.iter().find(|p| !(0.0..=100.0).contains(*p)) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "percentile must be between 0 and 100, got {}",
+                p
+            )));
+        }
+        let mut histogram = vec![0u64; max_size + 1];
+        self.scan(py, |rec| {
+            let flags = rec.flags();
+            if !flags.is_properly_segmented()
+                || !flags.is_first_segment()
+                || flags.is_secondary()
+                || flags.is_supplementary()
+            {
+                return;
+            }
+            let size = rec.template_length().unsigned_abs() as usize;
+            if size > 0 && size <= max_size {
+                histogram[size] += 1;
+            }
+        })?;
+
+        let total: u64 = histogram.iter().sum();
+        if total == 0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "no properly-paired read1 records with an insert size",
+            ));
+        }
+        Ok(percentiles
+            .iter()
+            .map(|&p| {
+                // nearest-rank: 累積数が ceil(p/100 * total) に達する最小のサイズ
+                let rank = ((p / 100.0 * total as f64).ceil() as u64).max(1);
+                let mut cumulative = 0u64;
+                histogram
+                    .iter()
+                    .position(|&n| {
+                        cumulative += n;
+                        cumulative >= rank
+                    })
+                    .unwrap_or(max_size) as i64
+            })
+            .collect())
+    }
+
+    /// ソフト/ハードクリップ位置を集計して SV ブレークポイント候補を返す
+    ///
+    /// Returns `(reference_name, position, side, support)` for every
+    /// 0-based breakpoint where at least `min_support` mapped reads carry a
+    /// clip of `min_clip_len` or more. `side` is `"left"` when the clip
+    /// precedes the aligned part (breakpoint at the alignment start) and
+    /// `"right"` when it follows it (breakpoint at the alignment end).
+    #[pyo3(signature = (min_clip_len=10, min_support=3))]
+    fn clip_breakpoints(
+        &self,
+        py: Python<'_>,
+        min_clip_len: usize,
+        min_support: usize,
+    ) -> PyResult<Vec<(String, i64, &'static str, usize)>> {
+        let mut support: HashMap<(usize, i64, &'static str), usize> = HashMap::new();
+        self.scan(py, |rec| {
+            let Some(Ok(rid)) = rec.reference_sequence_id() else {
+                return;
+            };
+            let Some((start, end)) = alignment::reference_span(rec) else {
+                return;
+            };
+            let ops: Vec<Op> = rec.cigar().iter().filter_map(Result::ok).collect();
+            let (left, right) = alignment::terminal_clips(&ops);
+            if left >= min_clip_len.max(1) {
+                *support.entry((rid, start, "left")).or_default() += 1;
+            }
+            if right >= min_clip_len.max(1) {
+                *support.entry((rid, end, "right")).or_default() += 1;
+            }
+        })?;
+
+        let mut breakpoints: Vec<_> = support
+            .into_iter()
+            .filter(|&(_, n)| n >= min_support)
+            .collect();
+        breakpoints.sort_unstable();
+        Ok(breakpoints
+            .into_iter()
+            .map(|((rid, pos, side), n)| {
+                let name = self
+                    .header
+                    .reference_sequences()
+                    .get_index(rid)
+                    .map(|(name, _)| name.to_string())
+                    .unwrap_or_default();
+                (name, pos, side, n)
+            })
+            .collect())
+    }
+
+    /// 指定したスカラー項目だけを持つ namedtuple のリストを返す
+    ///
+    /// Reads up to `n` records from the current iteration position.
+    /// `fields` is any subset of `qname`, `flag`, `rid`, `pos`, `mapq` and
+    /// `len`, with the same values as the `PyBamRecord` getters.
+    #[pyo3(signature = (n, fields=None))]
+    fn next_namedtuples(
+        &mut self,
+        py: Python<'_>,
+        n: usize,
+        fields: Option<Vec<String>>,
+    ) -> PyResult<Vec<PyObject>> {
+        let fields = fields.unwrap_or_else(|| {
+            PyBamRecord::SCALAR_FIELDS
+                .iter()
+                .map(|f| f.to_string())
+                .collect()
+        });
+        if let Some(bad) = fields
+            .iter()
+            .find(|f| !PyBamRecord::SCALAR_FIELDS.contains(&f.as_str()))
+        {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "unknown field: {}",
+                bad
+            )));
+        }
+        let tuple_type = py
+            .import("collections")?
+            .getattr("namedtuple")?
+            .call1(("BamRecordTuple", fields.clone()))?;
+
+        let mut out = Vec::new();
+        for rec in self.next_raw(py, n)? {
+            let rec = PyBamRecord::from_record(rec);
+            let values = fields
+                .iter()
+                .map(|f| rec.scalar_field(py, f))
+                .collect::<PyResult<Vec<_>>>()?;
+            out.push(tuple_type.call1(PyTuple::new(py, values)?)?.unbind());
+        }
+        Ok(out)
+    }
+
+    /// 次の最大 `n` 件のタグを列ごとの masked array にまとめて返す
+    ///
+    /// Returns `{name: numpy.ma.MaskedArray}` with missing values masked.
+    /// Integer tags become `int64`, float tags (or a mix of float and
+    /// integer) `float64`, and anything else an `object` array of strings
+    /// formatted as in `write_parquet`. A tag absent from every record is a
+    /// fully masked `float64` array.
+    fn next_tag_columns<'py>(
+        &mut self,
+        py: Python<'py>,
+        names: Vec<String>,
+        n: usize,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let tags = names
+            .iter()
+            .map(|name| {
+                record_override::convert_string_to_tag(name.clone())
+                    .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        let records = self.next_raw(py, n)?;
+
+        let masked_array = py.import("numpy.ma")?.getattr("masked_array")?;
+        let columns = PyDict::new(py);
+        for (name, tag) in names.iter().zip(tags) {
+            let values: Vec<Option<TagScalar>> = records
+                .iter()
+                .map(|rec| parquet_export::tag_scalar(rec, tag))
+                .collect();
+            let mask: Vec<bool> = values.iter().map(Option::is_none).collect();
+            let kinds: HashSet<TagKind> = values.iter().flatten().map(TagScalar::kind).collect();
+            let data: Bound<'py, PyAny> = if kinds.contains(&TagKind::Text) {
+                let texts: Vec<Option<String>> = values
+                    .iter()
+                    .map(|v| v.as_ref().map(TagScalar::text))
+                    .collect();
+                py.import("numpy")?
+                    .getattr("array")?
+                    .call1((texts, "object"))?
+            } else if kinds.contains(&TagKind::Int) && !kinds.contains(&TagKind::Float) {
+                let ints: Vec<i64> = values
+                    .iter()
+                    .map(|v| match v {
+                        Some(TagScalar::Int(i)) => *i,
+                        _ => 0,
+                    })
+                    .collect();
+                PyArray1::from_vec(py, ints).into_any()
+            } else {
+                let floats: Vec<f64> = values
+                    .iter()
+                    .map(|v| match v {
+                        Some(TagScalar::Int(i)) => *i as f64,
+                        Some(TagScalar::Float(f)) => *f,
+                        _ => f64::NAN,
+                    })
+                    .collect();
+                PyArray1::from_vec(py, floats).into_any()
+            };
+            let kwargs = PyDict::new(py);
+            kwargs.set_item("mask", PyArray1::from_vec(py, mask))?;
+            columns.set_item(name, masked_array.call((data,), Some(&kwargs))?)?;
+        }
+        Ok(columns)
+    }
+
+    /// 次の `chunk_size` 件を PyBamRecord を作らずに列ごとの配列で返す
+    ///
+    /// Keys: `pos` (`int64`, 1-based as in `PyBamRecord.pos`, -1 if
+    /// unset), `mapq` (`uint8`), `flag` (`uint16`), `reference_id`
+    /// (`int32`, -1 if unset), `mapped_length` (`int32`, reference bases
+    /// spanned, 0 for unmapped reads) and `qname` (list of `str`). The
+    /// reader's filter applies; arrays are empty once the reader is
+    /// exhausted.
+    fn next_batch<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let records = self.next_raw(py, self.chunk_size)?;
+        let n = records.len();
+        let (mut pos, mut mapq, mut flag) = (
+            Vec::with_capacity(n),
+            Vec::with_capacity(n),
+            Vec::with_capacity(n),
+        );
+        let (mut reference_id, mut mapped_length, mut qname) = (
+            Vec::with_capacity(n),
+            Vec::with_capacity(n),
+            Vec::with_capacity(n),
+        );
+        for rec in &records {
+            let row = StructRow::from_record(rec);
+            pos.push(row.pos);
+            mapq.push(row.mapq);
+            flag.push(row.flag);
+            reference_id.push(row.reference_id);
+            mapped_length.push(row.mapped_length);
+            qname.push(rec.name().map(|name| name.to_string()).unwrap_or_default());
+        }
+
+        let batch = PyDict::new(py);
+        batch.set_item("pos", PyArray1::from_vec(py, pos))?;
+        batch.set_item("mapq", PyArray1::from_vec(py, mapq))?;
+        batch.set_item("flag", PyArray1::from_vec(py, flag))?;
+        batch.set_item("reference_id", PyArray1::from_vec(py, reference_id))?;
+        batch.set_item("mapped_length", PyArray1::from_vec(py, mapped_length))?;
+        batch.set_item("qname", qname)?;
+        Ok(batch)
+    }
+
+    /// `next_batch` と同じ項目を 1 つの numpy 構造化配列で返す
+    ///
+    /// The dtype is packed `[("pos", "<i8"), ("mapq", "u1"), ("flag",
+    /// "<u2"), ("reference_id", "<i4"), ("mapped_length", "<i4")]` with the
+    /// same values as `next_batch`. The reader's filter applies; the array
+    /// is empty once the reader is exhausted.
+    fn next_struct<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyArray1<StructRow>>> {
+        let records = self.next_raw(py, self.chunk_size)?;
+        let rows: Vec<StructRow> = records.iter().map(StructRow::from_record).collect();
+        Ok(PyArray1::from_vec(py, rows))
+    }
+
+    /// プライマリリードの配列を FASTA に書き出す
+    ///
+    /// Secondary and supplementary records are skipped. Reverse-strand
+    /// reads are reverse-complemented back to their sequenced orientation
+    /// unless `reverse_complement` is false. Each entry is a single line.
+    /// Returns the number of entries written.
+    #[pyo3(signature = (output_path, reverse_complement=true))]
+    fn write_fasta(
+        &self,
+        py: Python<'_>,
+        output_path: &str,
+        reverse_complement: bool,
+    ) -> PyResult<u64> {
+        let file = std::fs::File::create(output_path)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        let mut out = io::BufWriter::new(file);
+        let mut written = 0u64;
+        let mut result = Ok(());
+        self.scan_while(py, |rec| {
+            let flags = rec.flags();
+            if flags.is_secondary() || flags.is_supplementary() {
+                return true;
+            }
+            let mut seq: Vec<u8> = rec.sequence().iter().collect();
+            if reverse_complement && flags.is_reverse_complemented() {
+                seq.reverse();
+                for base in seq.iter_mut() {
+                    *base = iupac_complement(*base);
+                }
+            }
+            let name = rec.name().map(|n| n.to_vec()).unwrap_or_default();
+            result = out
+                .write_all(b">")
+                .and_then(|_| out.write_all(&name))
+                .and_then(|_| out.write_all(b"\n"))
+                .and_then(|_| out.write_all(&seq))
+                .and_then(|_| out.write_all(b"\n"));
+            written += 1;
+            result.is_ok()
+        })?;
+        result
+            .and_then(|_| out.flush())
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        Ok(written)
+    }
+
+    /// 指定した列を pyarrow の RecordBatch として `chunk_size` 件ずつ返す
+    ///
+    /// `columns` is any subset of `qname`, `flag`, `rid`, `rname`, `pos`,
+    /// `mapq`, `len`, `cigar_string`, `seq` and `tlen`, in output order.
+    /// `pos` is 1-based like `PyBamRecord.pos`; a missing name, reference,
+    /// position, MAPQ (255), CIGAR or sequence is null. Reads from the start of the file,
+    /// independent of the reader's position. Needs `pyarrow`.
+    fn to_arrow_batches(
+        &self,
+        py: Python<'_>,
+        columns: Vec<String>,
+    ) -> PyResult<ArrowBatchIterator> {
+        let columns = columns
+            .iter()
+            .map(|name| {
+                RecordColumn::from_name(name).ok_or_else(|| {
+                    PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "unknown column: {} (expected one of {})",
+                        name,
+                        RecordColumn::NAMES.join(", ")
+                    ))
+                })
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        // pyarrow が無ければ最初のバッチを読む前に ImportError にする
+        py.import("pyarrow")?;
+        Ok(ArrowBatchIterator::new(
+            self.open_scan_reader()?,
+            self.header.clone(),
+            columns,
+            self.chunk_size,
+            self.max_record_size,
+        ))
+    }
+
+    /// スカラー項目とタグを Parquet ファイルに書き出す
+    ///
+    /// `fields` takes the same column names as `to_arrow_batches` (default:
+    /// `qname`, `flag`, `rid`, `pos`, `mapq` and `len`), but missing values
+    /// are written as the getters' sentinels (-1, 255, "") instead of
+    /// nulls; `tags` adds one nullable column per
+    /// tag. Records are streamed from the start of the file in row groups of
+    /// the reader's `chunk_size`. Returns the number of rows written.
+    #[pyo3(signature = (output_path, fields=None, tags=None))]
+    fn write_parquet(
+        &self,
+        py: Python<'_>,
+        output_path: &str,
+        fields: Option<Vec<String>>,
+        tags: Option<Vec<String>>,
+    ) -> PyResult<u64> {
+        let fields = match fields {
+            Some(names) => names
+                .iter()
+                .map(|name| {
+                    RecordColumn::from_name(name).ok_or_else(|| {
+                        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                            "unknown field: {} (expected one of {})",
+                            name,
+                            RecordColumn::NAMES.join(", ")
+                        ))
+                    })
+                })
+                .collect::<PyResult<Vec<_>>>()?,
+            None => PyBamRecord::SCALAR_FIELDS
+                .iter()
+                .filter_map(|name| RecordColumn::from_name(name))
+                .collect(),
+        };
+        let tags = tags
+            .unwrap_or_default()
+            .into_iter()
+            .map(|t| {
+                record_override::convert_string_to_tag(t)
+                    .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+
+        let mut reader = self.open_scan_reader()?;
+        let header = self.header.clone();
+        let max_record_size = self.max_record_size;
+        let batch_size = self.chunk_size;
+        py.allow_threads(|| {
+            parquet_export::write_parquet(
+                &mut reader,
+                max_record_size,
+                &header,
+                output_path,
+                &fields,
+                &tags,
+                batch_size,
+            )
+        })
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::InvalidData if !is_record_too_large(&e) => {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string())
+            }
+            _ => read_error_to_py(e),
+        })
+    }
+
+    /// 実際のレコード順が `order` どおりか走査して確かめる
+    ///
+    /// `order` is `"coordinate"` (reference id, then position; unplaced
+    /// records last) or `"queryname"` (byte-wise qname order). Returns
+    /// `(index, qname)` of the first record that breaks the order, or `None`.
+    #[pyo3(signature = (order="coordinate"))]
+    fn verify_sorted(&self, py: Python<'_>, order: &str) -> PyResult<Option<(usize, String)>> {
+        let by_name = match order {
+            "coordinate" => false,
+            "queryname" => true,
+            _ => {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "order must be 'coordinate' or 'queryname', got '{}'",
+                    order
+                )))
+            }
+        };
+
+        let mut index = 0usize;
+        let mut violation: Option<(usize, String)> = None;
+        let mut prev_coord: Option<(usize, usize)> = None;
+        let mut prev_name: Option<Vec<u8>> = None;
+        self.scan_while(py, |rec| {
+            let name = rec.name().map(|n| n.to_vec()).unwrap_or_default();
+            let in_order = if by_name {
+                let ok = prev_name.as_ref().is_none_or(|prev| *prev <= name);
+                prev_name = Some(name.clone());
+                ok
+            } else {
+                let rid = match rec.reference_sequence_id() {
+                    Some(Ok(rid)) => rid,
+                    _ => usize::MAX,
+                };
+                let pos = match rec.alignment_start() {
+                    Some(Ok(pos)) => usize::from(pos),
+                    _ => 0,
+                };
+                let ok = prev_coord.is_none_or(|prev| prev <= (rid, pos));
+                prev_coord = Some((rid, pos));
+                ok
+            };
+            if !in_order {
+                violation = Some((index, String::from_utf8_lossy(&name).into_owned()));
+                return false;
+            }
+            index += 1;
+            true
+        })?;
+        Ok(violation)
+    }
+
+    /// サイクル位置 × 品質ビン × 塩基の出現数を数える
+    ///
+    /// Returns an `int64` array of shape `(max_read_len, n_qual_bins, 5)`
+    /// with bases ordered `A, C, G, T, N`. Positions are sequencing cycles:
+    /// reverse-strand reads are reverse-complemented first. Quality scores
+    /// 0..=93 are split into `n_qual_bins` equal-width bins. Bases past
+    /// `max_read_len` and reads without qualities are skipped.
+    fn quality_base_matrix<'py>(
+        &self,
+        py: Python<'py>,
+        max_read_len: usize,
+        n_qual_bins: usize,
+    ) -> PyResult<Bound<'py, PyArray3<i64>>> {
+        if max_read_len == 0 || n_qual_bins == 0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "max_read_len and n_qual_bins must be positive",
+            ));
+        }
+        const N_BASES: usize = 5;
+        let mut counts = vec![0i64; max_read_len * n_qual_bins * N_BASES];
+        self.scan(py, |rec| {
+            let quals = rec.quality_scores();
+            let quals = quals.as_ref();
+            if quals.is_empty() || quals[0] == 0xff {
+                return;
+            }
+            let reverse = rec.flags().is_reverse_complemented();
+            let len = quals.len();
+            for (i, (base, &q)) in rec.sequence().iter().zip(quals).enumerate() {
+                let cycle = if reverse { len - 1 - i } else { i };
+                if cycle >= max_read_len {
+                    continue;
+                }
+                let base = match (base.to_ascii_uppercase(), reverse) {
+                    (b'A', false) | (b'T', true) => 0,
+                    (b'C', false) | (b'G', true) => 1,
+                    (b'G', false) | (b'C', true) => 2,
+                    (b'T', false) | (b'A', true) => 3,
+                    _ => 4,
+                };
+                let bin = (q.min(93) as usize * n_qual_bins / 94).min(n_qual_bins - 1);
+                counts[(cycle * n_qual_bins + bin) * N_BASES + base] += 1;
+            }
+        })?;
+        PyArray1::from_vec(py, counts).reshape([max_read_len, n_qual_bins, N_BASES])
+    }
+
+    /// 深さ `min_depth` 以上で覆われた参照塩基数を数える
+    ///
+    /// Counts over `reference_name` only, or over the whole genome when
+    /// `None`. Requires a coordinate-sorted BAM; memory use is bounded by
+    /// the span of overlapping reads. `min_depth` must be at least 1.
+    #[pyo3(signature = (reference_name=None, min_depth=1))]
+    fn covered_bases(
+        &self,
+        py: Python<'_>,
+        reference_name: Option<&str>,
+        min_depth: i64,
+    ) -> PyResult<u64> {
+        // 深さ 0 以下では未被覆の区間を数えられない
+        if min_depth < 1 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "min_depth must be positive",
+            ));
+        }
+        let target = self.resolve_reference_id(reference_name)?;
+
+        let mut covered = 0u64;
+        let mut current: Option<usize> = None;
+        let mut sweep = DepthSweep::default();
+        let mut unsorted = false;
+        let mut count = |start: i64, end: i64, depth: i64| {
+            if depth >= min_depth {
+                covered += (end - start) as u64;
+            }
+        };
+        self.scan_while(py, |rec| {
+            if !coverage::counts_toward_depth(rec.flags()) {
+                return true;
+            }
+            let Some(Ok(rid)) = rec.reference_sequence_id() else {
+                return true;
+            };
+            if target.is_some_and(|t| t != rid) {
+                return true;
+            }
+            let Some((start, _)) = alignment::reference_span(rec) else {
+                return true;
+            };
+            if current != Some(rid) {
+                sweep.finish(&mut count);
+                sweep = DepthSweep::default();
+                current = Some(rid);
+            } else if start < sweep.flushed_to() {
+                unsorted = true;
+                return false;
+            }
+            sweep.advance(start, &mut count);
+            let ops: Vec<Op> = rec.cigar().iter().filter_map(Result::ok).collect();
+            for (block_start, block_end) in alignment::aligned_blocks(&ops, start) {
+                sweep.add_block(block_start, block_end);
+            }
+            true
+        })?;
+        sweep.finish(&mut count);
+
+        if unsorted {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "covered_bases requires a coordinate-sorted BAM",
+            ));
+        }
+        Ok(covered)
+    }
+
+    /// 参照配列ごとのカバレッジ要約を 1 回のスキャンで求める
+    ///
+    /// Returns `{reference_name: {"length", "mean", "breadth",
+    /// "fraction_1x", "fraction_10x"}}` for every reference in the header,
+    /// where `breadth` is the number of bases at depth >= 1. Depth follows
+    /// the same rules as `covered_bases` and is swept from aligned blocks
+    /// without per-base arrays. Requires a coordinate-sorted BAM.
+    fn coverage_summary<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        // 参照配列ごとの (深さ x 塩基数の合計, 1x 以上の塩基数, 10x 以上の塩基数)
+        let mut totals = vec![[0u64; 3]; self.header.reference_sequences().len()];
+        let add = |acc: &mut [u64; 3], start: i64, end: i64, depth: i64| {
+            let len = (end - start) as u64;
+            acc[0] += len * depth as u64;
+            if depth >= 1 {
+                acc[1] += len;
+            }
+            if depth >= 10 {
+                acc[2] += len;
+            }
+        };
+
+        let mut current: Option<usize> = None;
+        let mut sweep = DepthSweep::default();
+        let mut unsorted = false;
+        self.scan_while(py, |rec| {
+            if !coverage::counts_toward_depth(rec.flags()) {
+                return true;
+            }
+            let Some(Ok(rid)) = rec.reference_sequence_id() else {
+                return true;
+            };
+            if rid >= totals.len() {
+                return true;
+            }
+            let Some((start, _)) = alignment::reference_span(rec) else {
+                return true;
+            };
+            if current != Some(rid) {
+                if let Some(prev) = current {
+                    sweep.finish(|s, e, d| add(&mut totals[prev], s, e, d));
+                }
+                sweep = DepthSweep::default();
+                current = Some(rid);
+            } else if start < sweep.flushed_to() {
+                unsorted = true;
+                return false;
+            }
+            sweep.advance(start, |s, e, d| add(&mut totals[rid], s, e, d));
+            let ops: Vec<Op> = rec.cigar().iter().filter_map(Result::ok).collect();
+            for (block_start, block_end) in alignment::aligned_blocks(&ops, start) {
+                sweep.add_block(block_start, block_end);
+            }
+            true
+        })?;
+        if let Some(prev) = current {
+            sweep.finish(|s, e, d| add(&mut totals[prev], s, e, d));
+        }
+
+        if unsorted {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "coverage_summary requires a coordinate-sorted BAM",
+            ));
+        }
+        let summary = PyDict::new(py);
+        for ((name, reference), acc) in self.header.reference_sequences().iter().zip(&totals) {
+            let length = usize::from(reference.length()) as u64;
+            let fraction = |n: u64| n as f64 / length as f64;
+            let entry = PyDict::new(py);
+            entry.set_item("length", length)?;
+            entry.set_item("mean", fraction(acc[0]))?;
+            entry.set_item("breadth", acc[1])?;
+            entry.set_item("fraction_1x", fraction(acc[1]))?;
+            entry.set_item("fraction_10x", fraction(acc[2]))?;
+            summary.set_item(name.to_string(), entry)?;
+        }
+        Ok(summary)
     }
 
     fn __enter__(slf: PyRefMut<'_, Self>) -> PyRefMut<'_, Self> {
@@ -116,54 +2342,15 @@ impl BamReader {
 
     /// chunk_size ごとにレコードを返す
     fn __next__(mut slf: PyRefMut<'_, Self>, py: Python<'_>) -> PyResult<Option<Vec<Py<PyAny>>>> {
-        // --- region_records を一度だけクローンしてローカルに逃がす
-        let region_opt: Option<Arc<Vec<bam::Record>>> = slf.region_records.clone();
-
-        if let Some(records) = region_opt {
-            let start = slf.region_pos;
-            if start >= records.len() {
-                return Ok(None);
-            }
-            let end = (start + slf.chunk_size).min(records.len());
-            slf.region_pos = end;
-
-            let slice = &records[start..end];
-            let mut out = Vec::with_capacity(slice.len());
-            for rec in slice.iter().cloned() {
-                let obj: Py<PyAny> = Py::new(py, PyBamRecord::from_record(rec))
-                    .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?
-                    .into();
-                out.push(obj);
-            }
-            return Ok(Some(out));
-        }
-
-        // シーケンシャルモード
-        let reader_arc = slf.reader.as_ref().unwrap().clone();
         let chunk = slf.chunk_size;
-        let raw_recs: Vec<bam::Record> = py.allow_threads(move || {
-            let mut guard = reader_arc.lock().unwrap();
-            let mut v = Vec::with_capacity(chunk);
-            for _ in 0..chunk {
-                let mut rec = bam::Record::default();
-                match guard.read_record(&mut rec) {
-                    Ok(0) => break,
-                    Ok(_) => v.push(rec),
-                    Err(e) => {
-                        eprintln!("Error reading BAM record: {}", e);
-                        break;
-                    }
-                }
-            }
-            v
-        });
+        let raw_recs = slf.next_raw(py, chunk)?;
 
         if raw_recs.is_empty() {
             Ok(None)
         } else {
             let mut out = Vec::with_capacity(raw_recs.len());
             for rec in raw_recs {
-                let obj: Py<PyAny> = Py::new(py, PyBamRecord::from_record(rec))
+                let obj: Py<PyAny> = Py::new(py, slf.make_record(rec))
                     .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?
                     .into();
                 out.push(obj);
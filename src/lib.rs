@@ -1,22 +1,62 @@
 use pyo3::prelude::*;
+mod alignment;
+mod arrow_export;
+mod columns;
+mod consensus;
+mod coverage;
+mod cram;
+mod dedup;
+mod downsample;
+mod filter;
+mod interleave;
+mod intervals;
 mod iterator;
 mod merge_bams;
+mod modifications;
+mod parquet_export;
 mod record;
 mod record_buf;
 mod record_override;
+mod records;
+mod reference;
+mod region_query;
+mod sam_lines;
+mod sliding;
+mod sort_window;
+mod source;
+mod struct_row;
+mod tag_value;
+mod transform;
 mod write;
 mod write_bams;
+mod writer;
 
 /// A Python module implemented in Rust.
 #[pymodule(name = "lazybam")]
 fn lazybam(py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<iterator::BamReader>()?;
+    m.add_class::<cram::CramReader>()?;
     m.add_class::<record::PyBamRecord>()?;
     m.add_class::<record_override::RecordOverride>()?;
     m.add_class::<record_buf::PyRecordBuf>()?;
+    m.add_class::<sort_window::SortWindowIterator>()?;
+    m.add_class::<sliding::SlidingIterator>()?;
+    m.add_class::<sam_lines::SamLinesIterator>()?;
+    m.add_class::<records::RecordIterator>()?;
+    m.add_class::<region_query::RegionIterator>()?;
+    m.add_class::<arrow_export::ArrowBatchIterator>()?;
+    m.add_class::<intervals::IntervalSet>()?;
+    m.add_class::<writer::BamWriter>()?;
+    m.add_class::<interleave::BamInterleaver>()?;
     m.add_function(wrap_pyfunction!(write::write_chunk_py, m)?)?;
     m.add_function(wrap_pyfunction!(write::write_recordbuf_chunk_py, m)?)?;
     m.add_function(wrap_pyfunction!(write::merge_chunks_py, m)?)?;
+    m.add_function(wrap_pyfunction!(consensus::build_consensus_read, m)?)?;
+    m.add_function(wrap_pyfunction!(consensus::merge_pair, m)?)?;
+    m.add_function(wrap_pyfunction!(transform::transform, m)?)?;
+    m.add_function(wrap_pyfunction!(transform::tag_all, m)?)?;
+    m.add_function(wrap_pyfunction!(dedup::deduplicate, m)?)?;
+    m.add_function(wrap_pyfunction!(downsample::downsample_bam, m)?)?;
 
     m.add("__doc__", "Rust powered BAM reader built on noodles + PyO3")?;
 
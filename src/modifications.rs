@@ -0,0 +1,170 @@
+//! Parsing of base modification calls from `MM` / `ML` tags.
+//!
+//! `MM` positions count occurrences of the canonical base along the
+//! *original* read, i.e. the reverse complement of `SEQ` for reverse-strand
+//! reads. Calls are reported against the stored `SEQ` orientation so they
+//! can be mapped to the reference with the CIGAR directly.
+
+use noodles::bam;
+use noodles::sam::alignment::record::data::field::value::Array;
+use noodles::sam::alignment::record::data::field::{Tag, Value as BamValue};
+
+/// A single modification call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModCall {
+    /// 0-based offset in the stored `SEQ`.
+    pub query_pos: usize,
+    /// Canonical base as written in `MM` (`A`, `C`, `G`, `T` or `N`).
+    pub canonical: u8,
+    /// `'+'` or `'-'`.
+    pub strand: char,
+    /// Modification code, e.g. `"m"` or a ChEBI id such as `"76792"`.
+    pub code: String,
+    /// `ML` likelihood (0-255); implicit calls carry 0.
+    pub prob: u8,
+    /// `false` for bases skipped in `.` mode, which are implicitly
+    /// unmodified.
+    pub explicit: bool,
+}
+
+fn complement(base: u8) -> u8 {
+    match base.to_ascii_uppercase() {
+        b'A' => b'T',
+        b'C' => b'G',
+        b'G' => b'C',
+        b'T' => b'A',
+        other => other,
+    }
+}
+
+/// Split the codes of an `MM` entry header (`"mh"` → `["m", "h"]`, a ChEBI
+/// id stays whole).
+fn split_codes(codes: &str) -> Vec<String> {
+    if codes.bytes().all(|b| b.is_ascii_digit()) {
+        vec![codes.to_string()]
+    } else {
+        codes.chars().map(|c| c.to_string()).collect()
+    }
+}
+
+/// Parse `mm` / `ml` against the stored sequence `seq`.
+pub fn parse(seq: &[u8], reverse: bool, mm: &str, ml: &[u8]) -> Result<Vec<ModCall>, String> {
+    // 元のリードの向きでの配列
+    let original: Vec<u8> = if reverse {
+        seq.iter().rev().map(|&b| complement(b)).collect()
+    } else {
+        seq.iter().map(|b| b.to_ascii_uppercase()).collect()
+    };
+    let to_stored = |i: usize| if reverse { seq.len() - 1 - i } else { i };
+
+    let mut calls = Vec::new();
+    let mut ml_pos = 0usize;
+    for entry in mm.split(';').map(str::trim).filter(|e| !e.is_empty()) {
+        let mut parts = entry.split(',');
+        let head = parts.next().unwrap_or_default();
+        let bytes = head.as_bytes();
+        if bytes.len() < 3 {
+            return Err(format!("invalid MM entry: {}", entry));
+        }
+        let canonical = bytes[0].to_ascii_uppercase();
+        let strand = bytes[1] as char;
+        if !b"ACGTN".contains(&canonical) || !matches!(strand, '+' | '-') {
+            return Err(format!("invalid MM entry: {}", entry));
+        }
+        let (codes, implicit) = match head[2..].strip_suffix('?') {
+            Some(codes) => (codes, false),
+            None => (head[2..].strip_suffix('.').unwrap_or(&head[2..]), true),
+        };
+        let codes = split_codes(codes);
+        if codes.is_empty() {
+            return Err(format!("invalid MM entry: {}", entry));
+        }
+        let deltas = parts
+            .map(|d| d.parse::<usize>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| format!("invalid MM entry: {}", entry))?;
+
+        // 読み上での対象塩基 (`-` 鎖は相補塩基を数える)
+        let target = if strand == '-' {
+            complement(canonical)
+        } else {
+            canonical
+        };
+        let mut candidates = original
+            .iter()
+            .enumerate()
+            .filter(|&(_, &b)| target == b'N' || b == target)
+            .map(|(i, _)| i);
+
+        let emit_implicit = |i: usize, calls: &mut Vec<ModCall>| {
+            for code in &codes {
+                calls.push(ModCall {
+                    query_pos: to_stored(i),
+                    canonical,
+                    strand,
+                    code: code.clone(),
+                    prob: 0,
+                    explicit: false,
+                });
+            }
+        };
+        for delta in deltas {
+            for _ in 0..delta {
+                let Some(i) = candidates.next() else {
+                    return Err(format!("MM entry runs past the read: {}", entry));
+                };
+                if implicit {
+                    emit_implicit(i, &mut calls);
+                }
+            }
+            let Some(i) = candidates.next() else {
+                return Err(format!("MM entry runs past the read: {}", entry));
+            };
+            for code in &codes {
+                let Some(&prob) = ml.get(ml_pos) else {
+                    return Err("ML has fewer values than MM calls".to_string());
+                };
+                ml_pos += 1;
+                calls.push(ModCall {
+                    query_pos: to_stored(i),
+                    canonical,
+                    strand,
+                    code: code.clone(),
+                    prob,
+                    explicit: true,
+                });
+            }
+        }
+        if implicit {
+            for i in candidates {
+                emit_implicit(i, &mut calls);
+            }
+        }
+    }
+    Ok(calls)
+}
+
+/// Modification calls of `rec`, or an empty list when it has no `MM` tag
+/// (`Mm` / `Ml` from older writers are accepted too).
+pub fn base_modifications(rec: &bam::Record) -> Result<Vec<ModCall>, String> {
+    let data = rec.data();
+    let string_tag = |tag: Tag| match data.get(&tag) {
+        Some(Ok(BamValue::String(s))) => Some(String::from_utf8_lossy(s).into_owned()),
+        _ => None,
+    };
+    let array_tag = |tag: Tag| match data.get(&tag) {
+        Some(Ok(BamValue::Array(Array::UInt8(a)))) => {
+            Some(a.iter().filter_map(Result::ok).collect::<Vec<u8>>())
+        }
+        _ => None,
+    };
+    let Some(mm) = string_tag(Tag::BASE_MODIFICATIONS).or_else(|| string_tag(Tag::new(b'M', b'm')))
+    else {
+        return Ok(Vec::new());
+    };
+    let ml = array_tag(Tag::BASE_MODIFICATION_PROBABILITIES)
+        .or_else(|| array_tag(Tag::new(b'M', b'l')))
+        .unwrap_or_default();
+    let seq: Vec<u8> = rec.sequence().iter().collect();
+    parse(&seq, rec.flags().is_reverse_complemented(), &mm, &ml)
+}
@@ -0,0 +1,260 @@
+//! Export of per-record scalar fields and tags to Parquet.
+//!
+//! Scalar columns mirror the `PyBamRecord` getters, with the same
+//! sentinels for missing values. Tag columns are typed
+//! from the first batch: integer tags become `int64`, float tags
+//! `float64` and everything else (characters, strings, hex, arrays) `utf8`.
+//! A tag missing from the whole first batch is written as `utf8`. An
+//! integer in a `float64` column is widened; any other type change in a
+//! later batch is an error and no file is left at the output path.
+
+use arrow_array::builder::{Float64Builder, Int64Builder, StringBuilder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema};
+use noodles::sam::alignment::record::data::field::value::Array;
+use noodles::sam::alignment::record::data::field::{Tag, Value as BamValue};
+use noodles::{bam, sam};
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::io::{self, BufRead};
+use std::sync::Arc;
+
+use crate::columns::{Missing, RecordColumn};
+use crate::iterator::read_record_limited;
+
+/// タグ値の列型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum TagKind {
+    Int,
+    Float,
+    Text,
+}
+
+/// 1 つのタグ値を列に入れられる形にしたもの
+pub(crate) enum TagScalar {
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl TagScalar {
+    pub(crate) fn kind(&self) -> TagKind {
+        match self {
+            TagScalar::Int(_) => TagKind::Int,
+            TagScalar::Float(_) => TagKind::Float,
+            TagScalar::Text(_) => TagKind::Text,
+        }
+    }
+
+    pub(crate) fn text(&self) -> String {
+        match self {
+            TagScalar::Int(n) => n.to_string(),
+            TagScalar::Float(f) => f.to_string(),
+            TagScalar::Text(s) => s.clone(),
+        }
+    }
+}
+
+fn join_array<T: ToString>(values: impl Iterator<Item = io::Result<T>>) -> String {
+    values
+        .filter_map(Result::ok)
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub(crate) fn tag_scalar(rec: &bam::Record, tag: Tag) -> Option<TagScalar> {
+    let data = rec.data();
+    let value = data.get(&tag)?.ok()?;
+    Some(match value {
+        BamValue::Int8(n) => TagScalar::Int(n.into()),
+        BamValue::UInt8(n) => TagScalar::Int(n.into()),
+        BamValue::Int16(n) => TagScalar::Int(n.into()),
+        BamValue::UInt16(n) => TagScalar::Int(n.into()),
+        BamValue::Int32(n) => TagScalar::Int(n.into()),
+        BamValue::UInt32(n) => TagScalar::Int(n.into()),
+        BamValue::Float(f) => TagScalar::Float(f.into()),
+        BamValue::Character(c) => TagScalar::Text((c as char).to_string()),
+        BamValue::String(s) | BamValue::Hex(s) => {
+            TagScalar::Text(String::from_utf8_lossy(s).into_owned())
+        }
+        BamValue::Array(arr) => TagScalar::Text(match arr {
+            Array::Int8(a) => join_array(a.iter()),
+            Array::UInt8(a) => join_array(a.iter()),
+            Array::Int16(a) => join_array(a.iter()),
+            Array::UInt16(a) => join_array(a.iter()),
+            Array::Int32(a) => join_array(a.iter()),
+            Array::UInt32(a) => join_array(a.iter()),
+            Array::Float(a) => join_array(a.iter()),
+        }),
+    })
+}
+
+fn tag_column(records: &[bam::Record], tag: Tag, kind: TagKind) -> io::Result<ArrayRef> {
+    let mismatch = |found: TagKind| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "tag {}{} changes type from {:?} to {:?}",
+                tag.as_ref()[0] as char,
+                tag.as_ref()[1] as char,
+                kind,
+                found
+            ),
+        )
+    };
+    Ok(match kind {
+        TagKind::Int => {
+            let mut b = Int64Builder::new();
+            for rec in records {
+                match tag_scalar(rec, tag) {
+                    None => b.append_null(),
+                    Some(TagScalar::Int(n)) => b.append_value(n),
+                    Some(other) => return Err(mismatch(other.kind())),
+                }
+            }
+            Arc::new(b.finish())
+        }
+        TagKind::Float => {
+            let mut b = Float64Builder::new();
+            for rec in records {
+                match tag_scalar(rec, tag) {
+                    None => b.append_null(),
+                    Some(TagScalar::Float(f)) => b.append_value(f),
+                    Some(TagScalar::Int(n)) => b.append_value(n as f64),
+                    Some(other) => return Err(mismatch(other.kind())),
+                }
+            }
+            Arc::new(b.finish())
+        }
+        TagKind::Text => {
+            let mut b = StringBuilder::new();
+            for rec in records {
+                match tag_scalar(rec, tag) {
+                    None => b.append_null(),
+                    Some(v) => b.append_value(v.text()),
+                }
+            }
+            Arc::new(b.finish())
+        }
+    })
+}
+
+/// Stream every remaining record of `reader` into a Parquet file at
+/// `path`, one row group per `batch_size` records. Returns the row count.
+///
+/// The file is written to `<path>.partial` and renamed on success; on
+/// error the partial file is removed and `path` is left untouched.
+pub fn write_parquet<R: BufRead>(
+    reader: &mut bam::io::Reader<R>,
+    max_record_size: usize,
+    header: &sam::Header,
+    path: &str,
+    fields: &[RecordColumn],
+    tags: &[Tag],
+    batch_size: usize,
+) -> io::Result<u64> {
+    let partial_path = format!("{path}.partial");
+    let result = write_parquet_to(
+        reader,
+        max_record_size,
+        header,
+        &partial_path,
+        fields,
+        tags,
+        batch_size,
+    )
+    .and_then(|rows| std::fs::rename(&partial_path, path).map(|_| rows));
+    if result.is_err() {
+        // 途中まで書いたファイルを残さない
+        let _ = std::fs::remove_file(&partial_path);
+    }
+    result
+}
+
+fn write_parquet_to<R: BufRead>(
+    reader: &mut bam::io::Reader<R>,
+    max_record_size: usize,
+    header: &sam::Header,
+    path: &str,
+    fields: &[RecordColumn],
+    tags: &[Tag],
+    batch_size: usize,
+) -> io::Result<u64> {
+    let batch_size = batch_size.max(1);
+    let mut records: Vec<bam::Record> = Vec::with_capacity(batch_size);
+    let mut schema: Option<(Arc<Schema>, Vec<TagKind>)> = None;
+    let mut writer: Option<ArrowWriter<File>> = None;
+    let mut rows = 0u64;
+
+    loop {
+        records.clear();
+        while records.len() < batch_size {
+            let mut rec = bam::Record::default();
+            if read_record_limited(reader, &mut rec, max_record_size)? == 0 {
+                break;
+            }
+            records.push(rec);
+        }
+
+        // 最初のバッチでタグ列の型を決める
+        let (schema, kinds) = schema.get_or_insert_with(|| {
+            let kinds: Vec<TagKind> = tags
+                .iter()
+                .map(|&tag| {
+                    records
+                        .iter()
+                        .find_map(|rec| tag_scalar(rec, tag))
+                        .map(|v| v.kind())
+                        .unwrap_or(TagKind::Text)
+                })
+                .collect();
+            let mut columns: Vec<Field> =
+                fields.iter().map(|f| f.field(Missing::Sentinel)).collect();
+            for (tag, kind) in tags.iter().zip(&kinds) {
+                let name = String::from_utf8_lossy(tag.as_ref()).into_owned();
+                let data_type = match kind {
+                    TagKind::Int => DataType::Int64,
+                    TagKind::Float => DataType::Float64,
+                    TagKind::Text => DataType::Utf8,
+                };
+                columns.push(Field::new(name, data_type, true));
+            }
+            (Arc::new(Schema::new(columns)), kinds)
+        });
+        if writer.is_none() {
+            let props = WriterProperties::builder()
+                .set_max_row_group_size(batch_size)
+                .build();
+            let file = File::create(path)?;
+            writer = Some(
+                ArrowWriter::try_new(file, schema.clone(), Some(props))
+                    .map_err(io::Error::other)?,
+            );
+        }
+        let writer = writer.as_mut().unwrap();
+        if records.is_empty() {
+            break;
+        }
+
+        let mut columns: Vec<ArrayRef> = fields
+            .iter()
+            .map(|f| f.column(&records, header, Missing::Sentinel))
+            .collect();
+        for (&tag, &kind) in tags.iter().zip(kinds.iter()) {
+            columns.push(tag_column(&records, tag, kind)?);
+        }
+        let batch = RecordBatch::try_new(schema.clone(), columns).map_err(io::Error::other)?;
+        writer.write(&batch).map_err(io::Error::other)?;
+        rows += records.len() as u64;
+        if records.len() < batch_size {
+            break;
+        }
+    }
+
+    if let Some(writer) = writer {
+        writer.close().map_err(io::Error::other)?;
+    }
+    Ok(rows)
+}
@@ -1,20 +1,91 @@
-use numpy::PyArray1;
+use numpy::{PyArray1, PyArray2, PyArrayMethods};
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 use pyo3::IntoPyObjectExt;
+use std::collections::HashSet;
+use std::sync::Arc;
 
-use noodles::sam::alignment::record::data::field::value::Array;
 use noodles::sam::alignment::record::data::field::Tag;
 use noodles::sam::alignment::record_buf::{Cigar, Data, QualityScores, Sequence as SeqBuf};
 use noodles::sam::alignment::{
-    record::Flags, //, MappingQuality
+    record::{Flags, MappingQuality},
     RecordBuf,
 };
 use noodles::{bam, core::Position, sam};
-use sam::alignment::record::cigar::op::Op;
+use sam::alignment::io::Write as _;
+use sam::alignment::record::cigar::op::{Kind, Op};
 use sam::alignment::record::data::field::Value as BamValue;
 use sam::alignment::record::Cigar as _;
+use sam::alignment::Record as _;
 
-use crate::record_override::RecordOverride;
+use crate::alignment;
+use crate::modifications;
+use crate::record_override::{self, RecordOverride};
+use crate::reference;
+use crate::tag_value::{self, TagConversion};
+
+/// 任意のアラインメントレコード (RecordBuf, CRAM など) を BAM レコードに変換する
+pub(crate) fn encode_bam_record(
+    header: &sam::Header,
+    record: &dyn sam::alignment::Record,
+) -> std::io::Result<bam::Record> {
+    let mut writer = bam::io::Writer::from(Vec::new());
+    writer.write_alignment_record(header, record)?;
+    let mut reader = bam::io::Reader::from(writer.get_ref().as_slice());
+    let mut bam_record = bam::Record::default();
+    reader.read_record(&mut bam_record)?;
+    Ok(bam_record)
+}
+
+/// IUPAC 塩基の相補塩基 (大文字・小文字は保持、未知の文字はそのまま)
+pub(crate) fn iupac_complement(base: u8) -> u8 {
+    let upper = match base.to_ascii_uppercase() {
+        b'A' => b'T',
+        b'T' | b'U' => b'A',
+        b'C' => b'G',
+        b'G' => b'C',
+        b'R' => b'Y',
+        b'Y' => b'R',
+        b'K' => b'M',
+        b'M' => b'K',
+        b'B' => b'V',
+        b'V' => b'B',
+        b'D' => b'H',
+        b'H' => b'D',
+        other => other,
+    };
+    if base.is_ascii_lowercase() {
+        upper.to_ascii_lowercase()
+    } else {
+        upper
+    }
+}
+
+/// 64-bit FNV-1a (プロセスや Rust のバージョンをまたいで安定)
+pub(crate) fn fnv1a64(fields: &[&[u8]]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for field in fields {
+        for &b in (field.len() as u32)
+            .to_le_bytes()
+            .iter()
+            .chain(field.iter())
+        {
+            hash ^= u64::from(b);
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+/// (read_pos, ref_pos, event, read_base, ref_base, qual)
+type AlignmentEvent = (
+    Option<usize>,
+    Option<i64>,
+    &'static str,
+    Option<char>,
+    Option<char>,
+    Option<u8>,
+);
 
 #[pyclass]
 #[derive(Clone, Copy, Debug)]
@@ -51,6 +122,9 @@ impl From<sam::alignment::record::cigar::op::Kind> for PyKind {
 pub struct PyBamRecord {
     record: bam::Record,
     record_override: Option<RecordOverride>,
+    /// 読み出し元のヘッダ (参照名の解決に使う)
+    header: Option<Arc<sam::Header>>,
+    tag_conversion: TagConversion,
 }
 
 impl PyBamRecord {
@@ -58,9 +132,136 @@ impl PyBamRecord {
         Self {
             record,
             record_override: None,
+            header: None,
+            tag_conversion: TagConversion::default(),
+        }
+    }
+
+    /// Scalar getters available through [`PyBamRecord::scalar_field`].
+    pub const SCALAR_FIELDS: [&'static str; 6] = ["qname", "flag", "rid", "pos", "mapq", "len"];
+
+    /// Value of the scalar getter named `field`.
+    pub fn scalar_field(&self, py: Python<'_>, field: &str) -> PyResult<PyObject> {
+        match field {
+            "qname" => self.qname().into_py_any(py),
+            "flag" => self.flag().into_py_any(py),
+            "rid" => self.rid().into_py_any(py),
+            "pos" => self.pos().into_py_any(py),
+            "mapq" => self.mapq().into_py_any(py),
+            "len" => self.len().into_py_any(py),
+            _ => Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "unknown field: {}",
+                field
+            ))),
         }
     }
 
+    /// Encode a `RecordBuf` into a BAM record and wrap it.
+    pub fn from_record_buf(header: Arc<sam::Header>, buf: &RecordBuf) -> std::io::Result<Self> {
+        let record = encode_bam_record(&header, buf)?;
+        Ok(Self::from_record(record).with_header(header))
+    }
+
+    /// The underlying BAM record (overrides are not applied).
+    pub fn record(&self) -> &bam::Record {
+        &self.record
+    }
+
+    /// Header attached by the reader, if any.
+    pub fn header(&self) -> Option<&Arc<sam::Header>> {
+        self.header.as_ref()
+    }
+
+    /// Attach the header the record was read with.
+    pub fn with_header(mut self, header: Arc<sam::Header>) -> Self {
+        self.header = Some(header);
+        self
+    }
+
+    /// Set how tag values are converted to Python objects.
+    pub fn with_tag_conversion(mut self, tag_conversion: TagConversion) -> Self {
+        self.tag_conversion = tag_conversion;
+        self
+    }
+
+    /// Decoded CIGAR ops; ops that fail to decode are skipped.
+    fn cigar_ops(&self) -> Vec<Op> {
+        self.record.cigar().iter().filter_map(Result::ok).collect()
+    }
+
+    /// `reference_fasta` のうちアラインメント範囲 (両端含む) の塩基
+    fn aligned_reference(&self, reference_fasta: &str) -> PyResult<Vec<u8>> {
+        let name = self.reference_name().ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>("record has no reference sequence name")
+        })?;
+        let (start, end) = match (
+            self.record.alignment_start().and_then(|r| r.ok()),
+            self.record.alignment_end().and_then(|r| r.ok()),
+        ) {
+            (Some(start), Some(end)) if !self.record.flags().is_unmapped() => (start, end),
+            _ => {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                    "record has no alignment span",
+                ))
+            }
+        };
+        let ref_bases = reference::fetch_bases(reference_fasta, &name, start, end)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+        Ok(ref_bases)
+    }
+
+    /// 末端のソフトクリップを除いた read 上の範囲 `(start, end)` (pysam の
+    /// `query_alignment_start` / `query_alignment_end` と同じ)
+    fn query_alignment_bounds(&self) -> (usize, usize) {
+        let ops = self.cigar_ops();
+        let soft_len = |op: &Op| match op.kind() {
+            Kind::SoftClip => Some(op.len()),
+            _ => None,
+        };
+        let left = ops
+            .iter()
+            .find(|op| op.kind() != Kind::HardClip)
+            .and_then(soft_len)
+            .unwrap_or(0);
+        let right = ops
+            .iter()
+            .rev()
+            .find(|op| op.kind() != Kind::HardClip)
+            .and_then(soft_len)
+            .unwrap_or(0);
+        let len = self.record.sequence().len();
+        let right = right.min(len - left.min(len));
+        let left = left.min(len);
+        (left, len - right)
+    }
+
+    /// Value of a `Z`-typed tag, if present.
+    fn string_tag(&self, tag: Tag) -> Option<String> {
+        match self.record.data().get(&tag) {
+            Some(Ok(BamValue::String(s))) => Some(String::from_utf8_lossy(s).into_owned()),
+            _ => None,
+        }
+    }
+
+    /// 0-based alignment start, if the record is mapped.
+    fn reference_start(&self) -> Option<i64> {
+        if self.record.flags().is_unmapped() {
+            return None;
+        }
+        let pos = self.record.alignment_start()?.ok()?;
+        Some(usize::from(pos) as i64 - 1)
+    }
+
+    /// Reference sequence name resolved against the attached header.
+    pub fn reference_name(&self) -> Option<String> {
+        let header = self.header.as_ref()?;
+        let rid = self.record.reference_sequence_id()?.ok()?;
+        header
+            .reference_sequences()
+            .get_index(rid)
+            .map(|(name, _)| name.to_string())
+    }
+
     /// Convert to RecordBuf, applying overrides
     pub fn to_record_buf(&self) -> anyhow::Result<RecordBuf> {
         // sequence & quality
@@ -98,7 +299,9 @@ impl PyBamRecord {
                 flag.remove(Flags::UNMAPPED);
             }
             if let Some(start) = ov.alignment_start {
-                position_opt = Some(Position::try_from(start as usize)?);
+                position_opt = Some(Position::try_from(start as usize).map_err(|_| {
+                    anyhow::anyhow!("alignment_start override must be 1-based, got {}", start)
+                })?);
             }
             if let Some(seq) = &ov.seq {
                 seq_opt = SeqBuf::from(seq.clone());
@@ -109,10 +312,28 @@ impl PyBamRecord {
             if let Some(qname) = &ov.qname {
                 qname_opt = qname.clone();
             }
-            if let Some(mapq) = &ov.mapping_quality {
-                mapq_opt = Some(*mapq)
+            if let Some(mapq) = ov.mapping_quality {
+                mapq_opt = MappingQuality::new(mapq);
             }
         }
+        // 参照 ID なしは unplaced の unmapped として位置も落とす
+        if ref_id_opt.is_none() {
+            flag.insert(Flags::UNMAPPED);
+            position_opt = None;
+        }
+        // unmapped は MAPQ 0、CIGAR なしで書き出す。メイトの位置に置かれた
+        // unmapped は参照 ID・位置を残し、座標順を崩さない
+        if flag.is_unmapped() {
+            mapq_opt = Some(MappingQuality::MIN);
+            cigar_vec.clear();
+        } else if position_opt.is_none() {
+            // 位置なしの mapped レコードは書き出さない
+            return Err(anyhow::anyhow!(
+                "record {} is mapped but has no alignment start",
+                qname_opt
+            ));
+        }
+
         // builder
         let mut builder = RecordBuf::builder()
             .set_name(qname_opt)
@@ -131,6 +352,18 @@ impl PyBamRecord {
         if let Some(mapq) = mapq_opt {
             builder = builder.set_mapping_quality(mapq);
         }
+        // メイト情報と TLEN は元レコードのまま
+        if let Some(rid) = self.record.mate_reference_sequence_id() {
+            builder = builder.set_mate_reference_sequence_id(
+                rid.map_err(|_| anyhow::anyhow!("Invalid mate reference sequence ID"))?,
+            );
+        }
+        if let Some(pos) = self.record.mate_alignment_start() {
+            builder = builder.set_mate_alignment_start(
+                pos.map_err(|_| anyhow::anyhow!("Invalid mate alignment start position"))?,
+            );
+        }
+        builder = builder.set_template_length(self.record.template_length());
         let record_buf = builder.build();
 
         Ok(record_buf)
@@ -143,13 +376,34 @@ impl PyBamRecord {
         self.record_override = Some(override_);
     }
     // ── getters and setters ────────────────────────────────────────────
+    /// Read name; reflects a name set through the setter.
     #[getter]
     fn qname(&self) -> String {
+        if let Some(name) = self
+            .record_override
+            .as_ref()
+            .and_then(|ov| ov.qname.clone())
+        {
+            return name;
+        }
         self.record
             .name()
             .map(|b| b.to_string())
             .unwrap_or_default()
     }
+    /// 書き出し時のリード名を上書きする (空文字・空白を含む名前は不可)
+    #[setter]
+    fn set_qname(&mut self, name: String) -> PyResult<()> {
+        if name.is_empty() || name.chars().any(char::is_whitespace) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "invalid read name {name:?}: must be non-empty without whitespace"
+            )));
+        }
+        self.record_override
+            .get_or_insert_with(RecordOverride::default)
+            .qname = Some(name);
+        Ok(())
+    }
     #[getter]
     fn rid(&self) -> i32 {
         self.record
@@ -158,10 +412,82 @@ impl PyBamRecord {
             .map(|r| r as i32)
             .unwrap_or(-1)
     }
+    /// Reference sequence name resolved against the reader's header, or
+    /// `None` for unmapped records and records without a reference id.
+    #[getter]
+    fn rname(&self) -> Option<String> {
+        if self.record.flags().is_unmapped() {
+            return None;
+        }
+        self.reference_name()
+    }
     #[getter]
     fn flag(&self) -> u16 {
         u16::from(self.record.flags())
     }
+    // ── flag predicates ───────────────────────────────────────────────
+    /// Flag `0x1`.
+    #[getter]
+    fn is_paired(&self) -> bool {
+        self.record.flags().contains(Flags::SEGMENTED)
+    }
+    /// Flag `0x2`.
+    #[getter]
+    fn is_proper_pair(&self) -> bool {
+        self.record.flags().contains(Flags::PROPERLY_SEGMENTED)
+    }
+    /// Flag `0x4`.
+    #[getter]
+    fn is_unmapped(&self) -> bool {
+        self.record.flags().contains(Flags::UNMAPPED)
+    }
+    /// Flag `0x8`.
+    #[getter]
+    fn is_mate_unmapped(&self) -> bool {
+        self.record.flags().contains(Flags::MATE_UNMAPPED)
+    }
+    /// Flag `0x10`.
+    #[getter]
+    fn is_reverse(&self) -> bool {
+        self.record.flags().contains(Flags::REVERSE_COMPLEMENTED)
+    }
+    /// Flag `0x20`.
+    #[getter]
+    fn is_mate_reverse(&self) -> bool {
+        self.record
+            .flags()
+            .contains(Flags::MATE_REVERSE_COMPLEMENTED)
+    }
+    /// Flag `0x40`.
+    #[getter]
+    fn is_read1(&self) -> bool {
+        self.record.flags().contains(Flags::FIRST_SEGMENT)
+    }
+    /// Flag `0x80`.
+    #[getter]
+    fn is_read2(&self) -> bool {
+        self.record.flags().contains(Flags::LAST_SEGMENT)
+    }
+    /// Flag `0x100`.
+    #[getter]
+    fn is_secondary(&self) -> bool {
+        self.record.flags().contains(Flags::SECONDARY)
+    }
+    /// Flag `0x200`.
+    #[getter]
+    fn is_qcfail(&self) -> bool {
+        self.record.flags().contains(Flags::QC_FAIL)
+    }
+    /// Flag `0x400`.
+    #[getter]
+    fn is_duplicate(&self) -> bool {
+        self.record.flags().contains(Flags::DUPLICATE)
+    }
+    /// Flag `0x800`.
+    #[getter]
+    fn is_supplementary(&self) -> bool {
+        self.record.flags().contains(Flags::SUPPLEMENTARY)
+    }
     #[getter]
     fn pos(&self) -> i64 {
         self.record
@@ -170,22 +496,127 @@ impl PyBamRecord {
             .map(|p| usize::from(p) as i64)
             .unwrap_or(-1)
     }
+    /// MAPQ (255 if missing); reflects a value set through the setter.
     #[getter]
     fn mapq(&self) -> u8 {
+        if let Some(mapq) = self
+            .record_override
+            .as_ref()
+            .and_then(|ov| ov.mapping_quality)
+        {
+            return mapq;
+        }
         self.record
             .mapping_quality()
             .map(|mq| u8::from(mq))
             .unwrap_or(255)
     }
+    /// 書き出し時の MAPQ を上書きする (255 は MAPQ なし)
+    #[setter]
+    fn set_mapq(&mut self, mapq: u8) {
+        self.record_override
+            .get_or_insert_with(RecordOverride::default)
+            .mapping_quality = Some(mapq);
+    }
+    /// Absolute template length; see `template_length` for the signed value.
     #[getter]
     fn len(&self) -> usize {
         self.record.template_length().abs() as usize
     }
+    /// Signed template length (`TLEN`), negative for the rightmost mate.
+    #[getter]
+    fn template_length(&self) -> i64 {
+        i64::from(self.record.template_length())
+    }
+    /// Mate alignment start (1-based like `pos`), or -1 if absent.
+    #[getter]
+    fn mate_pos(&self) -> i64 {
+        self.record
+            .mate_alignment_start()
+            .and_then(|r| r.ok())
+            .map(|p| usize::from(p) as i64)
+            .unwrap_or(-1)
+    }
+    /// Same as `mate_reference_name` (`None` for single-end reads,
+    /// unmapped mates and records without a header).
+    #[getter]
+    fn mate_rname(&self) -> Option<String> {
+        self.mate_reference_name()
+    }
 
     #[getter]
     fn seq(&self) -> String {
         self.record.sequence().iter().map(|b| b as char).collect()
     }
+    /// Sequence in sequencing orientation: reverse-complemented (IUPAC
+    /// codes included, `N` stays `N`) for reverse-strand reads, as stored
+    /// otherwise.
+    #[getter]
+    fn seq_forward(&self) -> String {
+        let seq = self.record.sequence();
+        if self.record.flags().is_reverse_complemented() {
+            let bases: Vec<u8> = seq.iter().collect();
+            bases
+                .iter()
+                .rev()
+                .map(|&b| iupac_complement(b) as char)
+                .collect()
+        } else {
+            seq.iter().map(|b| b as char).collect()
+        }
+    }
+    /// Reference bases consumed by the CIGAR (`M`/`D`/`N`/`=`/`X`); 0
+    /// without a CIGAR.
+    #[getter]
+    fn reference_length(&self) -> usize {
+        self.record
+            .cigar()
+            .iter()
+            .filter_map(Result::ok)
+            .filter(|op| op.kind().consumes_reference())
+            .map(|op| op.len())
+            .sum()
+    }
+
+    /// 参照上のアラインメント終端 (0-based, exclusive)
+    ///
+    /// `alignment_start + reference_length`; -1 for unmapped reads and
+    /// records whose CIGAR is `*`.
+    #[getter]
+    fn alignment_end(&self) -> i64 {
+        match (self.reference_start(), self.reference_length()) {
+            (Some(start), len) if len > 0 => start + len as i64,
+            _ => -1,
+        }
+    }
+    /// Alias of `alignment_end`, as named by pysam.
+    #[getter]
+    fn reference_end(&self) -> i64 {
+        self.alignment_end()
+    }
+
+    /// Query bases consumed by the CIGAR (`M`/`I`/`S`/`=`/`X`), or the
+    /// sequence length for records without a CIGAR.
+    #[getter]
+    fn query_length(&self) -> usize {
+        let cigar = self.record.cigar();
+        if cigar.is_empty() {
+            return self.record.sequence().len();
+        }
+        cigar
+            .iter()
+            .filter_map(Result::ok)
+            .filter(|op| op.kind().consumes_read())
+            .map(|op| op.len())
+            .sum()
+    }
+
+    /// `seq` as a uint8 array of ASCII base codes (empty for `*`).
+    #[getter]
+    fn seq_array<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<u8>> {
+        PyArray1::from_vec(py, self.record.sequence().iter().collect())
+    }
+    /// Base qualities as a list of ints; `qual_array` is the faster path.
     #[getter]
     fn qual(&self) -> Vec<usize> {
         self.record
@@ -195,7 +626,18 @@ impl PyBamRecord {
             .map(|&b| b as usize)
             .collect()
     }
+    /// Base qualities as a uint8 array (one byte per base, same values as
+    /// `qual`).
+    #[getter]
+    fn qual_array<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<u8>> {
+        PyArray1::from_slice(py, self.record.quality_scores().as_ref())
+    }
 
+    /// `(kind, length)` pairs in read order
+    ///
+    /// `kind` is the BAM op code (`MIDNSHP=X` → 0..8, the values of
+    /// `PyKind`) and always comes first. Ops that fail to decode are
+    /// skipped (see `cigar_strict`).
     #[getter]
     fn cigar(&self) -> Vec<(u32, u32)> {
         let ops: Vec<(u32, u32)> = self
@@ -208,8 +650,58 @@ impl PyBamRecord {
         return ops;
     }
 
+    /// `cigar` と同じだが、デコードできない op があれば ValueError
+    #[getter]
+    fn cigar_strict(&self) -> PyResult<Vec<(u32, u32)>> {
+        self.record
+            .cigar()
+            .iter()
+            .enumerate()
+            .map(|(i, op)| {
+                op.map(|op| (op.kind() as u32, op.len() as u32))
+                    .map_err(|e| {
+                        PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                            "invalid CIGAR op at index {i}: {e}"
+                        ))
+                    })
+            })
+            .collect()
+    }
+
+    /// タグを追加・上書きする (書き出し時に反映)
+    ///
+    /// The SAM type follows the Python type: `int` → `i`, `float` → `f`,
+    /// `str` → `Z`, a single byte (`b"A"`) → `A`, a list of ints → `B:i`
+    /// and a list of floats → `B:f`. The `tags` getter keeps returning the
+    /// values read from the file.
+    fn set_tag(&mut self, tag: &str, value: &Bound<'_, PyAny>) -> PyResult<()> {
+        if tag.len() != 2 || !tag.bytes().all(|b| b.is_ascii_graphic()) {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                "invalid tag {tag:?}: must be exactly two ASCII characters"
+            )));
+        }
+        let key = Tag::new(tag.as_bytes()[0], tag.as_bytes()[1]);
+        let value = record_override::convert_pyany_to_tag_value(value)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+        let tags = &mut self
+            .record_override
+            .get_or_insert_with(RecordOverride::default)
+            .tags;
+        tags.retain(|(t, _)| *t != key);
+        tags.push((key, value));
+        Ok(())
+    }
+
     fn get_field_by_tag<'py>(&self, tag: &str, py: Python<'py>) -> PyResult<PyObject> {
-        // First, convert tag to two bytes
+        self.get_tag(tag, py)?.ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyKeyError, _>(format!("tag not found: {}", tag))
+        })
+    }
+
+    /// 1 つのタグの値 (`tags` と同じ変換)。なければ `None`
+    ///
+    /// Stops at the first matching field instead of decoding every tag.
+    fn get_tag<'py>(&self, tag: &str, py: Python<'py>) -> PyResult<Option<PyObject>> {
         let tag_bytes = tag.as_bytes();
         // tag が 2 バイトでない場合はエラー
         if tag_bytes.len() != 2 {
@@ -217,7 +709,7 @@ impl PyBamRecord {
                 "tag must be 2 bytes",
             ));
         }
-        // それ以外は元の record.data() から取得
+        let wanted = Tag::new(tag_bytes[0], tag_bytes[1]);
         for result in self.record.data().iter() {
             let (key, value) = result.map_err(|_| {
                 PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
@@ -225,100 +717,637 @@ impl PyBamRecord {
                     tag
                 ))
             })?;
-            if key == Tag::new(tag_bytes[0], tag_bytes[1]) {
-                let obj = match value {
-                    BamValue::Int8(n) => (n as i32).into_py_any(py).unwrap(),
-                    BamValue::UInt8(n) => (n as u32).into_py_any(py).unwrap(),
-                    BamValue::Int16(n) => (n as i32).into_py_any(py).unwrap(),
-                    BamValue::UInt16(n) => (n as u32).into_py_any(py).unwrap(),
-                    BamValue::Int32(n) => (n as i32).into_py_any(py).unwrap(),
-                    BamValue::UInt32(n) => (n as u32).into_py_any(py).unwrap(),
-                    BamValue::Float(f) => (f as f64).into_py_any(py).unwrap(),
-                    BamValue::Character(c) => c.to_string().into_py_any(py).unwrap(),
-                    BamValue::String(bs) => String::from_utf8_lossy(bs)
-                        .into_owned()
-                        .into_py_any(py)
-                        .unwrap(),
-                    BamValue::Array(arr) => match arr {
-                        Array::UInt8(a) => {
-                            PyArray1::from_vec(py, a.iter().filter_map(|r| r.ok()).collect())
-                                .into_py_any(py)
-                                .unwrap()
-                        }
-                        Array::Int8(a) => {
-                            PyArray1::from_vec(py, a.iter().filter_map(|r| r.ok()).collect())
-                                .into_py_any(py)
-                                .unwrap()
-                        }
-                        Array::Int16(a) => {
-                            PyArray1::from_vec(py, a.iter().filter_map(|r| r.ok()).collect())
-                                .into_py_any(py)
-                                .unwrap()
-                        }
-                        Array::Float(a) => {
-                            PyArray1::from_vec(py, a.iter().filter_map(|r| r.ok()).collect())
-                                .into_py_any(py)
-                                .unwrap()
-                        }
-                        _ => py.None().into_py_any(py).unwrap(),
-                    },
-                    _ => py.None().into_py_any(py).unwrap(),
-                };
-                return Ok(obj);
+            if key == wanted {
+                return tag_value::value_to_py(py, value, self.tag_conversion).map(Some);
             }
         }
-        Err(PyErr::new::<pyo3::exceptions::PyKeyError, _>(format!(
-            "tag not found: {}",
-            tag
-        )))
+        Ok(None)
+    }
+
+    /// 塩基ごとのクエリ位置と参照位置の対応を `(N, 2)` の int64 配列で返す
+    ///
+    /// Column 0 is the 0-based query position, column 1 the 0-based
+    /// reference position; `-1` marks insertions (no reference base) and
+    /// deletions / skips (no query base). Soft-clipped bases are included
+    /// as `(query_pos, -1)` only with `include_soft_clips`. Empty for
+    /// unmapped reads. Mirrors pysam's `get_aligned_pairs`.
+    #[pyo3(signature = (include_soft_clips=false))]
+    fn aligned_pairs<'py>(
+        &self,
+        py: Python<'py>,
+        include_soft_clips: bool,
+    ) -> PyResult<Bound<'py, PyArray2<i64>>> {
+        let pairs = match self.reference_start() {
+            Some(start) => alignment::aligned_pairs(&self.cigar_ops(), start, include_soft_clips),
+            None => Vec::new(),
+        };
+        let n = pairs.len();
+        let flat: Vec<i64> = pairs.into_iter().flat_map(|(q, r)| [q, r]).collect();
+        PyArray1::from_vec(py, flat).reshape([n, 2])
     }
 
+    /// MD タグとリード配列からアラインメント範囲の参照配列を復元する
+    ///
+    /// Matches copy the read base, mismatches and deletions take the bases
+    /// written in `MD`; the result is uppercase. Mirrors pysam's
+    /// `get_reference_sequence`. Raises `ValueError` when `MD` is missing
+    /// or disagrees with the CIGAR.
+    fn get_reference_sequence(&self) -> PyResult<String> {
+        let md = self.string_tag(Tag::MISMATCHED_POSITIONS).ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>("record has no MD tag")
+        })?;
+        let query: Vec<u8> = self.record.sequence().iter().collect();
+        let reference = alignment::md_reference(&self.cigar_ops(), &query, &md)
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+        Ok(String::from_utf8_lossy(&reference).into_owned())
+    }
+
+    /// `MM` / `ML` のモディフィケーションを pysam の `modified_bases` 形式で返す
+    ///
+    /// Keys are `(canonical_base, strand, code)` with `strand` 0 for `+`
+    /// and 1 for `-`, e.g. `("C", 0, "m")`. Values are `(N, 2)` int64
+    /// arrays of `(read_position, ML value 0-255)`, read positions being
+    /// 0-based offsets in the stored `SEQ`. Only explicitly called bases
+    /// are listed. Empty without an `MM` tag; malformed tags raise
+    /// `ValueError`.
     #[getter]
-    fn tags<'py>(&self, py: Python<'py>) -> Vec<(String, PyObject)> {
-        // override がなければ元の record.data() から構築
-        let mut vec = Vec::new();
-        for field in self.record.data().iter().filter_map(Result::ok) {
-            let key = String::from_utf8_lossy(field.0.as_ref()).into_owned();
-            let obj = match field.1 {
-                BamValue::Int8(n) => (n as i32).into_py_any(py).unwrap(),
-                BamValue::UInt8(n) => (n as u32).into_py_any(py).unwrap(),
-                BamValue::Int16(n) => (n as i32).into_py_any(py).unwrap(),
-                BamValue::UInt16(n) => (n as u32).into_py_any(py).unwrap(),
-                BamValue::Int32(n) => (n as i32).into_py_any(py).unwrap(),
-                BamValue::UInt32(n) => (n as u32).into_py_any(py).unwrap(),
-                BamValue::Float(f) => (f as f64).into_py_any(py).unwrap(),
-                BamValue::Character(c) => c.to_string().into_py_any(py).unwrap(),
-                BamValue::String(bs) => String::from_utf8_lossy(bs)
-                    .into_owned()
-                    .into_py_any(py)
-                    .unwrap(),
-                BamValue::Array(arr) => match arr {
-                    Array::UInt8(a) => {
-                        PyArray1::from_vec(py, a.iter().filter_map(|r| r.ok()).collect())
-                            .into_py_any(py)
-                            .unwrap()
-                    }
-                    Array::Int8(a) => {
-                        PyArray1::from_vec(py, a.iter().filter_map(|r| r.ok()).collect())
-                            .into_py_any(py)
-                            .unwrap()
+    fn modified_bases<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let calls = modifications::base_modifications(&self.record)
+            .map_err(PyErr::new::<pyo3::exceptions::PyValueError, _>)?;
+        let mut groups: Vec<((String, u8, String), Vec<i64>)> = Vec::new();
+        for call in calls.into_iter().filter(|c| c.explicit) {
+            let key = (
+                (call.canonical as char).to_string(),
+                (call.strand == '-') as u8,
+                call.code,
+            );
+            let i = match groups.iter().position(|(k, _)| *k == key) {
+                Some(i) => i,
+                None => {
+                    groups.push((key, Vec::new()));
+                    groups.len() - 1
+                }
+            };
+            groups[i]
+                .1
+                .extend([call.query_pos as i64, call.prob as i64]);
+        }
+        let dict = PyDict::new(py);
+        for (key, values) in groups {
+            let n = values.len() / 2;
+            dict.set_item(key, PyArray1::from_vec(py, values).reshape([n, 2])?)?;
+        }
+        Ok(dict)
+    }
+
+    /// 1 レコード分の SAM テキスト行 (末尾の改行なし, override 適用済み)
+    ///
+    /// Reference names are resolved against the reader's header; records
+    /// without a header raise `ValueError` unless they are unplaced.
+    fn to_sam_line(&self) -> PyResult<String> {
+        let buf = self
+            .to_record_buf()
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+        let empty = sam::Header::default();
+        let header = self.header.as_deref().unwrap_or(&empty);
+        let mut writer = sam::io::Writer::new(Vec::new());
+        writer
+            .write_alignment_record(header, &buf)
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+        let line = String::from_utf8_lossy(writer.get_ref());
+        Ok(line.trim_end_matches('\n').to_string())
+    }
+
+    /// Reference position (0-based) aligned to the 0-based read offset
+    /// `query_pos`; `None` if it falls in an insertion or soft clip.
+    fn query_to_reference(&self, query_pos: usize) -> Option<i64> {
+        let ref_start = self.reference_start()?;
+        alignment::query_to_reference(&self.cigar_ops(), ref_start, query_pos)
+    }
+
+    /// 0-based read offset aligned to the 0-based reference position
+    /// `ref_pos`; `None` if the position is deleted or skipped in the read.
+    fn reference_to_query(&self, ref_pos: i64) -> Option<usize> {
+        let ref_start = self.reference_start()?;
+        alignment::reference_to_query(&self.cigar_ops(), ref_start, ref_pos)
+    }
+
+    /// Illumina read-name coordinates
+    /// (`instrument:run:flowcell:lane:tile:x:y[:UMI]`) as a dict, or `None`
+    /// when the name does not follow that layout.
+    #[getter]
+    fn optical_coordinates<'py>(&self, py: Python<'py>) -> PyResult<Option<Bound<'py, PyDict>>> {
+        let qname = self.qname();
+        let fields: Vec<&str> = qname.split(':').collect();
+        if fields.len() != 7 && fields.len() != 8 {
+            return Ok(None);
+        }
+        let (Ok(run), Ok(lane), Ok(tile), Ok(x), Ok(y)) = (
+            fields[1].parse::<u32>(),
+            fields[3].parse::<u32>(),
+            fields[4].parse::<u32>(),
+            fields[5].parse::<u32>(),
+            fields[6].parse::<u32>(),
+        ) else {
+            return Ok(None);
+        };
+        let dict = PyDict::new(py);
+        dict.set_item("instrument", fields[0])?;
+        dict.set_item("run", run)?;
+        dict.set_item("flowcell", fields[2])?;
+        dict.set_item("lane", lane)?;
+        dict.set_item("tile", tile)?;
+        dict.set_item("x", x)?;
+        dict.set_item("y", y)?;
+        Ok(Some(dict))
+    }
+
+    /// Pair orientation (`"FR"`, `"RF"`, `"FF"` or `"RR"`), reading the
+    /// strands of the leftmost mate first. When both mates start at the
+    /// same position the forward one counts as leftmost. `None` for
+    /// single-end reads, unmapped reads or mates, and mates on another
+    /// reference.
+    #[getter]
+    fn pair_orientation(&self) -> Option<&'static str> {
+        let flags = self.record.flags();
+        if !flags.is_segmented() || flags.is_unmapped() || flags.is_mate_unmapped() {
+            return None;
+        }
+        let rid = self.record.reference_sequence_id()?.ok()?;
+        let mate_rid = self.record.mate_reference_sequence_id()?.ok()?;
+        if rid != mate_rid {
+            return None;
+        }
+        let start = self.record.alignment_start()?.ok()?;
+        let mate_start = self.record.mate_alignment_start()?.ok()?;
+        let reverse = flags.is_reverse_complemented();
+        let mate_reverse = flags.is_mate_reverse_complemented();
+
+        let self_first = match start.cmp(&mate_start) {
+            std::cmp::Ordering::Less => true,
+            std::cmp::Ordering::Greater => false,
+            std::cmp::Ordering::Equal => !reverse,
+        };
+        let (left, right) = if self_first {
+            (reverse, mate_reverse)
+        } else {
+            (mate_reverse, reverse)
+        };
+        Some(match (left, right) {
+            (false, true) => "FR",
+            (true, false) => "RF",
+            (false, false) => "FF",
+            (true, true) => "RR",
+        })
+    }
+
+    /// 64-bit hash of `(reference id, 5' position, strand, CIGAR, mate
+    /// position)` for coordinate-based duplicate detection.
+    ///
+    /// The 5' position includes clipped bases, as in `deduplicate`. The
+    /// hash is FNV-1a, so it is stable across runs and platforms.
+    #[getter]
+    fn signature(&self) -> u64 {
+        let rid = match self.record.reference_sequence_id() {
+            Some(Ok(rid)) if !self.record.flags().is_unmapped() => rid as i64,
+            _ => -1,
+        };
+        let reverse = self.record.flags().is_reverse_complemented();
+        let five_prime = match alignment::reference_span(&self.record) {
+            Some((start, end)) => {
+                let (left, right) = alignment::terminal_clips(&self.cigar_ops());
+                if reverse {
+                    end + right as i64
+                } else {
+                    start - left as i64
+                }
+            }
+            None => -1,
+        };
+        let mate_pos = match self.record.mate_alignment_start() {
+            Some(Ok(pos)) => usize::from(pos) as i64 - 1,
+            _ => -1,
+        };
+        fnv1a64(&[
+            &rid.to_le_bytes(),
+            &five_prime.to_le_bytes(),
+            &[reverse as u8],
+            self.record.cigar().as_ref(),
+            &mate_pos.to_le_bytes(),
+        ])
+    }
+
+    /// Whether the alignment runs past the end of its reference sequence
+    /// (header `LN`). `False` for unmapped reads and records without a
+    /// header.
+    #[getter]
+    fn extends_past_contig(&self) -> bool {
+        let Some(header) = self.header.as_ref() else {
+            return false;
+        };
+        let Some((_, end)) = alignment::reference_span(&self.record) else {
+            return false;
+        };
+        match self.record.reference_sequence_id() {
+            Some(Ok(rid)) => header
+                .reference_sequences()
+                .get_index(rid)
+                .is_some_and(|(_, rs)| end > usize::from(rs.length()) as i64),
+            _ => false,
+        }
+    }
+
+    /// Whether the mate is on the reverse strand (flag 0x20). `False` for
+    /// single-end reads and unmapped mates.
+    #[getter]
+    fn mate_is_reverse(&self) -> bool {
+        let flags = self.record.flags();
+        flags.is_segmented() && !flags.is_mate_unmapped() && flags.is_mate_reverse_complemented()
+    }
+
+    /// Reference name of the mate resolved against the attached header.
+    /// `None` for single-end reads, unmapped mates and records without a
+    /// header.
+    #[getter]
+    fn mate_reference_name(&self) -> Option<String> {
+        let flags = self.record.flags();
+        if !flags.is_segmented() || flags.is_mate_unmapped() {
+            return None;
+        }
+        let header = self.header.as_ref()?;
+        let mate_rid = self.record.mate_reference_sequence_id()?.ok()?;
+        header
+            .reference_sequences()
+            .get_index(mate_rid)
+            .map(|(name, _)| name.to_string())
+    }
+
+    /// Midpoint (0-based) of the fragment for properly paired reads.
+    ///
+    /// The fragment runs from the leftmost mate's alignment start over
+    /// `abs(template_length)` bases; the midpoint is rounded down, so both
+    /// mates report the same value. `None` for single-end reads, improper
+    /// pairs and pairs without a template length.
+    #[getter]
+    fn fragment_midpoint(&self) -> Option<i64> {
+        let flags = self.record.flags();
+        if !flags.is_segmented() || !flags.is_properly_segmented() || flags.is_unmapped() {
+            return None;
+        }
+        let tlen = self.record.template_length() as i64;
+        let frag_start = if tlen > 0 {
+            self.reference_start()?
+        } else if tlen < 0 {
+            usize::from(self.record.mate_alignment_start()?.ok()?) as i64 - 1
+        } else {
+            return None;
+        };
+        Some(frag_start + tlen.abs() / 2)
+    }
+
+    /// Original base qualities from the `OQ` tag (phred+33 decoded), or
+    /// `qual` when the tag is absent.
+    #[getter]
+    fn original_quality<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<u8>> {
+        let scores = match self.string_tag(Tag::new(b'O', b'Q')) {
+            Some(oq) => oq.bytes().map(|b| b.saturating_sub(33)).collect(),
+            None => self.record.quality_scores().as_ref().to_vec(),
+        };
+        PyArray1::from_vec(py, scores)
+    }
+
+    /// GC fraction of every `window`-base window along `seq` (step 1), as
+    /// a float64 array of `len(seq) - window + 1` values. Reads shorter
+    /// than `window` give a single whole-read value; an empty read gives an
+    /// empty array.
+    fn local_gc<'py>(&self, py: Python<'py>, window: usize) -> PyResult<Bound<'py, PyArray1<f64>>> {
+        if window == 0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "window must be positive",
+            ));
+        }
+        let is_gc: Vec<u32> = self
+            .record
+            .sequence()
+            .iter()
+            .map(|b| matches!(b.to_ascii_uppercase(), b'G' | b'C') as u32)
+            .collect();
+        if is_gc.is_empty() {
+            return Ok(PyArray1::from_vec(py, Vec::new()));
+        }
+        let window = window.min(is_gc.len());
+        let mut count: u32 = is_gc[..window].iter().sum();
+        let mut fractions = Vec::with_capacity(is_gc.len() - window + 1);
+        fractions.push(count as f64 / window as f64);
+        for i in window..is_gc.len() {
+            count = count + is_gc[i] - is_gc[i - window];
+            fractions.push(count as f64 / window as f64);
+        }
+        Ok(PyArray1::from_vec(py, fractions))
+    }
+
+    /// Base qualities between `query_alignment_start` and
+    /// `query_alignment_end` (terminal soft clips excluded), as in pysam.
+    #[getter]
+    fn query_alignment_qualities<'py>(&self, py: Python<'py>) -> Bound<'py, PyArray1<u8>> {
+        let (start, end) = self.query_alignment_bounds();
+        let quals = self.record.quality_scores();
+        let quals = quals.as_ref();
+        PyArray1::from_vec(py, quals.get(start..end).unwrap_or_default().to_vec())
+    }
+
+    /// Read split at its terminal soft clips into
+    /// `(kind, start, end, seq)` segments, `kind` being `"soft_clip"` or
+    /// `"aligned"` and `start`/`end` 0-based read offsets.
+    #[getter]
+    fn sequence_segments(&self) -> Vec<(&'static str, usize, usize, String)> {
+        let seq = self.seq();
+        let len = seq.len();
+        if len == 0 {
+            return Vec::new();
+        }
+        let (left, end) = self.query_alignment_bounds();
+        let right = len - end;
+        let mut segments = Vec::with_capacity(3);
+        if left > 0 {
+            segments.push(("soft_clip", 0, left, seq[..left].to_string()));
+        }
+        if left < len - right {
+            segments.push((
+                "aligned",
+                left,
+                len - right,
+                seq[left..len - right].to_string(),
+            ));
+        }
+        if right > 0 {
+            segments.push((
+                "soft_clip",
+                len - right,
+                len,
+                seq[len - right..].to_string(),
+            ));
+        }
+        segments
+    }
+
+    /// Introns of this read that are not in `known_junctions`.
+    ///
+    /// `known_junctions` holds `(reference_id, donor, acceptor)` tuples
+    /// with 0-based, half-open intron coordinates; the result lists the
+    /// read's unannotated `(donor, acceptor)` pairs in read order.
+    fn novel_junctions(&self, known_junctions: HashSet<(i64, i64, i64)>) -> Vec<(i64, i64)> {
+        let Some(start) = self.reference_start() else {
+            return Vec::new();
+        };
+        let rid = self.rid() as i64;
+        alignment::introns(&self.cigar_ops(), start)
+            .into_iter()
+            .filter(|&(donor, acceptor)| !known_junctions.contains(&(rid, donor, acceptor)))
+            .collect()
+    }
+
+    /// Insertion / deletion summary from a single CIGAR pass.
+    ///
+    /// Keys: `insertions` / `deletions` (number of ops),
+    /// `inserted_bases` / `deleted_bases` (summed lengths) and
+    /// `largest_gap` (longest single `I` or `D` op, 0 if none).
+    #[getter]
+    fn gap_summary<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let (mut insertions, mut inserted) = (0usize, 0usize);
+        let (mut deletions, mut deleted) = (0usize, 0usize);
+        let mut largest = 0usize;
+        for op in self.record.cigar().iter().filter_map(Result::ok) {
+            match op.kind() {
+                Kind::Insertion => {
+                    insertions += 1;
+                    inserted += op.len();
+                }
+                Kind::Deletion => {
+                    deletions += 1;
+                    deleted += op.len();
+                }
+                _ => continue,
+            }
+            largest = largest.max(op.len());
+        }
+        let dict = PyDict::new(py);
+        dict.set_item("insertions", insertions)?;
+        dict.set_item("inserted_bases", inserted)?;
+        dict.set_item("deletions", deletions)?;
+        dict.set_item("deleted_bases", deleted)?;
+        dict.set_item("largest_gap", largest)?;
+        Ok(dict)
+    }
+
+    /// Per-exon contribution of a spliced read as 0-based, half-open
+    /// `(ref_start, ref_end, aligned_base_count)`, split at `N` ops.
+    ///
+    /// `aligned_base_count` counts `M`/`=`/`X` bases only, so deletions
+    /// inside an exon widen it without adding to the count. Empty for
+    /// unmapped reads.
+    #[getter]
+    fn exon_contributions(&self) -> Vec<(i64, i64, usize)> {
+        match self.reference_start() {
+            Some(start) => alignment::exon_blocks(&self.cigar_ops(), start),
+            None => Vec::new(),
+        }
+    }
+
+    /// Number of `N` bases in `reference_fasta` over the read's aligned
+    /// span (deleted and skipped positions included).
+    fn reference_n_count(&self, reference_fasta: &str) -> PyResult<usize> {
+        let ref_bases = self.aligned_reference(reference_fasta)?;
+        Ok(ref_bases.iter().filter(|&&b| b == b'N').count())
+    }
+
+    /// リファレンスとクエリのアラインメントを `-` 付きの同じ長さの文字列で返す
+    ///
+    /// Returns `(ref_aln, query_aln)` built by walking the CIGAR against
+    /// `reference_fasta`. Insertions put `-` in the reference string,
+    /// deletions and skips `-` in the query string. Clips and padding are
+    /// left out.
+    fn aligned_sequences(&self, reference_fasta: &str) -> PyResult<(String, String)> {
+        let ref_bases = self.aligned_reference(reference_fasta)?;
+        let query: Vec<u8> = self.record.sequence().iter().collect();
+
+        let mut ref_aln = Vec::with_capacity(ref_bases.len());
+        let mut query_aln = Vec::with_capacity(ref_bases.len());
+        let (mut r, mut q) = (0usize, 0usize);
+        for op in self.cigar_ops() {
+            let len = op.len();
+            match op.kind() {
+                Kind::Match | Kind::SequenceMatch | Kind::SequenceMismatch => {
+                    ref_aln.extend(ref_bases.iter().skip(r).take(len));
+                    query_aln.extend(query.iter().skip(q).take(len));
+                    r += len;
+                    q += len;
+                }
+                Kind::Insertion => {
+                    ref_aln.extend(std::iter::repeat_n(b'-', len));
+                    query_aln.extend(query.iter().skip(q).take(len));
+                    q += len;
+                }
+                Kind::Deletion | Kind::Skip => {
+                    ref_aln.extend(ref_bases.iter().skip(r).take(len));
+                    query_aln.extend(std::iter::repeat_n(b'-', len));
+                    r += len;
+                }
+                Kind::SoftClip => q += len,
+                Kind::HardClip | Kind::Pad => {}
+            }
+        }
+        Ok((
+            String::from_utf8_lossy(&ref_aln).into_owned(),
+            String::from_utf8_lossy(&query_aln).into_owned(),
+        ))
+    }
+
+    /// Per-base alignment events for building error models.
+    ///
+    /// Returns `(read_pos, ref_pos, event, read_base, ref_base, qual)`
+    /// tuples in alignment order, with 0-based positions and `event` one of
+    /// `"match"`, `"mismatch"`, `"insertion"` or `"deletion"`. Insertions
+    /// have no `ref_pos` / `ref_base`, deletions no `read_pos` /
+    /// `read_base` / `qual`. Clips and skipped regions produce no events.
+    fn alignment_events(&self, reference_fasta: &str) -> PyResult<Vec<AlignmentEvent>> {
+        let ref_bases = self.aligned_reference(reference_fasta)?;
+        let ref_start = self.reference_start().ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>("record has no alignment span")
+        })?;
+        let query: Vec<u8> = self.record.sequence().iter().collect();
+        let quals = self.record.quality_scores();
+        let quals = quals.as_ref();
+        let qual_at = |q: usize| quals.get(q).copied().filter(|&b| b != 0xff);
+
+        let mut events = Vec::new();
+        let (mut r, mut q) = (0usize, 0usize);
+        for op in self.cigar_ops() {
+            let len = op.len();
+            match op.kind() {
+                Kind::Match | Kind::SequenceMatch | Kind::SequenceMismatch => {
+                    for i in 0..len {
+                        let read_base = query.get(q + i).map(u8::to_ascii_uppercase);
+                        let ref_base = ref_bases.get(r + i).copied();
+                        let event = if read_base == ref_base {
+                            "match"
+                        } else {
+                            "mismatch"
+                        };
+                        events.push((
+                            Some(q + i),
+                            Some(ref_start + (r + i) as i64),
+                            event,
+                            read_base.map(char::from),
+                            ref_base.map(char::from),
+                            qual_at(q + i),
+                        ));
                     }
-                    Array::Int16(a) => {
-                        PyArray1::from_vec(py, a.iter().filter_map(|r| r.ok()).collect())
-                            .into_py_any(py)
-                            .unwrap()
+                    r += len;
+                    q += len;
+                }
+                Kind::Insertion => {
+                    for i in 0..len {
+                        events.push((
+                            Some(q + i),
+                            None,
+                            "insertion",
+                            query
+                                .get(q + i)
+                                .map(|&b| char::from(b.to_ascii_uppercase())),
+                            None,
+                            qual_at(q + i),
+                        ));
                     }
-                    Array::Float(a) => {
-                        PyArray1::from_vec(py, a.iter().filter_map(|r| r.ok()).collect())
-                            .into_py_any(py)
-                            .unwrap()
+                    q += len;
+                }
+                Kind::Deletion => {
+                    for i in 0..len {
+                        events.push((
+                            None,
+                            Some(ref_start + (r + i) as i64),
+                            "deletion",
+                            None,
+                            ref_bases.get(r + i).copied().map(char::from),
+                            None,
+                        ));
                     }
-                    _ => py.None().into_py_any(py).unwrap(),
-                },
-                _ => py.None().into_py_any(py).unwrap(),
+                    r += len;
+                }
+                Kind::Skip => r += len,
+                Kind::SoftClip => q += len,
+                Kind::HardClip | Kind::Pad => {}
+            }
+        }
+        Ok(events)
+    }
+
+    /// Counts of matched / mismatched / inserted / deleted / clipped bases.
+    ///
+    /// `M` ops are split into matches and mismatches by comparing against
+    /// `reference_fasta` when given, otherwise from the `MD` tag, otherwise
+    /// from `NM`. Without any of these all `M` bases count as matches.
+    #[pyo3(signature = (reference_fasta=None))]
+    fn alignment_stats<'py>(
+        &self,
+        py: Python<'py>,
+        reference_fasta: Option<&str>,
+    ) -> PyResult<Bound<'py, PyDict>> {
+        let ops = self.cigar_ops();
+        let mut counts = alignment::count_ops(&ops);
+
+        let m_mismatches = if counts.unresolved == 0 {
+            0
+        } else if let Some(fasta_path) = reference_fasta {
+            let name = self.reference_name().ok_or_else(|| {
+                PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                    "record has no reference sequence name",
+                )
+            })?;
+            let (start, end) = match (
+                self.record.alignment_start().and_then(|r| r.ok()),
+                self.record.alignment_end().and_then(|r| r.ok()),
+            ) {
+                (Some(start), Some(end)) => (start, end),
+                _ => {
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                        "record has no alignment span",
+                    ))
+                }
             };
-            vec.push((key, obj));
+            let ref_bases = reference::fetch_bases(fasta_path, &name, start, end)
+                .map_err(|e| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string()))?;
+            let query: Vec<u8> = self.record.sequence().iter().collect();
+            alignment::count_match_op_mismatches(&ops, &query, &ref_bases)
+        } else if let Some(md) = self.string_tag(Tag::MISMATCHED_POSITIONS) {
+            alignment::md_mismatch_count(&md).saturating_sub(counts.mismatches)
+        } else if let Some(Ok(nm)) = self.record.data().get(&Tag::EDIT_DISTANCE) {
+            let nm = nm.as_int().unwrap_or(0).max(0) as usize;
+            nm.saturating_sub(counts.insertions + counts.deletions + counts.mismatches)
+        } else {
+            0
+        };
+        let m_mismatches = m_mismatches.min(counts.unresolved);
+        counts.matches += counts.unresolved - m_mismatches;
+        counts.mismatches += m_mismatches;
+
+        let dict = PyDict::new(py);
+        dict.set_item("matches", counts.matches)?;
+        dict.set_item("mismatches", counts.mismatches)?;
+        dict.set_item("insertions", counts.insertions)?;
+        dict.set_item("deletions", counts.deletions)?;
+        dict.set_item("soft_clips", counts.soft_clips)?;
+        dict.set_item("hard_clips", counts.hard_clips)?;
+        Ok(dict)
+    }
+
+    #[getter]
+    fn tags<'py>(&self, py: Python<'py>) -> PyResult<Vec<(String, PyObject)>> {
+        let mut vec = Vec::new();
+        for (tag, value) in self.record.data().iter().filter_map(Result::ok) {
+            let key = String::from_utf8_lossy(tag.as_ref()).into_owned();
+            vec.push((key, tag_value::value_to_py(py, value, self.tag_conversion)?));
         }
-        vec
+        Ok(vec)
     }
 }
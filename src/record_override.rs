@@ -1,4 +1,3 @@
-use noodles::sam::alignment::record::MappingQuality;
 use noodles::sam::alignment::record_buf::Cigar;
 use noodles::sam::alignment::record_buf::{QualityScores, Sequence as SeqBuf};
 use noodles::sam::alignment::{
@@ -10,11 +9,11 @@ use anyhow::Context;
 use numpy::PyArrayMethods;
 use numpy::{PyArray1, PyReadonlyArray1};
 use pyo3::prelude::*;
-use pyo3::types::PyAny;
+use pyo3::types::{PyAny, PyBytes, PyFloat, PyInt, PyList, PyString};
 
 /// Python 用に限定した「オーバーライド」構造体
 #[pyclass]
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct RecordOverride {
     pub qname: Option<String>,
     pub seq: Option<SeqBuf>,
@@ -23,7 +22,8 @@ pub struct RecordOverride {
     pub cigar: Option<Cigar>,
     pub alignment_start: Option<u32>,
     pub tags: Vec<(Tag, Value)>,
-    pub mapping_quality: Option<MappingQuality>,
+    /// 255 は MAPQ なし (SAM の慣例)
+    pub mapping_quality: Option<u8>,
 }
 
 #[pymethods]
@@ -64,11 +64,6 @@ impl RecordOverride {
             }
         }
 
-        let mapq = match mapping_quality {
-            Some(mq) => MappingQuality::new(mq),
-            None => None,
-        };
-
         RecordOverride {
             qname: qname,
             seq: seq_opt,
@@ -77,7 +72,7 @@ impl RecordOverride {
             cigar: cigar_opt,
             alignment_start: alignment_start,
             tags: tag_vec,
-            mapping_quality: mapq,
+            mapping_quality,
         }
     }
 
@@ -250,22 +245,63 @@ pub fn convert_pyany_to_value(obj: PyObject) -> anyhow::Result<Value> {
     })
 }
 
+/// `set_tag` 用: Python の型から SAM の型を決める
+///
+/// `int` → `i` (Int32), `float` → `f`, `str` → `Z`, 1-byte `bytes` → `A`,
+/// list of ints → `B:i`, list of floats → `B:f`. Numpy arrays fall back
+/// to `convert_pyany_to_value`.
+pub fn convert_pyany_to_tag_value(any: &Bound<'_, PyAny>) -> anyhow::Result<Value> {
+    if any.is_instance_of::<PyInt>() {
+        let i: i64 = any.extract()?;
+        let i =
+            i32::try_from(i).map_err(|_| anyhow::anyhow!("integer {} is out of Int32 range", i))?;
+        return Ok(Value::Int32(i));
+    }
+    if any.is_instance_of::<PyFloat>() {
+        return Ok(Value::Float(any.extract::<f64>()? as f32));
+    }
+    if let Ok(s) = any.downcast::<PyString>() {
+        return Ok(Value::from(s.to_str()?));
+    }
+    if let Ok(b) = any.downcast::<PyBytes>() {
+        return match b.as_bytes() {
+            [c] if c.is_ascii_graphic() => Ok(Value::Character(*c)),
+            _ => Err(anyhow::anyhow!(
+                "a character value must be a single printable byte"
+            )),
+        };
+    }
+    if let Ok(list) = any.downcast::<PyList>() {
+        if list.iter().all(|v| v.is_instance_of::<PyInt>()) {
+            return Ok(Value::from(list.extract::<Vec<i32>>()?));
+        }
+        return Ok(Value::from(list.extract::<Vec<f32>>()?));
+    }
+    convert_pyany_to_value(any.clone().unbind())
+}
+
+/// BAM の op コード順 (`MIDNSHP=X`、`cigar` ゲッターが返すコードと同じ)
+const CIGAR_KINDS: [Kind; 9] = [
+    Kind::Match,
+    Kind::Insertion,
+    Kind::Deletion,
+    Kind::Skip,
+    Kind::SoftClip,
+    Kind::HardClip,
+    Kind::Pad,
+    Kind::SequenceMatch,
+    Kind::SequenceMismatch,
+];
+
 pub fn convert_vec_to_cigar(cigar_list: Vec<(u32, u32)>) -> anyhow::Result<Cigar> {
     let ops: Vec<Op> = cigar_list
         .into_iter()
         .map(|(k, l)| {
-            let kind = match k {
-                0 => Kind::Match,
-                1 => Kind::SequenceMismatch,
-                2 => Kind::Insertion,
-                3 => Kind::Deletion,
-                4 => Kind::Skip,
-                5 => Kind::SoftClip,
-                6 => Kind::HardClip,
-                _ => return Err(anyhow::anyhow!("Invalid CIGAR operation: {}", k)),
-            };
-            Ok(Op::new(kind, l as usize))
+            let kind = CIGAR_KINDS
+                .get(k as usize)
+                .ok_or_else(|| anyhow::anyhow!("Invalid CIGAR operation: {}", k))?;
+            Ok(Op::new(*kind, l as usize))
         })
-        .collect::<Result<_, _>>()?;
+        .collect::<anyhow::Result<_>>()?;
     Ok(Cigar::from(ops))
 }
@@ -0,0 +1,34 @@
+//! Record-at-a-time iteration over a `BamReader`.
+
+use pyo3::prelude::*;
+
+use crate::iterator::BamReader;
+use crate::record::PyBamRecord;
+
+/// Yields single `PyBamRecord`s from the reader's current position.
+///
+/// Shares the reader's position and filter with its chunked iteration, so
+/// the two can be mixed.
+#[pyclass]
+pub struct RecordIterator {
+    reader: Py<BamReader>,
+}
+
+impl RecordIterator {
+    pub fn new(reader: Py<BamReader>) -> Self {
+        Self { reader }
+    }
+}
+
+#[pymethods]
+impl RecordIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<Option<PyBamRecord>> {
+        let mut reader = self.reader.bind(py).try_borrow_mut()?;
+        let rec = reader.next_raw(py, 1)?.pop();
+        Ok(rec.map(|rec| reader.make_record(rec)))
+    }
+}
@@ -0,0 +1,20 @@
+//! Reference FASTA access for methods that compare reads against the genome.
+//!
+//! The FASTA must be indexed (`<path>.fai` next to it).
+
+use noodles::core::{Position, Region};
+use noodles::fasta;
+
+/// Fetch the 1-based, inclusive range `start..=end` of `name` as
+/// upper-case bases.
+pub fn fetch_bases(
+    fasta_path: &str,
+    name: &str,
+    start: Position,
+    end: Position,
+) -> std::io::Result<Vec<u8>> {
+    let mut reader = fasta::io::indexed_reader::Builder::default().build_from_path(fasta_path)?;
+    let region = Region::new(name, start..=end);
+    let record = reader.query(&region)?;
+    Ok(record.sequence().as_ref().to_ascii_uppercase())
+}
@@ -0,0 +1,147 @@
+//! Indexed region queries with the reader's record-size limit.
+//!
+//! noodles' `Reader::query` decodes records itself, which would bypass
+//! `max_record_size`. The chunks listed in the index are read here instead
+//! and every record goes through `read_record_limited`.
+
+use noodles::core::region::Interval;
+use noodles::csi::binning_index::index::reference_sequence::bin::Chunk;
+use noodles::sam::alignment::Record as _;
+use noodles::{bam, bgzf, sam};
+use pyo3::prelude::*;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use crate::filter::RecordFilter;
+use crate::iterator::{read_error_to_py, read_record_limited};
+use crate::record::PyBamRecord;
+use crate::source::{ChunkReader, Source};
+use crate::tag_value::TagConversion;
+
+/// Records overlapping one interval of one reference sequence, or the
+/// unmapped records at the end of the file.
+pub struct RegionQuery {
+    reader: bam::io::Reader<ChunkReader>,
+    /// `None` なら unmapped レコードを返す
+    target: Option<(usize, Interval)>,
+    max_record_size: usize,
+}
+
+impl RegionQuery {
+    pub fn new(
+        source: Source,
+        chunks: Vec<Chunk>,
+        reference_sequence_id: usize,
+        interval: Interval,
+        max_record_size: usize,
+    ) -> Self {
+        Self {
+            reader: bam::io::Reader::from(ChunkReader::new(source, chunks)),
+            target: Some((reference_sequence_id, interval)),
+            max_record_size,
+        }
+    }
+
+    /// `start` から末尾までの unmapped レコード (`region="*"`)
+    pub fn unmapped(source: Source, start: bgzf::VirtualPosition, max_record_size: usize) -> Self {
+        let chunks = vec![Chunk::new(start, bgzf::VirtualPosition::MAX)];
+        Self {
+            reader: bam::io::Reader::from(ChunkReader::new(source, chunks)),
+            target: None,
+            max_record_size,
+        }
+    }
+
+    /// チャンク内のレコードのうち領域に重なるものだけを返す
+    fn intersects(&self, rec: &bam::Record) -> io::Result<bool> {
+        let Some((reference_sequence_id, interval)) = self.target else {
+            return Ok(rec.flags().is_unmapped());
+        };
+        match (
+            rec.reference_sequence_id().transpose()?,
+            rec.alignment_start().transpose()?,
+            rec.alignment_end().transpose()?,
+        ) {
+            (Some(id), Some(start), Some(end)) => {
+                Ok(id == reference_sequence_id && interval.intersects((start..=end).into()))
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+impl Iterator for RegionQuery {
+    type Item = io::Result<bam::Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let mut rec = bam::Record::default();
+            match read_record_limited(&mut self.reader, &mut rec, self.max_record_size) {
+                Ok(0) => return None,
+                Ok(_) => match self.intersects(&rec) {
+                    Ok(true) => return Some(Ok(rec)),
+                    Ok(false) => {}
+                    Err(e) => return Some(Err(e)),
+                },
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+/// Yields the `PyBamRecord`s of a region query as they are read.
+///
+/// Created by `BamReader.fetch`; only the bgzf blocks the index lists for
+/// the region are read, one record at a time. The reader's filter and tag
+/// conversion at the time of the call apply.
+#[pyclass]
+pub struct RegionIterator {
+    query: Mutex<RegionQuery>,
+    header: Arc<sam::Header>,
+    tag_conversion: TagConversion,
+    filter: RecordFilter,
+}
+
+impl RegionIterator {
+    pub fn new(
+        query: RegionQuery,
+        header: Arc<sam::Header>,
+        tag_conversion: TagConversion,
+        filter: RecordFilter,
+    ) -> Self {
+        Self {
+            query: Mutex::new(query),
+            header,
+            tag_conversion,
+            filter,
+        }
+    }
+}
+
+#[pymethods]
+impl RegionIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<Option<PyBamRecord>> {
+        let filter = self.filter;
+        let rec = py
+            .allow_threads(|| -> io::Result<Option<bam::Record>> {
+                let mut query = self.query.lock().unwrap();
+                for result in query.by_ref() {
+                    let rec = result?;
+                    if filter.accepts(&rec) {
+                        return Ok(Some(rec));
+                    }
+                }
+                Ok(None)
+            })
+            .map_err(read_error_to_py)?;
+        Ok(rec.map(|rec| {
+            PyBamRecord::from_record(rec)
+                .with_header(self.header.clone())
+                .with_tag_conversion(self.tag_conversion)
+        }))
+    }
+}
@@ -0,0 +1,82 @@
+//! Lazy rendering of a BAM as SAM text lines.
+
+use noodles::sam::alignment::io::Write as _;
+use noodles::{bam, sam};
+use pyo3::prelude::*;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use crate::iterator::{read_error_to_py, read_record_limited};
+use crate::source::BamStream;
+
+#[pyclass]
+pub struct SamLinesIterator {
+    reader: Mutex<BamStream>,
+    header: Arc<sam::Header>,
+    max_record_size: usize,
+
+    /// まだ返していないヘッダー行
+    header_lines: VecDeque<String>,
+    /// 1 行分の書き出し先 (使い回す)
+    writer: sam::io::Writer<Vec<u8>>,
+}
+
+fn render_err(e: std::io::Error) -> PyErr {
+    PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string())
+}
+
+impl SamLinesIterator {
+    pub fn new(
+        reader: BamStream,
+        header: Arc<sam::Header>,
+        max_record_size: usize,
+    ) -> PyResult<Self> {
+        let mut writer = sam::io::Writer::new(Vec::new());
+        writer.write_header(&header).map_err(render_err)?;
+        let header_lines = String::from_utf8_lossy(writer.get_ref())
+            .lines()
+            .map(str::to_string)
+            .collect();
+        writer.get_mut().clear();
+        Ok(Self {
+            reader: Mutex::new(reader),
+            header,
+            max_record_size,
+            header_lines,
+            writer,
+        })
+    }
+}
+
+#[pymethods]
+impl SamLinesIterator {
+    fn __iter__(slf: PyRefMut<'_, Self>) -> PyRefMut<'_, Self> {
+        slf
+    }
+
+    /// 次の行 (末尾の改行なし)
+    fn __next__(mut slf: PyRefMut<'_, Self>, py: Python<'_>) -> PyResult<Option<String>> {
+        let this = &mut *slf;
+        if let Some(line) = this.header_lines.pop_front() {
+            return Ok(Some(line));
+        }
+        py.allow_threads(|| -> PyResult<Option<String>> {
+            let mut rec = bam::Record::default();
+            let n = read_record_limited(
+                &mut *this.reader.lock().unwrap(),
+                &mut rec,
+                this.max_record_size,
+            )
+            .map_err(read_error_to_py)?;
+            if n == 0 {
+                return Ok(None);
+            }
+            this.writer.get_mut().clear();
+            this.writer
+                .write_alignment_record(&this.header, &rec)
+                .map_err(render_err)?;
+            let line = String::from_utf8_lossy(this.writer.get_ref());
+            Ok(Some(line.trim_end_matches('\n').to_string()))
+        })
+    }
+}
@@ -0,0 +1,81 @@
+//! Iteration with a bounded look-behind buffer.
+
+use noodles::{bam, sam};
+use pyo3::prelude::*;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use crate::iterator::{read_error_to_py, read_record_limited};
+use crate::record::PyBamRecord;
+use crate::source::BamStream;
+use crate::tag_value::TagConversion;
+
+#[pyclass]
+pub struct SlidingIterator {
+    reader: Mutex<BamStream>,
+    header: Arc<sam::Header>,
+    tag_conversion: TagConversion,
+    max_record_size: usize,
+    n_before: usize,
+
+    /// 直前の最大 `n_before` 件 (古い順)
+    previous: VecDeque<bam::Record>,
+}
+
+impl SlidingIterator {
+    pub fn new(
+        reader: BamStream,
+        header: Arc<sam::Header>,
+        tag_conversion: TagConversion,
+        max_record_size: usize,
+        n_before: usize,
+    ) -> Self {
+        Self {
+            reader: Mutex::new(reader),
+            header,
+            tag_conversion,
+            max_record_size,
+            n_before,
+            previous: VecDeque::with_capacity(n_before),
+        }
+    }
+
+    fn wrap(&self, rec: bam::Record) -> PyBamRecord {
+        PyBamRecord::from_record(rec)
+            .with_header(self.header.clone())
+            .with_tag_conversion(self.tag_conversion)
+    }
+}
+
+#[pymethods]
+impl SlidingIterator {
+    fn __iter__(slf: PyRefMut<'_, Self>) -> PyRefMut<'_, Self> {
+        slf
+    }
+
+    fn __next__(
+        mut slf: PyRefMut<'_, Self>,
+        py: Python<'_>,
+    ) -> PyResult<Option<(PyBamRecord, Vec<PyBamRecord>)>> {
+        let this = &mut *slf;
+        let rec = py.allow_threads(|| -> PyResult<Option<bam::Record>> {
+            let mut reader = this.reader.lock().unwrap();
+            let mut rec = bam::Record::default();
+            let n = read_record_limited(&mut *reader, &mut rec, this.max_record_size)
+                .map_err(read_error_to_py)?;
+            Ok((n != 0).then_some(rec))
+        })?;
+        let Some(rec) = rec else {
+            return Ok(None);
+        };
+
+        let before: Vec<PyBamRecord> = this.previous.iter().map(|r| this.wrap(r.clone())).collect();
+        if this.n_before > 0 {
+            if this.previous.len() == this.n_before {
+                this.previous.pop_front();
+            }
+            this.previous.push_back(rec.clone());
+        }
+        Ok(Some((this.wrap(rec), before)))
+    }
+}
@@ -0,0 +1,131 @@
+//! Coordinate-order iteration over nearly-sorted BAMs.
+//!
+//! Records are buffered until no later record may precede them, so only
+//! reads within `window_size` bases of the furthest position seen are kept
+//! in memory.
+
+use noodles::{bam, sam};
+use pyo3::prelude::*;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+use crate::iterator::{read_error_to_py, read_record_limited};
+use crate::record::PyBamRecord;
+use crate::source::BamStream;
+use crate::tag_value::TagConversion;
+
+/// ソートキー (参照 ID, 0-based 開始位置, 入力順)。unplaced は末尾。
+type SortKey = (usize, i64, u64);
+
+fn sort_key(rec: &bam::Record, seq: u64) -> SortKey {
+    let rid = match rec.reference_sequence_id() {
+        Some(Ok(rid)) => rid,
+        _ => usize::MAX,
+    };
+    let pos = match rec.alignment_start() {
+        Some(Ok(pos)) => usize::from(pos) as i64 - 1,
+        _ => -1,
+    };
+    (rid, pos, seq)
+}
+
+#[pyclass]
+pub struct SortWindowIterator {
+    reader: Mutex<BamStream>,
+    header: Arc<sam::Header>,
+    tag_conversion: TagConversion,
+    max_record_size: usize,
+    window_size: i64,
+
+    /// 出力待ちのレコード
+    buffer: BTreeMap<SortKey, bam::Record>,
+    /// これまでに読んだ最大の (参照 ID, 位置)
+    furthest: Option<(usize, i64)>,
+    next_seq: u64,
+    eof: bool,
+}
+
+impl SortWindowIterator {
+    pub fn new(
+        reader: BamStream,
+        header: Arc<sam::Header>,
+        tag_conversion: TagConversion,
+        max_record_size: usize,
+        window_size: i64,
+    ) -> Self {
+        Self {
+            reader: Mutex::new(reader),
+            header,
+            tag_conversion,
+            max_record_size,
+            window_size,
+            buffer: BTreeMap::new(),
+            furthest: None,
+            next_seq: 0,
+            eof: false,
+        }
+    }
+
+    /// 先頭のレコードより前に来るレコードがもう現れないか
+    fn head_is_final(&self) -> bool {
+        let Some((&(rid, pos, _), _)) = self.buffer.first_key_value() else {
+            return false;
+        };
+        match self.furthest {
+            _ if self.eof => true,
+            Some((max_rid, max_pos)) => rid < max_rid || pos < max_pos - self.window_size,
+            None => false,
+        }
+    }
+
+    /// 先頭が確定するまで読み進める
+    fn fill(&mut self) -> PyResult<()> {
+        let mut reader = self.reader.lock().unwrap();
+        while !self.eof && !self.head_is_final() {
+            let mut rec = bam::Record::default();
+            if read_record_limited(&mut *reader, &mut rec, self.max_record_size)
+                .map_err(read_error_to_py)?
+                == 0
+            {
+                self.eof = true;
+                break;
+            }
+            let key = sort_key(&rec, self.next_seq);
+            self.next_seq += 1;
+            if let Some((max_rid, max_pos)) = self.furthest {
+                if key.0 < max_rid || (key.0 == max_rid && key.1 < max_pos - self.window_size) {
+                    let name = rec
+                        .name()
+                        .map(|n| String::from_utf8_lossy(n).into_owned())
+                        .unwrap_or_default();
+                    return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                        "record {} is more than {} bp out of order",
+                        name, self.window_size
+                    )));
+                }
+            }
+            if self.furthest.is_none_or(|max| max < (key.0, key.1)) {
+                self.furthest = Some((key.0, key.1));
+            }
+            self.buffer.insert(key, rec);
+        }
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl SortWindowIterator {
+    fn __iter__(slf: PyRefMut<'_, Self>) -> PyRefMut<'_, Self> {
+        slf
+    }
+
+    fn __next__(mut slf: PyRefMut<'_, Self>, py: Python<'_>) -> PyResult<Option<PyBamRecord>> {
+        let this = &mut *slf;
+        py.allow_threads(|| this.fill())?;
+        Ok(this.buffer.pop_first().map(|(_, rec)| {
+            PyBamRecord::from_record(rec)
+                .with_header(this.header.clone())
+                .with_tag_conversion(this.tag_conversion)
+        }))
+    }
+}
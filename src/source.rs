@@ -0,0 +1,505 @@
+//! Byte sources a `BamReader` can read from.
+//!
+//! Local files, HTTP(S) URLs and Python file objects are all exposed as a
+//! boxed `Read + Seek` so the bgzf / BAM layers stay the same for every
+//! backend. HTTP access uses range requests, which lets indexed queries
+//! download only the bgzf blocks they touch.
+
+use noodles::csi::binning_index::index::reference_sequence::bin::Chunk;
+use noodles::csi::BinningIndex;
+use noodles::{bam, bgzf, csi};
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+
+/// `Read + Seek` that can be moved across the GIL boundary.
+pub trait ReadSeek: Read + Seek + Send {}
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+pub type Source = Box<dyn ReadSeek>;
+
+/// BAM reader over a (possibly multithreaded) bgzf stream.
+pub type BamStream = bam::io::Reader<BgzfReader>;
+
+/// bgzf decompression of a `Source`, inflating blocks on worker threads
+/// when more than one thread is requested.
+pub enum BgzfReader {
+    Single(bgzf::io::Reader<Source>),
+    Multi(bgzf::io::MultithreadedReader<Source>),
+}
+
+impl BgzfReader {
+    pub fn new(source: Source, threads: usize) -> Self {
+        match NonZeroUsize::new(threads) {
+            Some(n) if n.get() > 1 => {
+                Self::Multi(bgzf::io::MultithreadedReader::with_worker_count(n, source))
+            }
+            _ => Self::Single(bgzf::io::Reader::new(source)),
+        }
+    }
+}
+
+impl BgzfReader {
+    /// 次に読むバイトの仮想オフセット
+    pub fn virtual_position(&self) -> bgzf::VirtualPosition {
+        match self {
+            Self::Single(r) => r.virtual_position(),
+            Self::Multi(r) => r.virtual_position(),
+        }
+    }
+
+    /// 仮想オフセットへシークする (次の読み出しはそこから始まる)
+    pub fn seek(&mut self, pos: bgzf::VirtualPosition) -> io::Result<bgzf::VirtualPosition> {
+        match self {
+            Self::Single(r) => r.seek(pos),
+            Self::Multi(r) => bgzf::io::Seek::seek_to_virtual_position(r, pos),
+        }
+    }
+}
+
+impl Read for BgzfReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Single(r) => r.read(buf),
+            Self::Multi(r) => r.read(buf),
+        }
+    }
+}
+
+impl BufRead for BgzfReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        match self {
+            Self::Single(r) => r.fill_buf(),
+            Self::Multi(r) => r.fill_buf(),
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        match self {
+            Self::Single(r) => r.consume(amt),
+            Self::Multi(r) => r.consume(amt),
+        }
+    }
+}
+
+/// インデックスのチャンク列だけを順に読む bgzf リーダー
+///
+/// An owned counterpart of `csi::io::Query`: seeks to the start of each
+/// chunk in turn and reports EOF once the last one has been read, so it
+/// can back a `bam::io::Reader` that outlives the call that opened it.
+pub struct ChunkReader {
+    inner: bgzf::io::Reader<Source>,
+    chunks: std::vec::IntoIter<Chunk>,
+    /// 読み出し中のチャンクの終端 (`None` なら次のチャンクへシークする)
+    chunk_end: Option<bgzf::VirtualPosition>,
+}
+
+impl ChunkReader {
+    pub fn new(source: Source, chunks: Vec<Chunk>) -> Self {
+        Self {
+            inner: bgzf::io::Reader::new(source),
+            chunks: chunks.into_iter(),
+            chunk_end: None,
+        }
+    }
+}
+
+impl Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut src = self.fill_buf()?;
+        let amt = src.read(buf)?;
+        self.consume(amt);
+        Ok(amt)
+    }
+}
+
+impl BufRead for ChunkReader {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        loop {
+            match self.chunk_end {
+                Some(end) if self.inner.virtual_position() < end => {
+                    return self.inner.fill_buf();
+                }
+                _ => match self.chunks.next() {
+                    Some(chunk) => {
+                        self.inner.seek(chunk.start())?;
+                        self.chunk_end = Some(chunk.end());
+                    }
+                    None => return Ok(&[]),
+                },
+            }
+        }
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt);
+    }
+}
+
+/// A loaded `.bai` or `.csi` index.
+pub enum BamIndex {
+    Bai(bam::bai::Index),
+    Csi(csi::Index),
+}
+
+impl BamIndex {
+    /// BAI/CSI 共通のビニングインデックスとして見る
+    pub fn as_binning_index(&self) -> &dyn BinningIndex {
+        match self {
+            BamIndex::Bai(index) => index,
+            BamIndex::Csi(index) => index,
+        }
+    }
+}
+
+/// Where the BAM (and its index) live.
+#[derive(Clone, Debug)]
+pub enum Location {
+    Path(String),
+    Url {
+        url: String,
+        /// `None` なら `url` の隣の `.csi`、次に `.bai` を探す
+        index_url: Option<String>,
+        headers: HashMap<String, String>,
+    },
+    /// `read()` を持つ Python オブジェクト (インデックスなし)
+    FileObj(Arc<Py<PyAny>>),
+}
+
+impl Location {
+    /// Open the BAM stream and read it with `threads` bgzf workers.
+    pub fn open_bam(&self, threads: usize) -> io::Result<BamStream> {
+        Ok(bam::io::Reader::from(BgzfReader::new(
+            self.open()?,
+            threads,
+        )))
+    }
+
+    /// Open the BAM stream.
+    pub fn open(&self) -> io::Result<Source> {
+        match self {
+            Location::Path(path) => Ok(Box::new(File::open(path)?)),
+            Location::Url { url, headers, .. } => {
+                Ok(Box::new(HttpRangeReader::new(url, headers.clone())?))
+            }
+            Location::FileObj(obj) => Ok(Box::new(PyFileReader::new(obj.clone())?)),
+        }
+    }
+
+    /// 先頭から開き直せるか (非シーク可能なファイルオブジェクトは読み切り)
+    pub fn is_rereadable(&self) -> bool {
+        match self {
+            Location::FileObj(obj) => {
+                Python::with_gil(|py| is_seekable(obj.bind(py))).unwrap_or(false)
+            }
+            _ => true,
+        }
+    }
+
+    /// Load the index that sits next to the BAM, preferring `.csi` over
+    /// `.bai` (CSI also covers contigs longer than 512 Mbp). URLs do the
+    /// same unless an explicit `index_url` is given, whose type follows its
+    /// extension.
+    pub fn read_index(&self) -> io::Result<BamIndex> {
+        let (source, is_csi): (Source, bool) = match self {
+            Location::Path(path) => open_index_beside(path, |p| Ok(Box::new(File::open(p)?)))?,
+            Location::Url {
+                url,
+                index_url: None,
+                headers,
+            } => open_index_beside(url, |u| {
+                Ok(Box::new(HttpRangeReader::new(u, headers.clone())?))
+            })?,
+            Location::Url {
+                index_url: Some(index_url),
+                headers,
+                ..
+            } => (
+                Box::new(HttpRangeReader::new(index_url, headers.clone())?),
+                index_url.ends_with(".csi"),
+            ),
+            Location::FileObj(_) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    "BAMs read from a file object have no index",
+                ))
+            }
+        };
+        if is_csi {
+            csi::io::Reader::new(source).read_index().map(BamIndex::Csi)
+        } else {
+            bam::bai::io::Reader::new(source)
+                .read_index()
+                .map(BamIndex::Bai)
+        }
+    }
+}
+
+/// `<base>.csi` を開き、無ければ `<base>.bai` を開く (CSI なら true)
+fn open_index_beside(
+    base: &str,
+    open: impl Fn(&str) -> io::Result<Source>,
+) -> io::Result<(Source, bool)> {
+    let csi = format!("{base}.csi");
+    let bai = format!("{base}.bai");
+    match open(&csi) {
+        Ok(source) => Ok((source, true)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => match open(&bai) {
+            Ok(source) => Ok((source, false)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("index file not found: {csi} or {bai}"),
+            )),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(e),
+    }
+}
+
+/// Size of a single range request. Sequential scans read ahead this much.
+const FETCH_SIZE: u64 = 1 << 20;
+
+/// Blocking HTTP(S) reader issuing `Range` requests on demand.
+pub struct HttpRangeReader {
+    url: String,
+    headers: HashMap<String, String>,
+    len: u64,
+    pos: u64,
+    /// 直近に取得した範囲 (開始オフセットとバイト列)
+    buf_start: u64,
+    buf: Vec<u8>,
+}
+
+impl HttpRangeReader {
+    pub fn new(url: &str, headers: HashMap<String, String>) -> io::Result<Self> {
+        let mut req = ureq::head(url);
+        for (k, v) in &headers {
+            req = req.set(k, v);
+        }
+        let resp = req.call().map_err(|e| match e {
+            // 404 は存在しないファイルとして扱う (インデックスの探索用)
+            ureq::Error::Status(404, _) => {
+                io::Error::new(io::ErrorKind::NotFound, format!("{url}: 404 Not Found"))
+            }
+            e => io::Error::other(e.to_string()),
+        })?;
+        let len = resp
+            .header("Content-Length")
+            .and_then(|v| v.parse::<u64>().ok())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("missing Content-Length for {url}"),
+                )
+            })?;
+        Ok(Self {
+            url: url.to_string(),
+            headers,
+            len,
+            pos: 0,
+            buf_start: 0,
+            buf: Vec::new(),
+        })
+    }
+
+    fn fetch(&mut self, start: u64) -> io::Result<()> {
+        let end = (start + FETCH_SIZE).min(self.len) - 1;
+        let mut req = ureq::get(&self.url).set("Range", &format!("bytes={start}-{end}"));
+        for (k, v) in &self.headers {
+            req = req.set(k, v);
+        }
+        let resp = req.call().map_err(|e| io::Error::other(e.to_string()))?;
+        if resp.status() != 206 {
+            return Err(io::Error::other(format!(
+                "server does not support range requests (status {})",
+                resp.status()
+            )));
+        }
+        let mut buf = Vec::with_capacity((end - start + 1) as usize);
+        resp.into_reader()
+            .take(end - start + 1)
+            .read_to_end(&mut buf)?;
+        // 途中で切れた応答を黙って EOF にしない
+        if (buf.len() as u64) < end - start + 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "short range response for {}: expected {} bytes at offset {start}, got {}",
+                    self.url,
+                    end - start + 1,
+                    buf.len()
+                ),
+            ));
+        }
+        self.buf_start = start;
+        self.buf = buf;
+        Ok(())
+    }
+}
+
+impl Read for HttpRangeReader {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.pos >= self.len || out.is_empty() {
+            return Ok(0);
+        }
+        let buf_end = self.buf_start + self.buf.len() as u64;
+        if self.pos < self.buf_start || self.pos >= buf_end {
+            self.fetch(self.pos)?;
+        }
+        let offset = (self.pos - self.buf_start) as usize;
+        let n = out.len().min(self.buf.len() - offset);
+        out[..n].copy_from_slice(&self.buf[offset..offset + n]);
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for HttpRangeReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_pos = match pos {
+            SeekFrom::Start(n) => Some(n),
+            SeekFrom::End(n) => self.len.checked_add_signed(n),
+            SeekFrom::Current(n) => self.pos.checked_add_signed(n),
+        };
+        match new_pos {
+            Some(n) => {
+                self.pos = n;
+                Ok(n)
+            }
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative position",
+            )),
+        }
+    }
+}
+
+/// Python の読み込み 1 回あたりのサイズ
+const PY_READ_SIZE: usize = 1 << 16;
+
+fn py_io_err(e: PyErr) -> io::Error {
+    io::Error::other(e.to_string())
+}
+
+/// `seekable()` があればそれに従い、なければ `seek` の有無で判断する
+fn is_seekable(obj: &Bound<'_, PyAny>) -> PyResult<bool> {
+    if obj.hasattr("seekable")? {
+        obj.call_method0("seekable")?.is_truthy()
+    } else {
+        obj.hasattr("seek")
+    }
+}
+
+/// Adapter reading from a Python object with a `read(n)` method.
+///
+/// Each adapter tracks its own position. When the object is seekable it
+/// is repositioned before every read, so several adapters (e.g. a
+/// sequential reader and a full scan) can share one object; otherwise
+/// the object is read as a forward-only stream from where it stands.
+pub struct PyFileReader {
+    obj: Arc<Py<PyAny>>,
+    seekable: bool,
+    pos: u64,
+    /// `pos` から始まる先読みデータ
+    buf: Vec<u8>,
+    buf_offset: usize,
+}
+
+impl PyFileReader {
+    pub fn new(obj: Arc<Py<PyAny>>) -> io::Result<Self> {
+        let seekable = Python::with_gil(|py| is_seekable(obj.bind(py))).map_err(py_io_err)?;
+        Ok(Self {
+            obj,
+            seekable,
+            pos: 0,
+            buf: Vec::new(),
+            buf_offset: 0,
+        })
+    }
+
+    /// Read up to `PY_READ_SIZE` bytes at `pos`. `read()` may return fewer
+    /// bytes than asked for, so it is called until the chunk is full or
+    /// it returns an empty result (end of file).
+    fn fill(&mut self) -> io::Result<()> {
+        let chunk = Python::with_gil(|py| -> PyResult<Vec<u8>> {
+            let obj = self.obj.bind(py);
+            if self.seekable {
+                obj.call_method1("seek", (self.pos,))?;
+            }
+            let mut chunk = Vec::with_capacity(PY_READ_SIZE);
+            while chunk.len() < PY_READ_SIZE {
+                let data = obj.call_method1("read", (PY_READ_SIZE - chunk.len(),))?;
+                // 非ブロッキングのストリームはデータなしで None を返す
+                if data.is_none() {
+                    break;
+                }
+                let data = data.downcast::<PyBytes>().map_err(|_| {
+                    PyErr::new::<pyo3::exceptions::PyTypeError, _>("read() must return bytes")
+                })?;
+                if data.as_bytes().is_empty() {
+                    break;
+                }
+                chunk.extend_from_slice(data.as_bytes());
+            }
+            Ok(chunk)
+        })
+        .map_err(py_io_err)?;
+        self.buf = chunk;
+        self.buf_offset = 0;
+        Ok(())
+    }
+}
+
+impl Read for PyFileReader {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if out.is_empty() {
+            return Ok(0);
+        }
+        if self.buf_offset >= self.buf.len() {
+            self.fill()?;
+        }
+        let n = out.len().min(self.buf.len() - self.buf_offset);
+        out[..n].copy_from_slice(&self.buf[self.buf_offset..self.buf_offset + n]);
+        self.buf_offset += n;
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for PyFileReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        if !self.seekable {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "the file object is not seekable",
+            ));
+        }
+        let new_pos = match pos {
+            SeekFrom::Start(n) => n,
+            SeekFrom::Current(n) => self.pos.checked_add_signed(n).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "invalid seek to a negative position",
+                )
+            })?,
+            SeekFrom::End(n) => Python::with_gil(|py| -> PyResult<u64> {
+                self.obj.bind(py).call_method1("seek", (n, 2))?.extract()
+            })
+            .map_err(py_io_err)?,
+        };
+        // 先読み範囲内ならバッファを使い回す
+        let buf_start = self.pos - self.buf_offset as u64;
+        if new_pos >= buf_start && new_pos <= buf_start + self.buf.len() as u64 {
+            self.buf_offset = (new_pos - buf_start) as usize;
+        } else {
+            self.buf.clear();
+            self.buf_offset = 0;
+        }
+        self.pos = new_pos;
+        Ok(new_pos)
+    }
+}
@@ -0,0 +1,71 @@
+//! Packed per-record row for `BamReader.next_struct`.
+
+use noodles::bam;
+use numpy::{Element, PyArrayDescr};
+use pyo3::prelude::*;
+use pyo3::sync::GILOnceCell;
+
+use crate::alignment;
+
+/// `next_struct` の 1 行 (numpy 側の dtype と同じ並び・詰め方)
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug)]
+pub struct StructRow {
+    /// 1-based, -1 if unset (as `PyBamRecord.pos`)
+    pub pos: i64,
+    pub mapq: u8,
+    pub flag: u16,
+    /// -1 if unset
+    pub reference_id: i32,
+    /// reference bases spanned, 0 for unmapped reads
+    pub mapped_length: i32,
+}
+
+impl StructRow {
+    pub const FIELDS: [(&'static str, &'static str); 5] = [
+        ("pos", "<i8"),
+        ("mapq", "u1"),
+        ("flag", "<u2"),
+        ("reference_id", "<i4"),
+        ("mapped_length", "<i4"),
+    ];
+
+    pub fn from_record(rec: &bam::Record) -> Self {
+        Self {
+            pos: rec
+                .alignment_start()
+                .and_then(|p| p.ok())
+                .map_or(-1, |p| usize::from(p) as i64),
+            mapq: rec.mapping_quality().map_or(255, u8::from),
+            flag: u16::from(rec.flags()),
+            reference_id: rec
+                .reference_sequence_id()
+                .and_then(|r| r.ok())
+                .map_or(-1, |r| r as i32),
+            mapped_length: alignment::reference_span(rec)
+                .map_or(0, |(start, end)| (end - start) as i32),
+        }
+    }
+}
+
+// Safety: plain integers only, laid out exactly as the packed dtype built
+// from `FIELDS` (no padding, little-endian fields).
+unsafe impl Element for StructRow {
+    const IS_COPY: bool = true;
+
+    fn get_dtype(py: Python<'_>) -> Bound<'_, PyArrayDescr> {
+        static DTYPE: GILOnceCell<Py<PyArrayDescr>> = GILOnceCell::new();
+        DTYPE
+            .get_or_init(py, || {
+                PyArrayDescr::new(py, StructRow::FIELDS.to_vec())
+                    .expect("valid structured dtype")
+                    .unbind()
+            })
+            .bind(py)
+            .clone()
+    }
+
+    fn clone_ref(&self, _py: Python<'_>) -> Self {
+        *self
+    }
+}
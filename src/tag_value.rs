@@ -0,0 +1,144 @@
+//! Conversion of BAM tag values into Python objects.
+
+use numpy::PyArray1;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use pyo3::IntoPyObjectExt;
+use std::str::FromStr;
+
+use noodles::sam::alignment::record::data::field::value::Array;
+use noodles::sam::alignment::record::data::field::Value as BamValue;
+
+/// How tag values are materialised in Python.
+///
+/// * `Python` – plain `int` / `float` / `str`, numpy arrays for `B` arrays.
+///   `A` tags keep their historical decoding as the decimal character code.
+/// * `Numpy` – numpy scalars preserving the BAM dtype (`c` → `int8`, ...).
+/// * `Raw` – `(type_code, payload)` where `payload` is the little-endian
+///   BAM encoding; arrays use `"B"` followed by the subtype (e.g. `"Bc"`).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TagConversion {
+    #[default]
+    Python,
+    Numpy,
+    Raw,
+}
+
+impl FromStr for TagConversion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "python" => Ok(TagConversion::Python),
+            "numpy" => Ok(TagConversion::Numpy),
+            "raw" => Ok(TagConversion::Raw),
+            _ => Err(format!(
+                "tag_conversion must be 'python', 'numpy' or 'raw', got '{}'",
+                s
+            )),
+        }
+    }
+}
+
+fn numpy_scalar<'py, T>(py: Python<'py>, dtype: &str, v: T) -> PyResult<PyObject>
+where
+    T: IntoPyObject<'py>,
+{
+    Ok(py.import("numpy")?.getattr(dtype)?.call1((v,))?.unbind())
+}
+
+fn array_to_py(py: Python<'_>, arr: Array<'_>) -> PyResult<PyObject> {
+    match arr {
+        Array::Int8(a) => {
+            PyArray1::from_vec(py, a.iter().filter_map(|r| r.ok()).collect()).into_py_any(py)
+        }
+        Array::UInt8(a) => {
+            PyArray1::from_vec(py, a.iter().filter_map(|r| r.ok()).collect()).into_py_any(py)
+        }
+        Array::Int16(a) => {
+            PyArray1::from_vec(py, a.iter().filter_map(|r| r.ok()).collect()).into_py_any(py)
+        }
+        Array::UInt16(a) => {
+            PyArray1::from_vec(py, a.iter().filter_map(|r| r.ok()).collect()).into_py_any(py)
+        }
+        Array::Int32(a) => {
+            PyArray1::from_vec(py, a.iter().filter_map(|r| r.ok()).collect()).into_py_any(py)
+        }
+        Array::UInt32(a) => {
+            PyArray1::from_vec(py, a.iter().filter_map(|r| r.ok()).collect()).into_py_any(py)
+        }
+        Array::Float(a) => {
+            PyArray1::from_vec(py, a.iter().filter_map(|r| r.ok()).collect()).into_py_any(py)
+        }
+    }
+}
+
+/// BAM encoding of `value` as `(type_code, payload)`.
+fn raw_parts(value: BamValue<'_>) -> (String, Vec<u8>) {
+    fn le_bytes<T, const N: usize>(
+        values: impl Iterator<Item = std::io::Result<T>>,
+        f: fn(T) -> [u8; N],
+    ) -> Vec<u8> {
+        values.filter_map(|r| r.ok()).flat_map(f).collect()
+    }
+
+    match value {
+        BamValue::Character(c) => ("A".into(), vec![c]),
+        BamValue::Int8(n) => ("c".into(), n.to_le_bytes().to_vec()),
+        BamValue::UInt8(n) => ("C".into(), vec![n]),
+        BamValue::Int16(n) => ("s".into(), n.to_le_bytes().to_vec()),
+        BamValue::UInt16(n) => ("S".into(), n.to_le_bytes().to_vec()),
+        BamValue::Int32(n) => ("i".into(), n.to_le_bytes().to_vec()),
+        BamValue::UInt32(n) => ("I".into(), n.to_le_bytes().to_vec()),
+        BamValue::Float(f) => ("f".into(), f.to_le_bytes().to_vec()),
+        BamValue::String(bs) => ("Z".into(), bs.to_vec()),
+        BamValue::Hex(bs) => ("H".into(), bs.to_vec()),
+        BamValue::Array(arr) => match arr {
+            Array::Int8(a) => ("Bc".into(), le_bytes(a.iter(), i8::to_le_bytes)),
+            Array::UInt8(a) => ("BC".into(), le_bytes(a.iter(), u8::to_le_bytes)),
+            Array::Int16(a) => ("Bs".into(), le_bytes(a.iter(), i16::to_le_bytes)),
+            Array::UInt16(a) => ("BS".into(), le_bytes(a.iter(), u16::to_le_bytes)),
+            Array::Int32(a) => ("Bi".into(), le_bytes(a.iter(), i32::to_le_bytes)),
+            Array::UInt32(a) => ("BI".into(), le_bytes(a.iter(), u32::to_le_bytes)),
+            Array::Float(a) => ("Bf".into(), le_bytes(a.iter(), f32::to_le_bytes)),
+        },
+    }
+}
+
+/// Convert a decoded tag value into a Python object according to `conv`.
+pub fn value_to_py(py: Python<'_>, value: BamValue<'_>, conv: TagConversion) -> PyResult<PyObject> {
+    match conv {
+        TagConversion::Python => match value {
+            BamValue::Int8(n) => (n as i32).into_py_any(py),
+            BamValue::UInt8(n) => (n as u32).into_py_any(py),
+            BamValue::Int16(n) => (n as i32).into_py_any(py),
+            BamValue::UInt16(n) => (n as u32).into_py_any(py),
+            BamValue::Int32(n) => n.into_py_any(py),
+            BamValue::UInt32(n) => n.into_py_any(py),
+            BamValue::Float(f) => (f as f64).into_py_any(py),
+            // 従来どおり文字コードの 10 進表記 (`A:G` → "71")
+            BamValue::Character(c) => c.to_string().into_py_any(py),
+            BamValue::String(bs) => String::from_utf8_lossy(bs).into_owned().into_py_any(py),
+            BamValue::Array(arr) => array_to_py(py, arr),
+            BamValue::Hex(_) => Ok(py.None()),
+        },
+        TagConversion::Numpy => match value {
+            BamValue::Int8(n) => numpy_scalar(py, "int8", n),
+            BamValue::UInt8(n) => numpy_scalar(py, "uint8", n),
+            BamValue::Int16(n) => numpy_scalar(py, "int16", n),
+            BamValue::UInt16(n) => numpy_scalar(py, "uint16", n),
+            BamValue::Int32(n) => numpy_scalar(py, "int32", n),
+            BamValue::UInt32(n) => numpy_scalar(py, "uint32", n),
+            BamValue::Float(f) => numpy_scalar(py, "float32", f),
+            BamValue::Character(c) => (c as char).to_string().into_py_any(py),
+            BamValue::String(bs) | BamValue::Hex(bs) => {
+                String::from_utf8_lossy(bs).into_owned().into_py_any(py)
+            }
+            BamValue::Array(arr) => array_to_py(py, arr),
+        },
+        TagConversion::Raw => {
+            let (code, payload) = raw_parts(value);
+            (code, PyBytes::new(py, &payload)).into_py_any(py)
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! Streaming BAM → BAM rewrite through a Python callback.
+
+use noodles::sam::alignment::io::Write as _;
+use noodles::sam::alignment::record::data::field::Tag;
+use noodles::sam::alignment::record_buf::data::field::Value;
+use noodles::sam::alignment::RecordBuf;
+use noodles::sam::header::record::value::map::{program::tag as program_tag, Map, Program};
+use noodles::{bam, sam};
+use pyo3::prelude::*;
+use std::fs::File;
+use std::io;
+use std::sync::Arc;
+
+use crate::iterator::{read_error_to_py, read_record_limited, DEFAULT_MAX_RECORD_SIZE};
+use crate::record::PyBamRecord;
+use crate::record_override;
+
+/// Records handed to Python per GIL round trip.
+const DEFAULT_TRANSFORM_CHUNK: usize = 1000;
+
+/// `func` の戻り値を RecordBuf に変換して `out` に追加する
+fn collect_result(result: &Bound<'_, PyAny>, out: &mut Vec<RecordBuf>) -> PyResult<()> {
+    let to_buf = |rec: PyRef<'_, PyBamRecord>| {
+        rec.to_record_buf()
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))
+    };
+    if result.is_none() {
+        return Ok(());
+    }
+    if let Ok(rec) = result.extract::<PyRef<'_, PyBamRecord>>() {
+        out.push(to_buf(rec)?);
+        return Ok(());
+    }
+    if let Ok(recs) = result.extract::<Vec<PyRef<'_, PyBamRecord>>>() {
+        for rec in recs {
+            out.push(to_buf(rec)?);
+        }
+        return Ok(());
+    }
+    Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+        "transform function must return a PyBamRecord, a list of PyBamRecord or None",
+    ))
+}
+
+/// Rewrite `input_path` to `output_path`, passing every record through
+/// `func`.
+///
+/// `func` receives a `PyBamRecord` and returns a record (e.g. the same one
+/// with an override set), a list of records to emit several, or `None` to
+/// drop it. Records are read, converted and written in batches of
+/// `chunk_size` with the GIL released around the I/O. The input header is
+/// copied to the output. Returns the number of records written. A record
+/// larger than `max_record_size` raises `ValueError`.
+#[pyfunction]
+#[pyo3(signature = (input_path, output_path, func, chunk_size=None, max_record_size=None))]
+pub fn transform(
+    py: Python<'_>,
+    input_path: &str,
+    output_path: &str,
+    func: &Bound<'_, PyAny>,
+    chunk_size: Option<usize>,
+    max_record_size: Option<usize>,
+) -> PyResult<u64> {
+    let chunk_size = chunk_size.unwrap_or(DEFAULT_TRANSFORM_CHUNK).max(1);
+    let max_record_size = max_record_size.unwrap_or(DEFAULT_MAX_RECORD_SIZE);
+    let io_err = |e: std::io::Error| PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string());
+
+    let mut reader = File::open(input_path)
+        .map(bam::io::Reader::new)
+        .map_err(io_err)?;
+    let header: Arc<sam::Header> = Arc::new(reader.read_header().map_err(io_err)?);
+    let mut writer = File::create(output_path)
+        .map(bam::io::Writer::new)
+        .map_err(io_err)?;
+    writer.write_header(&header).map_err(io_err)?;
+
+    let mut written = 0u64;
+    loop {
+        // ── 1. GIL なしでチャンクを読む
+        let records = py
+            .allow_threads(|| -> std::io::Result<Vec<bam::Record>> {
+                let mut v = Vec::with_capacity(chunk_size);
+                while v.len() < chunk_size {
+                    let mut rec = bam::Record::default();
+                    if read_record_limited(&mut reader, &mut rec, max_record_size)? == 0 {
+                        break;
+                    }
+                    v.push(rec);
+                }
+                Ok(v)
+            })
+            .map_err(read_error_to_py)?;
+        if records.is_empty() {
+            break;
+        }
+
+        // ── 2. Python 関数を適用
+        let mut bufs = Vec::with_capacity(records.len());
+        for rec in records {
+            let obj = Py::new(
+                py,
+                PyBamRecord::from_record(rec).with_header(header.clone()),
+            )?;
+            let result = func.call1((obj,))?;
+            collect_result(&result, &mut bufs)?;
+        }
+
+        // ── 3. GIL なしで書き出す
+        py.allow_threads(|| -> std::io::Result<()> {
+            for buf in &bufs {
+                writer.write_alignment_record(&header, buf)?;
+            }
+            Ok(())
+        })
+        .map_err(io_err)?;
+        written += bufs.len() as u64;
+    }
+
+    writer.try_finish().map_err(io_err)?;
+    Ok(written)
+}
+
+fn stamp(
+    input_path: &str,
+    output_path: &str,
+    tag: Tag,
+    value: &Value,
+    max_record_size: usize,
+) -> io::Result<u64> {
+    let mut reader = File::open(input_path).map(bam::io::Reader::new)?;
+    let mut header = reader.read_header()?;
+    let command_line = format!(
+        "tag_all {} {} {}",
+        String::from_utf8_lossy(tag.as_ref()),
+        input_path,
+        output_path
+    );
+    let program = Map::<Program>::builder()
+        .insert(program_tag::NAME, "lazybam")
+        .insert(program_tag::VERSION, env!("CARGO_PKG_VERSION"))
+        .insert(program_tag::COMMAND_LINE, command_line)
+        .build()
+        .map_err(io::Error::other)?;
+    header.programs_mut().add("lazybam", program)?;
+
+    let mut writer = File::create(output_path).map(bam::io::Writer::new)?;
+    writer.write_header(&header)?;
+    let mut rec = bam::Record::default();
+    let mut written = 0u64;
+    while read_record_limited(&mut reader, &mut rec, max_record_size)? != 0 {
+        let mut buf = RecordBuf::try_from_alignment_record(&header, &rec)?;
+        buf.data_mut().insert(tag, value.clone());
+        writer.write_alignment_record(&header, &buf)?;
+        written += 1;
+    }
+    writer.try_finish()?;
+    Ok(written)
+}
+
+/// Copy `input_path` to `output_path` with `tag_name` set to `value` on
+/// every record, replacing any existing value.
+///
+/// The tag type is inferred from `value` as for `RecordOverride` tags.
+/// A `@PG` line (`PN:lazybam`) is appended to the header, chained after
+/// the existing programs. Returns the number of records written. A record
+/// larger than `max_record_size` raises `ValueError`.
+#[pyfunction]
+#[pyo3(signature = (input_path, output_path, tag_name, value, max_record_size=None))]
+pub fn tag_all(
+    py: Python<'_>,
+    input_path: &str,
+    output_path: &str,
+    tag_name: &str,
+    value: &Bound<'_, PyAny>,
+    max_record_size: Option<usize>,
+) -> PyResult<u64> {
+    let value_err =
+        |e: anyhow::Error| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string());
+    let tag = record_override::convert_string_to_tag(tag_name.to_string()).map_err(value_err)?;
+    let value =
+        record_override::convert_pyany_to_value(value.clone().unbind()).map_err(value_err)?;
+    let max_record_size = max_record_size.unwrap_or(DEFAULT_MAX_RECORD_SIZE);
+    py.allow_threads(|| stamp(input_path, output_path, tag, &value, max_record_size))
+        .map_err(read_error_to_py)
+}
@@ -9,14 +9,27 @@ use crate::record::PyBamRecord;
 use crate::record_buf::PyRecordBuf;
 use crate::write_bams::write_chunk;
 
+/// `block_records` は 1 以上 (0 では区切れない)
+pub(crate) fn check_block_records(block_records: Option<usize>) -> PyResult<()> {
+    if block_records == Some(0) {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "block_records must be positive",
+        ));
+    }
+    Ok(())
+}
+
 #[pyfunction]
+#[pyo3(signature = (header_bytes, records, out_bam, sort, block_records=None))]
 pub fn write_chunk_py(
     py: Python<'_>, // ★ 追加
     header_bytes: Vec<u8>,
     records: Vec<PyRef<PyBamRecord>>, // PyRef → Py<...> に
     out_bam: &str,
     sort: bool,
+    block_records: Option<usize>,
 ) -> PyResult<()> {
+    check_block_records(block_records)?;
     // ── 1. ヘッダ復元（GIL 必須） ─────────────────────────────
     let hdr_txt = std::str::from_utf8(&header_bytes)
         .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
@@ -35,18 +48,21 @@ pub fn write_chunk_py(
     drop(records); // PyObject の参照を早めに解放（任意）
 
     // ── 3. 重い処理を GIL なしで実行 ────────────────────────
-    py.allow_threads(|| write_chunk(&header, &mut bufs, out_bam, sort))
+    py.allow_threads(|| write_chunk(&header, &mut bufs, out_bam, sort, block_records))
         .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))
 }
 
 #[pyfunction]
+#[pyo3(signature = (header_bytes, records, out_bam, sort, block_records=None))]
 pub fn write_recordbuf_chunk_py(
     py: Python<'_>,
     header_bytes: Vec<u8>,
     records: Vec<PyRef<PyRecordBuf>>,
     out_bam: &str,
     sort: bool,
+    block_records: Option<usize>,
 ) -> PyResult<()> {
+    check_block_records(block_records)?;
     // ── 1. ヘッダー復元 ───────────────────────────────────────────────
     let hdr_txt = std::str::from_utf8(&header_bytes)
         .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
@@ -66,7 +82,7 @@ pub fn write_recordbuf_chunk_py(
     drop(records);
 
     // ── 3. 重い I/O 処理を GIL なしで実行 ───────────────────────────────
-    py.allow_threads(|| write_chunk(&header, &mut bufs, out_bam, sort))
+    py.allow_threads(|| write_chunk(&header, &mut bufs, out_bam, sort, block_records))
         .map_err(|e| pyo3::exceptions::PyIOError::new_err(e.to_string()))
 }
 
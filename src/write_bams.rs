@@ -1,6 +1,7 @@
 use noodles::bam;
 use noodles::sam;
 use sam::alignment::io::Write;
+use std::io::Write as _;
 use std::{fs::File, path::Path};
 
 /// Write a chunk of alignments to a BAM file.
@@ -21,6 +22,10 @@ use std::{fs::File, path::Path};
 ///   (`reference_sequence_id`, then `alignment_start`) **before** writing.  
 ///   Sorting is done with `rayon::par_sort_unstable_by`, exploiting all CPU
 ///   cores.
+/// * `block_records` – If `Some(n)`, the current BGZF block is closed after
+///   every `n` records so block boundaries coincide with record boundaries.
+///   `n` must be positive. A block still splits early when `n` records exceed the 64 KiB BGZF
+///   block limit.
 ///
 /// Returns
 /// -------
@@ -37,13 +42,14 @@ use std::{fs::File, path::Path};
 /// Example
 /// -------
 /// ```
-/// write_chunk(&header, &mut chunk, "chunk_001.bam", true)?;
+/// write_chunk(&header, &mut chunk, "chunk_001.bam", true, None)?;
 /// ```
 pub fn write_chunk<P>(
     header: &sam::Header,
-    records: &mut Vec<sam::alignment::RecordBuf>,
+    records: &mut [sam::alignment::RecordBuf],
     path: P,
     sort: bool,
+    block_records: Option<usize>,
 ) -> std::io::Result<()>
 where
     P: AsRef<Path>,
@@ -59,8 +65,17 @@ where
 
     // ── 3. Emit header and alignment records ────────────────────────────────
     writer.write_header(header)?;
-    for rec in records {
+    // ヘッダは独立したブロックに置く
+    if block_records.is_some() {
+        writer.get_mut().flush()?;
+    }
+    for (i, rec) in records.iter().enumerate() {
         writer.write_alignment_record(header, rec)?;
+        if let Some(n) = block_records {
+            if (i + 1) % n == 0 {
+                writer.get_mut().flush()?;
+            }
+        }
     }
 
     // ── 4. Flush BGZF blocks to disk ────────────────────────────────────────
@@ -0,0 +1,117 @@
+//! Incremental BAM writing from Python.
+
+use noodles::sam::alignment::io::Write as _;
+use noodles::{bam, bgzf, sam};
+use pyo3::prelude::*;
+use std::fs::File;
+use std::io::Write as _;
+use std::sync::Mutex;
+
+use crate::record::PyBamRecord;
+use crate::write::check_block_records;
+
+type Writer = bam::io::Writer<bgzf::io::Writer<File>>;
+
+fn io_err(e: std::io::Error) -> PyErr {
+    PyErr::new::<pyo3::exceptions::PyIOError, _>(e.to_string())
+}
+
+/// BAM file opened for writing records one at a time.
+///
+/// `header_bytes` is SAM header text, e.g. `reader._header` or
+/// `reader.header.to_bytes()`. The file is finalised (including the bgzf
+/// EOF block) by `close()` or on leaving a `with` block. With
+/// `block_records=n` the bgzf block is closed after every `n` records, as
+/// in `write_chunk_py`.
+#[pyclass]
+pub struct BamWriter {
+    header: sam::Header,
+    block_records: Option<usize>,
+    /// close 後は None
+    writer: Mutex<Option<OpenWriter>>,
+}
+
+/// 書き込み中のファイルと書いたレコード数
+struct OpenWriter {
+    writer: Writer,
+    records: usize,
+}
+
+impl BamWriter {
+    fn with_writer<T>(&self, f: impl FnOnce(&mut OpenWriter) -> std::io::Result<T>) -> PyResult<T> {
+        let mut guard = self.writer.lock().unwrap();
+        let writer = guard.as_mut().ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>("write to a closed BamWriter")
+        })?;
+        f(writer).map_err(io_err)
+    }
+}
+
+#[pymethods]
+impl BamWriter {
+    #[new]
+    #[pyo3(signature = (path, header_bytes, block_records=None))]
+    fn new(path: &str, header_bytes: Vec<u8>, block_records: Option<usize>) -> PyResult<Self> {
+        check_block_records(block_records)?;
+        let hdr_txt = std::str::from_utf8(&header_bytes)
+            .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+        let header: sam::Header = hdr_txt.parse().map_err(|e: sam::header::ParseError| {
+            pyo3::exceptions::PyValueError::new_err(e.to_string())
+        })?;
+        let mut writer = File::create(path)
+            .map(bam::io::Writer::new)
+            .map_err(io_err)?;
+        writer.write_header(&header).map_err(io_err)?;
+        // ヘッダは独立したブロックに置く
+        if block_records.is_some() {
+            writer.get_mut().flush().map_err(io_err)?;
+        }
+        Ok(Self {
+            header,
+            block_records,
+            writer: Mutex::new(Some(OpenWriter { writer, records: 0 })),
+        })
+    }
+
+    /// レコードを 1 件書き出す (override 適用済み)
+    fn write(&self, py: Python<'_>, record: PyRef<'_, PyBamRecord>) -> PyResult<()> {
+        let buf = record
+            .to_record_buf()
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?;
+        drop(record);
+        py.allow_threads(|| {
+            self.with_writer(|w| {
+                w.writer.write_alignment_record(&self.header, &buf)?;
+                w.records += 1;
+                match self.block_records {
+                    Some(n) if w.records % n == 0 => w.writer.get_mut().flush(),
+                    _ => Ok(()),
+                }
+            })
+        })
+    }
+
+    /// Flush and write the bgzf EOF block. Closing twice is a no-op.
+    fn close(&self, py: Python<'_>) -> PyResult<()> {
+        py.allow_threads(|| {
+            let Some(mut open) = self.writer.lock().unwrap().take() else {
+                return Ok(());
+            };
+            open.writer.try_finish().map_err(io_err)
+        })
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __exit__(
+        &self,
+        py: Python<'_>,
+        _exc_type: PyObject,
+        _exc_val: PyObject,
+        _trace: PyObject,
+    ) -> PyResult<()> {
+        self.close(py)
+    }
+}
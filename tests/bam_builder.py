@@ -0,0 +1,147 @@
+"""Minimal BAM encoder for crafting test inputs the writer API cannot express
+(mate fields and template lengths from scratch, malformed records)."""
+
+import struct
+import zlib
+
+CIGAR_OPS = "MIDNSHP=X"
+SEQ_CODES = {b: i for i, b in enumerate("=ACMGRSVTWYHKDBN")}
+
+
+def bgzf_block(data: bytes) -> bytes:
+    compressor = zlib.compressobj(6, zlib.DEFLATED, -15)
+    cdata = compressor.compress(data) + compressor.flush()
+    header = b"\x1f\x8b\x08\x04\x00\x00\x00\x00\x00\xff\x06\x00BC\x02\x00"
+    bsize = len(header) + 2 + len(cdata) + 8 - 1
+    trailer = struct.pack("<II", zlib.crc32(data), len(data))
+    return header + struct.pack("<H", bsize) + cdata + trailer
+
+
+def reg2bin(beg: int, end: int) -> int:
+    end -= 1
+    for shift, offset in ((14, 4681), (17, 585), (20, 73), (23, 9), (26, 1)):
+        if beg >> shift == end >> shift:
+            return offset + (beg >> shift)
+    return 0
+
+
+def encode_header(references) -> bytes:
+    text = "@HD\tVN:1.6\tSO:unsorted\n" + "".join(
+        f"@SQ\tSN:{name}\tLN:{length}\n" for name, length in references
+    )
+    out = b"BAM\x01" + struct.pack("<i", len(text)) + text.encode()
+    out += struct.pack("<i", len(references))
+    for name, length in references:
+        out += struct.pack("<i", len(name) + 1) + name.encode() + b"\x00"
+        out += struct.pack("<i", length)
+    return out
+
+
+TAG_FORMATS = {"c": "<b", "C": "<B", "s": "<h", "S": "<H", "i": "<i", "I": "<I", "f": "<f"}
+
+
+def encode_tag(tag, type_code, value) -> bytes:
+    """Scalar aux field: `A` takes a character, `Z` a string, the numeric
+    types a number."""
+    if type_code == "A":
+        payload = value.encode()
+    elif type_code == "Z":
+        payload = value.encode() + b"\x00"
+    else:
+        payload = struct.pack(TAG_FORMATS[type_code], value)
+    return tag.encode() + type_code.encode() + payload
+
+
+def encode_record(
+    qname,
+    flag=0,
+    rid=-1,
+    pos=-1,
+    mapq=60,
+    cigar=(),
+    next_rid=-1,
+    next_pos=-1,
+    tlen=0,
+    seq="",
+    qual=None,
+    tags=(),
+) -> bytes:
+    """`pos` / `next_pos` are 0-based; `cigar` is a list of (op, len);
+    `tags` is a list of (tag, type, value), see `encode_tag`."""
+    ref_len = sum(n for op, n in cigar if op in "MDN=X")
+    bin_ = reg2bin(pos, pos + max(ref_len, 1)) if pos >= 0 else 4680
+    name = qname.encode() + b"\x00"
+    body = struct.pack(
+        "<iiBBHHHiiii",
+        rid,
+        pos,
+        len(name),
+        mapq,
+        bin_,
+        len(cigar),
+        flag,
+        len(seq),
+        next_rid,
+        next_pos,
+        tlen,
+    )
+    body += name
+    body += b"".join(struct.pack("<I", n << 4 | CIGAR_OPS.index(op)) for op, n in cigar)
+    codes = [SEQ_CODES[b] for b in seq.upper()]
+    if len(codes) % 2:
+        codes.append(0)
+    body += bytes(codes[i] << 4 | codes[i + 1] for i in range(0, len(codes), 2))
+    body += bytes(qual) if qual is not None else b"\xff" * len(seq)
+    body += b"".join(encode_tag(*t) for t in tags)
+    return struct.pack("<i", len(body)) + body
+
+
+def write_bam(path, references, records) -> None:
+    """Write `records` (output of `encode_record`) after a header listing
+    `references` as `(name, length)` pairs."""
+    data = encode_header(references) + b"".join(records)
+    blocks = [bgzf_block(data[i : i + 0xFF00]) for i in range(0, len(data), 0xFF00)]
+    with open(path, "wb") as fh:
+        fh.write(b"".join(blocks) + bgzf_block(b""))
+
+
+def write_bam_with_csi(path, references, records, min_shift=14, depth=5) -> None:
+    """Like `write_bam`, plus a minimal `<path>.csi` so region queries work
+    without samtools.
+
+    The header and the records go in separate bgzf blocks (the records must
+    fit in one). Each reference's records, which must be contiguous, are
+    listed as a single chunk in the root bin; readers filter the chunk by
+    overlap, so queries return the same records as with a full index.
+    """
+    header = encode_header(references)
+    data = b"".join(records)
+    assert len(data) <= 0xFF00, "records must fit in one bgzf block"
+    header_block = bgzf_block(header)
+    with open(path, "wb") as fh:
+        fh.write(header_block + bgzf_block(data) + bgzf_block(b""))
+
+    # virtual offsets of the records: block start << 16 | offset in block
+    spans = {}
+    offset = 0
+    for rec in records:
+        rid = struct.unpack_from("<i", rec, 4)[0]
+        start, _ = spans.get(rid, (offset, None))
+        offset += len(rec)
+        spans[rid] = (start, offset)
+
+    def voffset(within):
+        return len(header_block) << 16 | within
+
+    index = b"CSI\x01" + struct.pack("<iii", min_shift, depth, 0)
+    index += struct.pack("<i", len(references))
+    for rid in range(len(references)):
+        if rid not in spans:
+            index += struct.pack("<i", 0)
+            continue
+        beg, end = (voffset(o) for o in spans[rid])
+        index += struct.pack("<i", 1)
+        index += struct.pack("<IQi", 0, beg, 1) + struct.pack("<QQ", beg, end)
+    index += struct.pack("<Q", 0)
+    with open(f"{path}.csi", "wb") as fh:
+        fh.write(bgzf_block(index) + bgzf_block(b""))
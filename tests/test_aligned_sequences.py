@@ -0,0 +1,44 @@
+import tempfile
+from pathlib import Path
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+ref_seq = "ACGTACGTAC" + "G" + "TTGCATTGCA" + "A" * 19
+
+# 2S + 10M + 1D + 3M + 2I + 7M; the query skips the G at reference 10
+query = "NN" + ref_seq[:10] + ref_seq[11:14] + "CC" + ref_seq[14:21]
+record = encode_record(
+    "del1",
+    rid=0,
+    pos=0,
+    cigar=[("S", 2), ("M", 10), ("D", 1), ("M", 3), ("I", 2), ("M", 7)],
+    seq=query,
+    qual=[30] * len(query),
+)
+
+with tempfile.TemporaryDirectory() as tmp:
+    bam_path = Path(tmp) / "del.bam"
+    write_bam(bam_path, [("chr1", len(ref_seq))], [record])
+    fasta_path = Path(tmp) / "ref.fa"
+    fasta_path.write_text(">chr1\n" + ref_seq + "\n")
+    Path(str(fasta_path) + ".fai").write_text(f"chr1\t{len(ref_seq)}\t6\t{len(ref_seq)}\t{len(ref_seq) + 1}\n")
+
+    read = next(lb.BamReader(str(bam_path)))[0]
+    ref_aln, query_aln = read.aligned_sequences(str(fasta_path))
+    events = read.alignment_events(str(fasta_path))
+
+assert len(ref_aln) == len(query_aln)
+assert ref_aln == ref_seq[:14] + "--" + ref_seq[14:21]
+assert query_aln == ref_seq[:10] + "-" + ref_seq[11:14] + "CC" + ref_seq[14:21]
+assert query_aln[10] == "-" and ref_aln[10] == "G"
+
+# events rebuild the same alignment
+kinds = [e[2] for e in events]
+assert kinds.count("deletion") == 1 and kinds.count("insertion") == 2
+assert set(kinds) <= {"match", "insertion", "deletion"}
+assert "".join(e[4] or "-" for e in events) == ref_aln
+assert "".join(e[3] or "-" for e in events) == query_aln
+assert events[0] == (2, 0, "match", "A", "A", 30)
+assert events[10] == (None, 10, "deletion", None, "G", None)
+assert [e[0] for e in events if e[0] is not None] == list(range(2, len(query)))
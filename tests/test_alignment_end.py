@@ -0,0 +1,34 @@
+import tempfile
+from pathlib import Path
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+records = [
+    # 3M + 2D + 3M on the reference from 0-based 10; clips and insertions don't count
+    encode_record(
+        "indel", rid=0, pos=10, cigar=[("S", 2), ("M", 3), ("I", 2), ("D", 2), ("M", 3)], seq="TTACGAAGTC"
+    ),
+    encode_record("spliced", rid=0, pos=0, cigar=[("M", 5), ("N", 100), ("=", 2), ("X", 1)], seq="ACGTACGT"),
+    # unmapped mate placed at its partner's position
+    encode_record("placed_unmapped", flag=0x4, rid=0, pos=10, seq="ACGT"),
+    encode_record("unplaced", flag=0x4, seq="ACGT"),
+    # mapped flag but CIGAR `*`
+    encode_record("no_cigar", rid=0, pos=20, seq="ACGT"),
+]
+
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "ends.bam"
+    write_bam(path, [("chr1", 1000)], records)
+    recs = {r.qname: r for chunk in lb.BamReader(str(path)) for r in chunk}
+
+assert recs["indel"].alignment_end == 10 + 8
+assert recs["spliced"].alignment_end == 0 + 5 + 100 + 3
+for name in ("placed_unmapped", "unplaced", "no_cigar"):
+    assert recs[name].alignment_end == -1, name
+
+for rec in recs.values():
+    assert rec.reference_end == rec.alignment_end
+    if rec.alignment_end >= 0:
+        # pos is 1-based, the end 0-based exclusive
+        assert rec.alignment_end == rec.pos - 1 + rec.reference_length
@@ -0,0 +1,59 @@
+import tempfile
+from pathlib import Path
+
+import pyarrow as pa
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+records = [r for chunk in lb.BamReader(str(path_to_bam), chunk_size=1000) for r in chunk]
+
+f = lb.BamReader(str(path_to_bam), chunk_size=4)
+columns = ["qname", "flag", "rname", "pos", "mapq", "cigar_string", "seq", "tlen"]
+batches = list(f.to_arrow_batches(columns))
+assert [b.num_rows for b in batches] == [4, 4, 2]
+assert all(isinstance(b, pa.RecordBatch) for b in batches)
+
+table = pa.Table.from_batches(batches)
+assert table.column_names == columns
+assert str(table.schema.field("pos").type) == "int64"
+assert str(table.schema.field("flag").type) == "uint16"
+assert table.column("qname").to_pylist() == [r.qname for r in records]
+assert table.column("rname").to_pylist() == [r.rname for r in records]
+assert table.column("pos").to_pylist() == [r.pos for r in records]
+assert table.column("seq").to_pylist() == [r.seq for r in records]
+assert table.column("cigar_string").to_pylist() == [
+    "".join(f"{n}{'MIDNSHP=X'[op]}" for op, n in r.cigar) for r in records
+]
+
+# column subset and order follow the request
+(batch,) = lb.BamReader(str(path_to_bam), chunk_size=100).to_arrow_batches(["pos", "qname"])
+assert batch.schema.names == ["pos", "qname"]
+
+try:
+    f.to_arrow_batches(["qname", "nope"])
+except ValueError as e:
+    assert "nope" in str(e)
+else:
+    raise AssertionError("unknown column accepted")
+
+# missing values are nulls, not sentinels
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "unplaced.bam"
+    write_bam(
+        path,
+        [("chr1", 1000)],
+        [
+            encode_record("placed", rid=0, pos=9, mapq=30, cigar=[("M", 4)], seq="ACGT"),
+            encode_record("unplaced", flag=0x4, mapq=255),
+        ],
+    )
+    (batch,) = lb.BamReader(str(path)).to_arrow_batches(
+        ["rname", "pos", "mapq", "cigar_string", "seq"]
+    )
+    assert batch.to_pylist() == [
+        {"rname": "chr1", "pos": 10, "mapq": 30, "cigar_string": "4M", "seq": "ACGT"},
+        {"rname": None, "pos": None, "mapq": None, "cigar_string": None, "seq": None},
+    ]
@@ -0,0 +1,59 @@
+import tempfile
+from pathlib import Path
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+f = lb.BamReader(str(path_to_bam), chunk_size=1000)
+records = [r for chunk in f for r in chunk]
+
+with tempfile.TemporaryDirectory() as tmp:
+    out_path = Path(tmp) / "filtered.bam"
+    with lb.BamWriter(str(out_path), f._header) as writer:
+        for record in records[::2]:
+            writer.write(record)
+
+    # the file ends with the bgzf EOF block and reads back intact
+    eof = bytes.fromhex("1f8b08040000000000ff0600424302001b0003000000000000000000")
+    assert out_path.read_bytes().endswith(eof)
+    written = [r for chunk in lb.BamReader(str(out_path)) for r in chunk]
+    assert [r.qname for r in written] == [r.qname for r in records[::2]]
+    assert [r.seq for r in written] == [r.seq for r in records[::2]]
+
+    # writing after close is an error, closing again is not
+    try:
+        writer.write(records[0])
+    except ValueError as e:
+        print(e)
+    else:
+        raise AssertionError("write after close succeeded")
+    writer.close()
+
+    # the parsed BamHeader round-trips too
+    with lb.BamWriter(str(Path(tmp) / "header.bam"), f.header.to_bytes()) as writer:
+        writer.write(records[0])
+
+# mate fields and TLEN of a proper pair survive the round trip
+with tempfile.TemporaryDirectory() as tmp:
+    paired_path = Path(tmp) / "paired.bam"
+    common = dict(rid=0, cigar=[("M", 50)], next_rid=0, seq="A" * 50, qual=[30] * 50)
+    write_bam(
+        paired_path,
+        [("chr1", 1000)],
+        [
+            encode_record("p", flag=0x1 | 0x2 | 0x20 | 0x40, pos=99, next_pos=299, tlen=250, **common),
+            encode_record("p", flag=0x1 | 0x2 | 0x10 | 0x80, pos=299, next_pos=99, tlen=-250, **common),
+        ],
+    )
+    reader = lb.BamReader(str(paired_path))
+    pair = [r for chunk in reader for r in chunk]
+    out_path = Path(tmp) / "paired_out.bam"
+    with lb.BamWriter(str(out_path), reader._header) as writer:
+        for record in pair:
+            writer.write(record)
+    written = [r for chunk in lb.BamReader(str(out_path)) for r in chunk]
+    assert [r.mate_pos for r in written] == [300, 100]
+    assert [r.mate_rname for r in written] == ["chr1", "chr1"]
+    assert [r.template_length for r in written] == [250, -250]
@@ -0,0 +1,63 @@
+import tempfile
+from pathlib import Path
+
+import lazybam as lb
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+f = lb.BamReader(str(path_to_bam), chunk_size=1000)
+records = [r for chunk in f for r in chunk]
+assert len(records) == 10
+
+
+def record_offsets(path):
+    """Virtual offset of every record, in file order."""
+    reader = lb.BamReader(str(path), chunk_size=1)
+    offsets = []
+    while True:
+        vpos = reader.virtual_position
+        if next(reader, None) is None:
+            return offsets
+        offsets.append(vpos)
+
+
+with tempfile.TemporaryDirectory() as tmp:
+    for n in (1, 3, 4):
+        out_path = Path(tmp) / f"blocks_{n}.bam"
+        lb.write_chunk_py(f._header, records, str(out_path), sort=False, block_records=n)
+        offsets = record_offsets(out_path)
+        assert len(offsets) == len(records)
+
+        # every n-th record opens a new bgzf block (within-block offset 0),
+        # the others follow on inside the same block
+        for i, vpos in enumerate(offsets):
+            assert (vpos & 0xFFFF == 0) == (i % n == 0), (n, i, hex(vpos))
+        blocks = [vpos >> 16 for vpos in offsets]
+        assert len(set(blocks)) == (len(records) + n - 1) // n
+        assert all(blocks[i] == blocks[i - i % n] for i in range(len(blocks)))
+
+    # without block_records the small file is one block after the header
+    out_path = Path(tmp) / "unaligned.bam"
+    lb.write_chunk_py(f._header, records, str(out_path), sort=False)
+    offsets = record_offsets(out_path)
+    assert len({vpos >> 16 for vpos in offsets}) == 1
+
+    # BamWriter flushes the same way, one record at a time
+    out_path = Path(tmp) / "writer_blocks.bam"
+    with lb.BamWriter(str(out_path), f._header, block_records=3) as writer:
+        for record in records:
+            writer.write(record)
+    offsets = record_offsets(out_path)
+    assert [vpos & 0xFFFF == 0 for vpos in offsets] == [i % 3 == 0 for i in range(len(records))]
+
+    # zero cannot split anything
+    for name, call in [
+        ("write_chunk_py", lambda: lb.write_chunk_py(f._header, records, str(out_path), sort=False, block_records=0)),
+        ("BamWriter", lambda: lb.BamWriter(str(out_path), f._header, block_records=0)),
+    ]:
+        try:
+            call()
+        except ValueError as e:
+            print(name, e)
+        else:
+            raise AssertionError(f"{name} accepted block_records=0")
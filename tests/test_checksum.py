@@ -0,0 +1,23 @@
+from pathlib import Path
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+
+f = lb.BamReader(str(path_to_bam), chunk_size=1000)
+original = f.checksum()
+print("checksum:", original)
+
+# Rewrite the same records into a new file (different bgzf block layout).
+records = [r for chunk in lb.BamReader(str(path_to_bam), chunk_size=1000) for r in chunk]
+out_path = path_to_bam.parent / "test_reads_checksum.bam"
+lb.write_chunk_py(
+    header_bytes=f._header,
+    records=records,
+    out_bam=str(out_path),
+    sort=False,
+    block_records=1,
+)
+
+assert lb.BamReader(str(out_path)).checksum() == original
+out_path.unlink()
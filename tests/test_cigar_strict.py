@@ -0,0 +1,68 @@
+import struct
+import tempfile
+from pathlib import Path
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+
+def corrupt_second_op(rec: bytes, qname: str) -> bytes:
+    """Replace the op code of the second CIGAR op with 15 (undefined)."""
+    off = 4 + 32 + len(qname) + 1 + 4
+    (raw,) = struct.unpack_from("<I", rec, off)
+    return rec[:off] + struct.pack("<I", raw | 0xF) + rec[off + 4 :]
+
+
+good = encode_record("good", rid=0, pos=0, cigar=[("S", 2), ("M", 4)], seq="ACGTAC")
+bad = corrupt_second_op(
+    encode_record("bad", rid=0, pos=10, cigar=[("S", 2), ("M", 4)], seq="ACGTAC"), "bad"
+)
+
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "cigar.bam"
+    write_bam(path, [("chr1", 1000)], [good, bad])
+    good_rec, bad_rec = [r for chunk in lb.BamReader(str(path)) for r in chunk]
+
+    assert good_rec.cigar == good_rec.cigar_strict == [(4, 2), (0, 4)]
+
+    # lenient getter drops the undecodable op rather than inventing one
+    assert bad_rec.cigar == [(4, 2)]
+    try:
+        bad_rec.cigar_strict
+    except ValueError as e:
+        assert "index 1" in str(e)
+    else:
+        raise AssertionError("malformed CIGAR op was not reported")
+
+# column 0 is the op kind, column 1 the length
+ops = [("S", 3), ("M", 5), ("I", 2), ("M", 4), ("D", 1), ("M", 6)]
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "order.bam"
+    write_bam(
+        path,
+        [("chr1", 1000)],
+        [encode_record("order", rid=0, pos=0, cigar=ops, seq="A" * 20)],
+    )
+    rec = next(lb.BamReader(str(path)))[0]
+    expected = [("MIDNSHP=X".index(op), n) for op, n in ops]
+    assert rec.cigar == rec.cigar_strict == expected
+    assert [kind for kind, _ in rec.cigar] == [4, 0, 1, 0, 2, 0]
+
+# RecordOverride takes the same codes the getters return, for all nine ops
+ops = [(5, 1), (4, 2), (0, 3), (1, 1), (2, 2), (3, 10), (6, 1), (7, 4), (8, 1), (4, 1)]
+query_len = sum(n for kind, n in ops if kind in (0, 1, 4, 7, 8))
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "source.bam"
+    write_bam(
+        path,
+        [("chr1", 1000)],
+        [encode_record("rt", rid=0, pos=0, cigar=[("M", query_len)], seq="A" * query_len)],
+    )
+    f = lb.BamReader(str(path))
+    rec = next(f)[0]
+    rec.set_record_override(lb.RecordOverride(cigar=ops))
+    out = Path(tmp) / "roundtrip.bam"
+    lb.write_chunk_py(f._header, [rec], str(out), sort=False)
+    rec = next(lb.BamReader(str(out)))[0]
+    assert rec.cigar == rec.cigar_strict == ops
+    assert rec.reference_length == 3 + 2 + 10 + 4 + 1
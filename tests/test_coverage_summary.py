@@ -0,0 +1,63 @@
+import tempfile
+from pathlib import Path
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+
+def read(name, rid, pos, length):
+    return encode_record(
+        name, rid=rid, pos=pos, cigar=[("M", length)], seq="A" * length, qual=[30] * length
+    )
+
+
+# chr1: [0, 50) and [20, 70) once, [80, 90) ten times; chr2 untouched
+records = [read("a", 0, 0, 50), read("b", 0, 20, 50)]
+records += [read(f"deep{i}", 0, 80, 10) for i in range(10)]
+
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "contigs.bam"
+    write_bam(path, [("chr1", 100), ("chr2", 50)], records)
+    summary = lb.BamReader(str(path)).coverage_summary()
+
+# depth 1 over 0-20 and 50-70, 2 over 20-50, 10 over 80-90
+assert summary["chr1"] == {
+    "length": 100,
+    "mean": (20 + 30 * 2 + 20 + 10 * 10) / 100,
+    "breadth": 80,
+    "fraction_1x": 0.8,
+    "fraction_10x": 0.1,
+}
+assert summary["chr2"] == {
+    "length": 50,
+    "mean": 0.0,
+    "breadth": 0,
+    "fraction_1x": 0.0,
+    "fraction_10x": 0.0,
+}
+
+# an alignment running past the contig end is flagged
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "overhang.bam"
+    write_bam(path, [("chr1", 100)], [read("inside", 0, 10, 20), read("past", 0, 90, 20)])
+    inside, past = next(lb.BamReader(str(path)))
+assert inside.extends_past_contig is False
+assert past.extends_past_contig is True
+
+# quality yield over a known distribution; secondary reads are skipped
+quals = [[10, 20, 30, 40], [35, 35, 5, 5], [40, 40, 40, 40]]
+records = [
+    encode_record(f"q{i}", rid=0, pos=i * 10, cigar=[("M", 4)], seq="ACGT", qual=q)
+    for i, q in enumerate(quals)
+]
+records.append(
+    encode_record("sec", flag=0x100, rid=0, pos=50, cigar=[("M", 4)], seq="ACGT", qual=[40] * 4)
+)
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "quals.bam"
+    write_bam(path, [("chr1", 100)], records)
+    reader = lb.BamReader(str(path))
+    q30 = reader.quality_yield()
+    q20 = reader.quality_yield(20)
+assert q30 == {"total_reads": 3, "total_bases": 12, "bases_above_threshold": 8, "fraction": 8 / 12}
+assert q20["bases_above_threshold"] == 9
@@ -0,0 +1,41 @@
+"""CramReader round trip; needs samtools on PATH to produce the CRAM."""
+
+import random
+import shutil
+import subprocess
+import tempfile
+from pathlib import Path
+
+import pytest
+
+import lazybam as lb
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+if shutil.which("samtools") is None:
+    pytest.skip("samtools not found", allow_module_level=True)
+
+bam = lb.BamReader(str(path_to_bam), chunk_size=1000)
+expected = [(r.qname, r.pos, r.cigar, r.seq, r.qual) for chunk in bam for r in chunk]
+
+with tempfile.TemporaryDirectory() as tmp:
+    # any bases will do: CRAM stores the reads' differences from the reference
+    rng = random.Random(0)
+    fasta_path = Path(tmp) / "ref.fa"
+    with open(fasta_path, "w") as fh:
+        for name, length in bam.references:
+            fh.write(f">{name}\n{''.join(rng.choice('ACGT') for _ in range(length))}\n")
+    subprocess.run(["samtools", "faidx", str(fasta_path)], check=True)
+
+    cram_path = Path(tmp) / "reads.cram"
+    subprocess.run(
+        ["samtools", "view", "-C", "-T", str(fasta_path), "-o", str(cram_path), str(path_to_bam)],
+        check=True,
+    )
+
+    reader = lb.CramReader(str(cram_path), str(fasta_path), chunk_size=3)
+    assert reader.header.refs == bam.references
+    chunks = list(reader)
+    assert [len(c) for c in chunks[:-1]] == [3] * (len(chunks) - 1)
+    got = [(r.qname, r.pos, r.cigar, r.seq, r.qual) for chunk in chunks for r in chunk]
+    assert got == expected
@@ -0,0 +1,45 @@
+"""Region queries through a CSI index, written by `bam_builder`."""
+
+import os
+import tempfile
+from pathlib import Path
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam_with_csi
+
+records = [
+    encode_record("a", rid=0, pos=100, cigar=[("M", 50)], seq="A" * 50),
+    encode_record("b", rid=0, pos=120, cigar=[("M", 50)], seq="C" * 50),
+    encode_record("c", rid=0, pos=500, cigar=[("M", 30)], seq="G" * 30),
+    encode_record("d", rid=1, pos=10, cigar=[("M", 20)], seq="T" * 20),
+]
+
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "indexed.bam"
+    write_bam_with_csi(path, [("chr1", 1000), ("chr2", 1000), ("chr3", 1000)], records)
+
+    def fetched(contig, start, end):
+        reader = lb.BamReader(str(path))
+        return [r.qname for r in reader.fetch(contig, start, end)]
+
+    # CSI only
+    assert fetched("chr1", 0, 1000) == ["a", "b", "c"]
+    assert fetched("chr1", 140, 160) == ["a", "b"]
+    assert fetched("chr1", 200, 400) == []
+    assert fetched("chr2", 0, 1000) == ["d"]
+    assert fetched("chr3", 0, 1000) == []
+
+    # a .csi is preferred over a .bai next to it; this .bai is unreadable
+    bai_path = Path(str(path) + ".bai")
+    bai_path.write_bytes(b"not an index")
+    assert fetched("chr1", 0, 1000) == ["a", "b", "c"]
+    bai_path.unlink()
+
+    # neither index present
+    os.remove(str(path) + ".csi")
+    try:
+        fetched("chr1", 0, 1000)
+    except FileNotFoundError as e:
+        assert ".csi" in str(e) and ".bai" in str(e)
+    else:
+        raise AssertionError("missing index was not reported")
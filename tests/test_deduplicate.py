@@ -0,0 +1,37 @@
+import tempfile
+from pathlib import Path
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+
+f = lb.BamReader(str(path_to_bam), chunk_size=1000)
+first, second = next(f)[:2]
+
+with tempfile.TemporaryDirectory() as tmp:
+    # the same read twice with one UMI, plus a different read with another
+    first.set_record_override(lb.RecordOverride(tags=[("RX", "ACGTAC")]))
+    second.set_record_override(lb.RecordOverride(tags=[("RX", "TTTTTT")]))
+    in_path = Path(tmp) / "umi.bam"
+    lb.write_chunk_py(f._header, [first, first, second], str(in_path), sort=False)
+
+    out_path = Path(tmp) / "dedup.bam"
+    assert lb.deduplicate(str(in_path), str(out_path)) == (2, 1)
+    kept = [r for chunk in lb.BamReader(str(out_path), chunk_size=10) for r in chunk]
+    assert [r.qname for r in kept] == [first.qname, second.qname]
+
+    marked_path = Path(tmp) / "marked.bam"
+    assert lb.deduplicate(str(in_path), str(marked_path), remove=False) == (3, 1)
+    marked = [r for chunk in lb.BamReader(str(marked_path), chunk_size=10) for r in chunk]
+    assert [bool(r.flag & 0x400) for r in marked] == [False, True, False]
+
+    # duplicate_stats counts the 0x400 reads written by deduplicate
+    stats = lb.BamReader(str(marked_path), chunk_size=10).duplicate_stats()
+    assert stats["total_reads"] == 3
+    assert stats["duplicates"] == sum(bool(r.flag & 0x400) for r in marked)
+    assert stats["optical_duplicates"] == 0
+    assert abs(stats["duplication_rate"] - 1 / 3) < 1e-9
+    assert stats["estimated_library_size"] is not None
+    assert lb.BamReader(str(out_path), chunk_size=10).duplicate_stats()[
+        "estimated_library_size"
+    ] is None
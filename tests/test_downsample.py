@@ -0,0 +1,39 @@
+import collections
+import tempfile
+from pathlib import Path
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+records = []
+for i in range(100):
+    for flag in (0x1 | 0x40, 0x1 | 0x80):
+        records.append(
+            encode_record(f"pair{i}", flag=flag, rid=0, pos=i * 10, cigar=[("M", 4)], seq="ACGT", qual=[30] * 4)
+        )
+
+with tempfile.TemporaryDirectory() as tmp:
+    in_path = Path(tmp) / "pairs.bam"
+    write_bam(in_path, [("chr1", 10_000)], records)
+
+    def sample(name, **kwargs):
+        out = Path(tmp) / name
+        written = lb.downsample_bam(str(in_path), str(out), 0.3, **kwargs)
+        names = [r.qname for chunk in lb.BamReader(str(out), chunk_size=1000) for r in chunk]
+        assert len(names) == written
+        return names
+
+    kept = sample("a.bam", seed=7)
+    # both mates of every selected pair are present
+    assert set(collections.Counter(kept).values()) == {2}
+    assert 0 < len(kept) < len(records)
+    # the same seed gives the same subset, another seed a different one
+    assert sample("b.bam", seed=7) == kept
+    assert sample("c.bam", seed=8) != kept
+
+    try:
+        lb.downsample_bam(str(in_path), str(Path(tmp) / "bad.bam"), 1.5)
+    except ValueError as e:
+        print(e)
+    else:
+        raise AssertionError("fraction > 1 was accepted")
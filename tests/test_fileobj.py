@@ -0,0 +1,73 @@
+import io
+from pathlib import Path
+
+import lazybam as lb
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+data = path_to_bam.read_bytes()
+
+expected = [
+    (r.qname, r.pos, r.seq)
+    for chunk in lb.BamReader(str(path_to_bam), chunk_size=1000)
+    for r in chunk
+]
+
+
+class TrickleStream:
+    """Forward-only stream returning at most 7 bytes per read()."""
+
+    def __init__(self, data):
+        self.data = data
+        self.offset = 0
+
+    def read(self, n=-1):
+        out = self.data[self.offset : self.offset + min(n, 7)]
+        self.offset += len(out)
+        return out
+
+
+def records(reader):
+    return [(r.qname, r.pos, r.seq) for chunk in reader for r in chunk]
+
+
+# seekable object, e.g. a downloaded buffer or an S3 file object
+reader = lb.BamReader.from_fileobj(io.BytesIO(data), chunk_size=3)
+assert reader.references == lb.BamReader(str(path_to_bam)).references
+assert records(reader) == expected
+
+# full scans re-read the same object from the start
+reader = lb.BamReader.from_fileobj(io.BytesIO(data))
+counts, unmapped = reader.reference_counts()
+expected_counts, expected_unmapped = lb.BamReader(str(path_to_bam)).reference_counts()
+assert list(counts) == list(expected_counts) and unmapped == expected_unmapped
+
+# short reads from a non-seekable stream
+assert records(lb.BamReader.from_fileobj(TrickleStream(data), chunk_size=4)) == expected
+
+# scans and seeks would re-read a non-seekable stream from wherever it
+# stopped, so they are refused up front
+reader = lb.BamReader.from_fileobj(TrickleStream(data))
+for name, call in [
+    ("reference_counts", lambda: reader.reference_counts()),
+    ("seek", lambda: reader.seek(reader.virtual_position)),
+]:
+    try:
+        call()
+    except ValueError as e:
+        assert "seekable" in str(e), e
+    else:
+        raise AssertionError(f"{name} accepted a non-seekable stream")
+# plain iteration still works afterwards
+assert records(reader) == expected
+
+# reader options are forwarded as in the constructor
+assert records(lb.BamReader.from_fileobj(io.BytesIO(data), min_mapq=1)) == []
+assert records(lb.BamReader.from_fileobj(io.BytesIO(data), chunk_size=2, threads=1)) == expected
+
+# read() needs the GIL, so decompression threads are refused
+try:
+    lb.BamReader.from_fileobj(io.BytesIO(data), threads=2)
+except ValueError as e:
+    assert "threads" in str(e), e
+else:
+    raise AssertionError("from_fileobj accepted threads=2")
@@ -0,0 +1,48 @@
+import tempfile
+from pathlib import Path
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+# (flag, mapq) combinations: primary / secondary / supplementary / duplicate
+# reads on both strands at a spread of mapping qualities
+specs = [
+    (flag, mapq)
+    for flag in (0x0, 0x10, 0x100, 0x110, 0x400, 0x800, 0x810)
+    for mapq in (0, 20, 60)
+]
+records = [
+    encode_record(f"r{i}", flag=flag, rid=0, pos=i, mapq=mapq, cigar=[("M", 4)], seq="ACGT")
+    for i, (flag, mapq) in enumerate(specs)
+]
+
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "flags.bam"
+    write_bam(path, [("chr1", 1000)], records)
+
+    def kept(**kwargs):
+        f = lb.BamReader(str(path), chunk_size=4, **kwargs)
+        return [(r.flag, r.mapq) for chunk in f for r in chunk]
+
+    assert kept() == specs
+
+    # secondary / supplementary / duplicate reads dropped
+    assert kept(exclude_flags=0x100 | 0x400 | 0x800) == [
+        s for s in specs if s[0] in (0x0, 0x10)
+    ]
+    # only reverse-strand reads
+    assert kept(require_flags=0x10) == [s for s in specs if s[0] & 0x10]
+    assert kept(min_mapq=20) == [s for s in specs if s[1] >= 20]
+
+    # criteria combine
+    assert kept(min_mapq=60, exclude_flags=0x900, require_flags=0x10) == [(0x10, 60)]
+
+    # count() applies the same criteria without building records
+    reader = lb.BamReader(str(path))
+    assert reader.count() == len(specs)
+    assert reader.count(exclude_flags=0x100 | 0x400 | 0x800) == len(kept(exclude_flags=0xD00))
+    assert reader.count(require_flags=0x10) == len(kept(require_flags=0x10))
+    assert reader.count(min_mapq=20, exclude_flags=0x900) == len(kept(min_mapq=20, exclude_flags=0x900))
+    # independent of the reader's position
+    next(reader)
+    assert reader.count() == len(specs)
@@ -0,0 +1,52 @@
+import tempfile
+from pathlib import Path
+
+import numpy as np
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+
+def pair(name, start, mate_start):
+    """Properly paired FR reads of 50M; read1 leftmost."""
+    tlen = mate_start + 50 - start
+    common = dict(rid=0, cigar=[("M", 50)], next_rid=0, seq="A" * 50, qual=[30] * 50)
+    return [
+        encode_record(name, flag=0x1 | 0x2 | 0x20 | 0x40, pos=start, next_pos=mate_start, tlen=tlen, **common),
+        encode_record(name, flag=0x1 | 0x2 | 0x10 | 0x80, pos=mate_start, next_pos=start, tlen=-tlen, **common),
+    ]
+
+
+with tempfile.TemporaryDirectory() as tmp:
+    raw_path = Path(tmp) / "raw.bam"
+    write_bam(
+        raw_path,
+        [("chr1", 10_000)],
+        # "outer" spans [100, 400) with both mates outside [200, 300);
+        # "inner" spans [210, 450) with read1 inside the region
+        pair("outer", 100, 350) + pair("inner", 210, 400),
+    )
+    raw = lb.BamReader(str(raw_path))
+    records = [r for chunk in raw for r in chunk]
+
+    chunk_path = Path(tmp) / "chunk.bam"
+    indexed_path = Path(tmp) / "indexed.bam"
+    lb.write_chunk_py(raw._header, records, str(chunk_path), sort=True)
+    lb.merge_chunks_py(raw._header, [str(chunk_path)], str(indexed_path), sort=True)
+    reader = lb.BamReader(str(indexed_path))
+
+    expected = np.array([1] * 10 + [2] * 90, dtype=np.int32)
+    assert np.array_equal(reader.fragment_coverage("chr1", 200, 300), expected)
+
+    # the search window bounds how far away the mates may lie
+    assert np.array_equal(
+        reader.fragment_coverage("chr1", 200, 300, max_insert_size=50),
+        np.array([0] * 10 + [1] * 90, dtype=np.int32),
+    )
+
+    try:
+        reader.fragment_coverage("chr1", 200, 300, max_insert_size=-1)
+    except ValueError:
+        pass
+    else:
+        raise AssertionError("accepted a negative max_insert_size")
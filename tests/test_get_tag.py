@@ -0,0 +1,32 @@
+from pathlib import Path
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+
+for chunk in lb.BamReader(str(path_to_bam), chunk_size=1000):
+    for record in chunk:
+        tags = dict(record.tags)
+        assert tags
+        for name, value in tags.items():
+            got = record.get_tag(name)
+            if hasattr(value, "tolist"):
+                assert got.tolist() == value.tolist()
+            else:
+                assert got == value
+        assert record.get_tag("ZZ") is None
+
+try:
+    record.get_tag("NMX")
+except ValueError:
+    pass
+else:
+    raise AssertionError("accepted a three-letter tag")
+
+# get_field_by_tag keeps raising KeyError for absent tags
+try:
+    record.get_field_by_tag("ZZ")
+except KeyError:
+    pass
+else:
+    raise AssertionError("missing tag did not raise KeyError")
@@ -0,0 +1,29 @@
+from pathlib import Path
+
+import lazybam as lb
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+reader = lb.BamReader(str(path_to_bam), chunk_size=1000)
+
+refs = reader.references
+# same order and values as the parsed header text
+assert refs == reader.header.refs
+assert refs[0] == ("Ala1B", 99)
+assert refs[-1] == ("Val2B", 100)
+
+# indices line up with reference_sequence_id
+for chunk in reader:
+    for rec in chunk:
+        if rec.rid >= 0:
+            assert refs[rec.rid][0] == rec.rname
+
+rgs = reader.read_groups
+assert rgs == [rg["ID"] for rg in reader.header.header.get("@RG", [])]
+assert len(rgs) == 1 and rgs[0].startswith("9d794822")
+print(refs[:3], rgs)
+
+# header text is serialised once and the same bytes object is reused
+raw = reader._header
+assert raw is reader._header
+assert raw.startswith(b"@") and b"@SQ\tSN:Ala1B\tLN:99" in raw
@@ -0,0 +1,113 @@
+import tempfile
+import threading
+from functools import partial
+from http.server import SimpleHTTPRequestHandler, ThreadingHTTPServer
+from pathlib import Path
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam_with_csi
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+
+class RangeHandler(SimpleHTTPRequestHandler):
+    """Static file handler answering `Range: bytes=a-b` with 206.
+
+    With `truncate` set, ranged responses only carry half of the requested
+    bytes (with a matching Content-Length), like a misbehaving server.
+    """
+
+    truncate = False
+
+    def log_message(self, *args):
+        pass
+
+    def do_GET(self):
+        range_header = self.headers.get("Range")
+        if range_header is None:
+            return super().do_GET()
+        path = Path(self.translate_path(self.path))
+        data = path.read_bytes()
+        start, end = range_header.removeprefix("bytes=").split("-")
+        body = data[int(start) : int(end) + 1]
+        if self.truncate:
+            body = body[: len(body) // 2]
+        self.send_response(206)
+        self.send_header("Content-Length", str(len(body)))
+        self.send_header(
+            "Content-Range", f"bytes {start}-{int(start) + len(body) - 1}/{len(data)}"
+        )
+        self.end_headers()
+        self.wfile.write(body)
+
+
+def records(reader):
+    return [(r.qname, r.pos, r.flag, r.seq) for chunk in reader for r in chunk]
+
+
+with tempfile.TemporaryDirectory() as tmp:
+    # fetch needs a .bai next to the BAM; merge_chunks_py writes one
+    raw = lb.BamReader(str(path_to_bam))
+    raw_records = [r for chunk in raw for r in chunk]
+    chunk_path = Path(tmp) / "chunk.bam"
+    indexed_path = Path(tmp) / "indexed.bam"
+    lb.write_chunk_py(raw._header, raw_records, str(chunk_path), sort=True)
+    lb.merge_chunks_py(raw._header, [str(chunk_path)], str(indexed_path), sort=True)
+
+    server = ThreadingHTTPServer(
+        ("127.0.0.1", 0), partial(RangeHandler, directory=tmp)
+    )
+    threading.Thread(target=server.serve_forever, daemon=True).start()
+    url = f"http://127.0.0.1:{server.server_address[1]}/indexed.bam"
+
+    try:
+        local = lb.BamReader(str(indexed_path), chunk_size=100)
+
+        # sequential read matches the local file
+        remote = lb.BamReader.from_url(url, chunk_size=100)
+        assert remote.references == local.references
+        assert records(remote) == records(local)
+
+        # indexed fetch only downloads the chunks listed in the .bai; there
+        # is no .csi, so the 404 for it falls back to the .bai
+        contig, length = local.references[0]
+        expected = [(r.qname, r.pos) for r in local.fetch(contig, 0, length)]
+        assert expected
+        remote = lb.BamReader.from_url(url)
+        assert [(r.qname, r.pos) for r in remote.fetch(contig, 0, length)] == expected
+
+        # reader options are forwarded as in the constructor
+        remote = lb.BamReader.from_url(url, chunk_size=100, min_mapq=30, threads=2)
+        assert records(remote) == records(
+            lb.BamReader(str(indexed_path), chunk_size=100, min_mapq=30)
+        )
+        try:
+            lb.BamReader.from_url(url, tag_conversion="bogus")
+        except ValueError:
+            pass
+        else:
+            raise AssertionError("expected ValueError for an unknown tag_conversion")
+
+        # without index_url a .csi is preferred over a .bai next to the BAM
+        write_bam_with_csi(
+            Path(tmp) / "csi.bam",
+            [("chr1", 1000)],
+            [encode_record("a", rid=0, pos=100, cigar=[("M", 50)], seq="A" * 50)],
+        )
+        (Path(tmp) / "csi.bam.bai").write_bytes(b"not an index")
+        csi_url = url.replace("indexed.bam", "csi.bam")
+        remote = lb.BamReader.from_url(csi_url)
+        assert [r.qname for r in remote.fetch("chr1", 0, 1000)] == ["a"]
+
+        # a short range response is an error, not a silent EOF
+        RangeHandler.truncate = True
+        try:
+            records(lb.BamReader.from_url(url, chunk_size=100))
+        except OSError:
+            pass
+        else:
+            raise AssertionError("expected OSError for a truncated range response")
+    finally:
+        RangeHandler.truncate = False
+        server.shutdown()
+        server.server_close()
@@ -0,0 +1,40 @@
+import tempfile
+from pathlib import Path
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+refs = [("chr1", 1000), ("chr2", 500), ("chr3", 200)]
+records = [
+    encode_record("m1", flag=0x0, rid=0, pos=10, cigar=[("M", 4)], seq="ACGT"),
+    encode_record("m2", flag=0x10, rid=0, pos=20, cigar=[("M", 4)], seq="ACGT"),
+    # unmapped mate placed next to m3
+    encode_record("m3", flag=0x1 | 0x8, rid=0, pos=30, cigar=[("M", 4)], next_rid=0, next_pos=30, seq="ACGT"),
+    encode_record("m3", flag=0x1 | 0x4, rid=0, pos=30, next_rid=0, next_pos=30, seq="ACGT"),
+    encode_record("m4", flag=0x0, rid=1, pos=5, cigar=[("M", 4)], seq="ACGT"),
+    # unplaced unmapped
+    encode_record("u1", flag=0x4, seq="ACGT"),
+    encode_record("u2", flag=0x4, seq="ACGT"),
+]
+expected = [
+    ("chr1", 1000, 2 + 1, 1),
+    ("chr2", 500, 1, 0),
+    ("chr3", 200, 0, 0),
+    ("*", 0, 0, 2),
+]
+
+with tempfile.TemporaryDirectory() as tmp:
+    # no index: full scan
+    path = Path(tmp) / "plain.bam"
+    write_bam(path, refs, records)
+    reader = lb.BamReader(str(path))
+    assert reader.idxstats() == expected
+
+    # merge_chunks_py writes a .bai whose metadata carries the counts
+    recs = [r for chunk in lb.BamReader(str(path)) for r in chunk]
+    chunk_path = Path(tmp) / "chunk.bam"
+    indexed_path = Path(tmp) / "indexed.bam"
+    lb.write_chunk_py(reader._header, recs, str(chunk_path), sort=True)
+    lb.merge_chunks_py(reader._header, [str(chunk_path)], str(indexed_path), sort=True)
+    assert Path(str(indexed_path) + ".bai").exists()
+    assert lb.BamReader(str(indexed_path)).idxstats() == expected
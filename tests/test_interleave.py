@@ -0,0 +1,35 @@
+import tempfile
+from pathlib import Path
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+
+def lane(prefix, n):
+    return [
+        encode_record(f"{prefix}{i}", rid=0, pos=i * 10, cigar=[("M", 4)], seq="ACGT", qual=[30] * 4)
+        for i in range(n)
+    ]
+
+
+with tempfile.TemporaryDirectory() as tmp:
+    a, b, c = (Path(tmp) / f"{name}.bam" for name in "abc")
+    write_bam(a, [("chr1", 1000)], lane("a", 3))
+    write_bam(b, [("chr1", 1000)], lane("b", 3))
+    write_bam(c, [("chr1", 1000)], lane("c", 1))
+
+    names = [r.qname for r in lb.BamInterleaver([str(a), str(b)])]
+    assert names == ["a0", "b0", "a1", "b1", "a2", "b2"]
+
+    # a shorter file drops out of the rotation
+    names = [r.qname for r in lb.BamInterleaver([str(c), str(a)])]
+    assert names == ["c0", "a0", "a1", "a2"]
+
+    other = Path(tmp) / "other.bam"
+    write_bam(other, [("chr2", 1000)], lane("o", 1))
+    try:
+        lb.BamInterleaver([str(a), str(other)])
+    except ValueError as e:
+        print(e)
+    else:
+        raise AssertionError("incompatible headers were accepted")
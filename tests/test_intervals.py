@@ -0,0 +1,24 @@
+from pathlib import Path
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+
+record = next(lb.BamReader(str(path_to_bam), chunk_size=1))[0]
+five_prime = record.pos - 1  # forward-strand read
+
+features = lb.IntervalSet(
+    [
+        ("Ala1B", five_prime + 100, five_prime + 200, "downstream_gene"),
+        ("Ala1B", five_prime + 1000, five_prime + 1100, "far_gene"),
+        ("other", 0, 10, "other_contig"),
+    ]
+)
+assert len(features) == 3
+assert features.nearest_feature(record) == ("downstream_gene", -100)
+
+# on the minus strand the same read lies past the feature's 5' end
+minus = lb.IntervalSet([("Ala1B", five_prime + 100, five_prime + 200, "rev_gene", "-")])
+assert minus.nearest_feature(record) == ("rev_gene", 100)
+
+assert lb.IntervalSet([("other", 0, 10, "x")]).nearest_feature(record) is None
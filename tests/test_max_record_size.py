@@ -0,0 +1,86 @@
+import gzip
+import struct
+import tempfile
+import zlib
+from pathlib import Path
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+
+
+def bgzf_block(data: bytes) -> bytes:
+    compressor = zlib.compressobj(6, zlib.DEFLATED, -15)
+    cdata = compressor.compress(data) + compressor.flush()
+    header = b"\x1f\x8b\x08\x04\x00\x00\x00\x00\x00\xff\x06\x00BC\x02\x00"
+    bsize = len(header) + 2 + len(cdata) + 8 - 1
+    trailer = struct.pack("<II", zlib.crc32(data), len(data))
+    return header + struct.pack("<H", bsize) + cdata + trailer
+
+
+# header of the test BAM followed by a record claiming a ~2 GiB block
+raw = gzip.decompress(path_to_bam.read_bytes())
+offset = 8 + struct.unpack_from("<i", raw, 4)[0]
+n_ref = struct.unpack_from("<i", raw, offset)[0]
+offset += 4
+for _ in range(n_ref):
+    l_name = struct.unpack_from("<i", raw, offset)[0]
+    offset += 4 + l_name + 4
+hostile = raw[:offset] + struct.pack("<I", 0x7FFFFFF0) + b"\x00" * 32
+
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "hostile.bam"
+    path.write_bytes(bgzf_block(hostile) + bgzf_block(b""))
+
+    f = lb.BamReader(str(path), chunk_size=10)
+    try:
+        next(f)
+    except ValueError as e:
+        print(e)
+    else:
+        raise AssertionError("oversized record was not rejected")
+
+    # file-to-file helpers apply the same limit
+    out = str(Path(tmp) / "out.bam")
+    for name, call in [
+        ("downsample_bam", lambda: lb.downsample_bam(str(path), out, 0.5)),
+        ("deduplicate", lambda: lb.deduplicate(str(path), out)),
+        ("tag_all", lambda: lb.tag_all(str(path), out, "XT", 1)),
+        ("transform", lambda: lb.transform(str(path), out, lambda r: r)),
+    ]:
+        try:
+            call()
+        except ValueError as e:
+            print(name, e)
+        else:
+            raise AssertionError(f"{name} accepted an oversized record")
+
+    # so do indexed queries
+    records = [r for chunk in lb.BamReader(str(path_to_bam)) for r in chunk]
+    header = lb.BamReader(str(path_to_bam))._header
+    chunk_path = Path(tmp) / "chunk.bam"
+    indexed_path = Path(tmp) / "indexed.bam"
+    lb.write_chunk_py(header, records, str(chunk_path), sort=True)
+    lb.merge_chunks_py(header, [str(chunk_path)], str(indexed_path), sort=True)
+    contig, length = lb.BamReader(str(indexed_path)).references[0]
+    assert len(list(lb.BamReader(str(indexed_path)).fetch(contig, 0, length))) == len(records)
+    try:
+        list(lb.BamReader(str(indexed_path), max_record_size=16).fetch(contig, 0, length))
+    except ValueError as e:
+        print(e)
+    else:
+        raise AssertionError("fetch accepted an oversized record")
+
+    # the constructor's region= goes through the same query
+    region = f"{contig}:1-{length}"
+    assert len(next(lb.BamReader(str(indexed_path), chunk_size=1000, region=region))) == len(records)
+    try:
+        lb.BamReader(str(indexed_path), region=region, max_record_size=16)
+    except ValueError as e:
+        print(e)
+    else:
+        raise AssertionError("region= accepted an oversized record")
+
+# regular records pass a generous limit
+records = next(lb.BamReader(str(path_to_bam), chunk_size=10, max_record_size=1 << 20))
+assert len(records) == 10
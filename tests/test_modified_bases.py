@@ -0,0 +1,36 @@
+import tempfile
+from pathlib import Path
+
+import numpy as np
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+
+f = lb.BamReader(str(path_to_bam), chunk_size=1)
+record = next(f)[0]
+assert record.modified_bases == {}
+
+cs = [i for i, b in enumerate(record.seq) if b == "C"]
+a0 = record.seq.index("A")
+# two codes sharing each C call's ML entries, then a skip-free A call
+record.set_record_override(
+    lb.RecordOverride(
+        tags=[
+            ("MM", "C+mh?,1,0;A+a.,0;"),
+            ("ML", np.array([200, 10, 150, 20, 99], dtype=np.uint8)),
+        ]
+    )
+)
+with tempfile.TemporaryDirectory() as tmp:
+    out = Path(tmp) / "mod.bam"
+    lb.write_chunk_py(f._header, [record], str(out), sort=False)
+    record = next(lb.BamReader(str(out)))[0]
+
+mods = record.modified_bases
+assert set(mods) == {("C", 0, "m"), ("C", 0, "h"), ("A", 0, "a")}
+assert mods[("C", 0, "m")].tolist() == [[cs[1], 200], [cs[2], 150]]
+assert mods[("C", 0, "h")].tolist() == [[cs[1], 10], [cs[2], 20]]
+# implicitly unmodified A bases ('.' mode) are not listed
+assert mods[("A", 0, "a")].tolist() == [[a0, 99]]
+assert mods[("C", 0, "m")].dtype.name == "int64"
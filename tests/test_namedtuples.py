@@ -0,0 +1,37 @@
+from pathlib import Path
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+
+rows = lb.BamReader(str(path_to_bam)).next_namedtuples(5, ["qname", "pos", "mapq"])
+records = next(lb.BamReader(str(path_to_bam), chunk_size=5))
+
+assert len(rows) == 5
+for row, record in zip(rows, records):
+    print(row)
+    assert row.qname == record.qname
+    assert row.pos == record.pos
+    assert row.mapq == record.mapq
+
+# tag columns: NM on every read, AS on every other one
+import tempfile
+
+f = lb.BamReader(str(path_to_bam), chunk_size=4)
+tagged = next(f)
+for i, record in enumerate(tagged):
+    tags = [("NM", i)]
+    if i % 2 == 0:
+        tags.append(("AS", 100 - i))
+    record.set_record_override(lb.RecordOverride(tags=tags))
+
+with tempfile.TemporaryDirectory() as tmp:
+    tagged_path = Path(tmp) / "tagged.bam"
+    lb.write_chunk_py(f._header, tagged, str(tagged_path), sort=False)
+    columns = lb.BamReader(str(tagged_path)).next_tag_columns(["NM", "AS", "XX"], 10)
+
+assert columns["NM"].dtype.name == "int64"
+assert list(columns["NM"]) == [0, 1, 2, 3]
+assert list(columns["AS"].mask) == [False, True, False, True]
+assert list(columns["AS"].compressed()) == [100, 98]
+assert columns["XX"].mask.all()
@@ -0,0 +1,55 @@
+from pathlib import Path
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+
+records = [r for chunk in lb.BamReader(str(path_to_bam), chunk_size=1000) for r in chunk]
+
+f = lb.BamReader(str(path_to_bam), chunk_size=4)
+batches = []
+while True:
+    batch = f.next_batch()
+    if len(batch["qname"]) == 0:
+        break
+    batches.append(batch)
+
+assert [len(b["qname"]) for b in batches] == [4, 4, 2]
+assert batches[0]["pos"].dtype.name == "int64"
+assert batches[0]["mapq"].dtype.name == "uint8"
+assert batches[0]["flag"].dtype.name == "uint16"
+assert batches[0]["reference_id"].dtype.name == "int32"
+assert batches[0]["mapped_length"].dtype.name == "int32"
+
+columns = {
+    key: [v for b in batches for v in (b[key] if key == "qname" else b[key].tolist())]
+    for key in ("pos", "mapq", "flag", "reference_id", "mapped_length", "qname")
+}
+assert columns["pos"] == [r.pos for r in records]
+assert columns["mapq"] == [r.mapq for r in records]
+assert columns["flag"] == [r.flag for r in records]
+assert columns["reference_id"] == [r.rid for r in records]
+assert columns["mapped_length"] == [r.reference_length for r in records]
+assert columns["qname"] == [r.qname for r in records]
+
+# next_struct: the same fields packed into one structured array per chunk
+import numpy as np
+
+f = lb.BamReader(str(path_to_bam), chunk_size=4)
+structs = []
+while True:
+    arr = f.next_struct()
+    if len(arr) == 0:
+        break
+    structs.append(arr)
+
+assert [len(a) for a in structs] == [4, 4, 2]
+assert structs[0].dtype.names == ("pos", "mapq", "flag", "reference_id", "mapped_length")
+assert structs[0].dtype.itemsize == 8 + 1 + 2 + 4 + 4
+joined = np.concatenate(structs)
+for key in ("pos", "mapq", "flag", "reference_id", "mapped_length"):
+    assert joined[key].tolist() == columns[key], key
+assert joined["pos"].dtype.name == "int64"
+# sortable / maskable like any structured array
+assert np.sort(joined, order="pos")["pos"].tolist() == sorted(columns["pos"])
+assert len(joined[np.where(joined["mapq"] >= 0)]) == len(records)
@@ -0,0 +1,42 @@
+import tempfile
+from pathlib import Path
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+names = [
+    "M00123:45:000000000-ABCDE:1:1101:15589:1331",
+    # trailing UMI field
+    "NB501234:7:HXXXXBGXY:4:11401:2345:6789:ACGTACGT",
+    # SRA-style name, not colon-separated
+    "SRR000001.1",
+    # right field count, non-numeric tile
+    "M00123:45:FC:1:tile:15589:1331",
+    "a:b:c",
+]
+
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "names.bam"
+    write_bam(path, [("chr1", 1000)], [encode_record(n, seq="ACGT") for n in names])
+    recs = {r.qname: r for chunk in lb.BamReader(str(path)) for r in chunk}
+
+assert recs[names[0]].optical_coordinates == {
+    "instrument": "M00123",
+    "run": 45,
+    "flowcell": "000000000-ABCDE",
+    "lane": 1,
+    "tile": 1101,
+    "x": 15589,
+    "y": 1331,
+}
+assert recs[names[1]].optical_coordinates == {
+    "instrument": "NB501234",
+    "run": 7,
+    "flowcell": "HXXXXBGXY",
+    "lane": 4,
+    "tile": 11401,
+    "x": 2345,
+    "y": 6789,
+}
+for name in names[2:]:
+    assert recs[name].optical_coordinates is None, name
@@ -0,0 +1,188 @@
+import tempfile
+from pathlib import Path
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+# (read strand, mate strand, read start, mate start) -> orientation
+cases = [
+    (False, True, 100, 300, "FR"),
+    (True, False, 100, 300, "RF"),
+    (False, False, 100, 300, "FF"),
+    (True, True, 100, 300, "RR"),
+    # seen from the right-hand mate of an FR pair
+    (True, False, 300, 100, "FR"),
+]
+
+records = []
+for i, (reverse, mate_reverse, start, mate_start, _) in enumerate(cases):
+    flag = 0x1 | 0x2 | 0x40 | (0x10 if reverse else 0) | (0x20 if mate_reverse else 0)
+    records.append(
+        encode_record(
+            f"pair{i}",
+            flag=flag,
+            rid=0,
+            pos=start,
+            cigar=[("M", 50)],
+            next_rid=0,
+            next_pos=mate_start,
+            tlen=(mate_start + 50 - start) if start < mate_start else -(start + 50 - mate_start),
+            seq="A" * 50,
+            qual=[30] * 50,
+        )
+    )
+
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "pairs.bam"
+    write_bam(path, [("chr1", 10_000)], records)
+    reads = [r for chunk in lb.BamReader(str(path), chunk_size=100) for r in chunk]
+
+assert [r.pair_orientation for r in reads] == [c[-1] for c in cases]
+
+# insert_size_percentiles counts properly-paired primary read1 only
+sizes = [150, 200, 210, 250, 300, 320, 400]
+records = [
+    encode_record(
+        f"frag{i}",
+        flag=0x1 | 0x2 | 0x40 | 0x20,
+        rid=0,
+        pos=100,
+        cigar=[("M", 50)],
+        next_rid=0,
+        next_pos=100 + size - 50,
+        tlen=size,
+        seq="A" * 50,
+        qual=[30] * 50,
+    )
+    for i, size in enumerate(sizes)
+]
+# read2, not properly paired and oversized fragments are skipped
+for name, flag, tlen in [("r2", 0x1 | 0x2 | 0x80, 9999), ("np", 0x1 | 0x40, 9999), ("big", 0x1 | 0x2 | 0x40, 6000)]:
+    records.append(
+        encode_record(
+            name, flag=flag, rid=0, pos=100, cigar=[("M", 50)], next_rid=0,
+            next_pos=200, tlen=tlen, seq="A" * 50, qual=[30] * 50,
+        )
+    )
+
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "inserts.bam"
+    write_bam(path, [("chr1", 10_000)], records)
+    reader = lb.BamReader(str(path), chunk_size=100)
+    p10, p50, p90 = reader.insert_size_percentiles()
+    assert p50 == sorted(sizes)[len(sizes) // 2]
+    assert p10 == min(sizes) and p90 == max(sizes)
+    assert reader.insert_size_percentiles([0, 100], max_size=300) == [150, 300]
+
+# mate strand / reference from a single record
+records = [
+    encode_record(
+        "proper", flag=0x1 | 0x2 | 0x40 | 0x20, rid=0, pos=100, cigar=[("M", 50)],
+        next_rid=1, next_pos=300, tlen=0, seq="A" * 50, qual=[30] * 50,
+    ),
+    encode_record(
+        "lonely", flag=0x1 | 0x8 | 0x40 | 0x20, rid=0, pos=100, cigar=[("M", 50)],
+        next_rid=0, next_pos=100, tlen=0, seq="A" * 50, qual=[30] * 50,
+    ),
+]
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "mates.bam"
+    write_bam(path, [("chr1", 10_000), ("chr2", 10_000)], records)
+    proper, lonely = [r for chunk in lb.BamReader(str(path), chunk_size=100) for r in chunk]
+
+assert proper.mate_is_reverse is True
+assert proper.mate_reference_name == "chr2"
+assert lonely.mate_is_reverse is False
+assert lonely.mate_reference_name is None
+# mate_rname follows the same rule: RNEXT is set, but the mate is unmapped
+assert proper.mate_rname == "chr2"
+assert lonely.mate_rname is None
+
+# signature: coordinate duplicates collide, a shifted read does not
+def pair_read(name, pos, seq):
+    return encode_record(
+        name, flag=0x1 | 0x40, rid=0, pos=pos, cigar=[("S", 2), ("M", 8)],
+        next_rid=0, next_pos=500, tlen=0, seq=seq, qual=[30] * 10,
+    )
+
+
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "dups.bam"
+    records = [pair_read("d1", 100, "ACGTACGTAC"), pair_read("d2", 100, "TTTTACGTAC"), pair_read("s", 101, "ACGTACGTAC")]
+    write_bam(path, [("chr1", 10_000)], records)
+    d1, d2, shifted = [r for chunk in lb.BamReader(str(path), chunk_size=10) for r in chunk]
+
+assert d1.signature == d2.signature
+assert d1.signature != shifted.signature
+assert 0 <= d1.signature < 2**64
+
+# flag predicates mirror the SAM flag bits
+predicates = [
+    "is_paired", "is_proper_pair", "is_unmapped", "is_mate_unmapped",
+    "is_reverse", "is_mate_reverse", "is_read1", "is_read2",
+    "is_secondary", "is_qcfail", "is_duplicate", "is_supplementary",
+]
+records = [
+    encode_record(f"bit{i}", flag=1 << i, rid=0, pos=10, cigar=[("M", 4)], seq="ACGT", qual=[30] * 4)
+    for i in range(len(predicates))
+]
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "flags.bam"
+    write_bam(path, [("chr1", 10_000)], records)
+    reads = [r for chunk in lb.BamReader(str(path), chunk_size=100) for r in chunk]
+
+for i, read in enumerate(reads):
+    assert [getattr(read, p) for p in predicates] == [j == i for j in range(len(predicates))]
+
+# mate coordinates and the signed template length
+records = [
+    encode_record(
+        "left", flag=0x1 | 0x2 | 0x40 | 0x20, rid=0, pos=100, cigar=[("M", 50)],
+        next_rid=1, next_pos=299, tlen=250, seq="A" * 50, qual=[30] * 50,
+    ),
+    encode_record(
+        "right", flag=0x1 | 0x2 | 0x80 | 0x10, rid=0, pos=299, cigar=[("M", 50)],
+        next_rid=-1, next_pos=-1, tlen=-250, seq="A" * 50, qual=[30] * 50,
+    ),
+]
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "mate_coords.bam"
+    write_bam(path, [("chr1", 10_000), ("chr2", 10_000)], records)
+    left, right = [r for chunk in lb.BamReader(str(path), chunk_size=100) for r in chunk]
+
+assert left.mate_pos == 300 and left.mate_rname == "chr2"
+assert right.mate_pos == -1 and right.mate_rname is None
+assert left.template_length == 250 and right.template_length == -250
+assert left.len == right.len == 250
+
+# fragment midpoint: [100, 300) for the even pair, [100, 301) for the odd one
+records = []
+for name, mate_start in [("even", 250), ("odd", 251)]:
+    tlen = mate_start + 50 - 100
+    records += [
+        encode_record(
+            name, flag=0x1 | 0x2 | 0x40 | 0x20, rid=0, pos=100, cigar=[("M", 50)],
+            next_rid=0, next_pos=mate_start, tlen=tlen, seq="A" * 50, qual=[30] * 50,
+        ),
+        encode_record(
+            name, flag=0x1 | 0x2 | 0x80 | 0x10, rid=0, pos=mate_start, cigar=[("M", 50)],
+            next_rid=0, next_pos=100, tlen=-tlen, seq="A" * 50, qual=[30] * 50,
+        ),
+    ]
+# not properly paired
+records.append(
+    encode_record(
+        "improper", flag=0x1 | 0x40 | 0x20, rid=0, pos=100, cigar=[("M", 50)],
+        next_rid=0, next_pos=250, tlen=200, seq="A" * 50, qual=[30] * 50,
+    )
+)
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "midpoints.bam"
+    write_bam(path, [("chr1", 10_000)], records)
+    reads = [r for chunk in lb.BamReader(str(path), chunk_size=100) for r in chunk]
+
+even1, even2, odd1, odd2, improper = reads
+assert even1.fragment_midpoint == even2.fragment_midpoint == 200
+# rounded down, the same for both mates
+assert odd1.fragment_midpoint == odd2.fragment_midpoint == 200
+assert improper.fragment_midpoint is None
@@ -0,0 +1,71 @@
+import tempfile
+from pathlib import Path
+
+import pyarrow.parquet as pq
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+records = [r for chunk in lb.BamReader(str(path_to_bam), chunk_size=1000) for r in chunk]
+
+with tempfile.TemporaryDirectory() as tmp:
+    out_path = Path(tmp) / "reads.parquet"
+    f = lb.BamReader(str(path_to_bam), chunk_size=4)
+    rows = f.write_parquet(str(out_path), fields=["qname", "pos", "mapq"], tags=["NM"])
+    assert rows == len(records)
+
+    table = pq.read_table(out_path)
+    print(table.schema)
+    assert table.num_rows == len(records)
+    assert table.column_names == ["qname", "pos", "mapq", "NM"]
+    assert str(table.schema.field("pos").type) == "int64"
+    assert str(table.schema.field("mapq").type) == "uint8"
+    assert table.column("qname").to_pylist() == [r.qname for r in records]
+    # chunk_size=4 → row groups of 4, 4 and 2 rows
+    assert pq.ParquetFile(out_path).num_row_groups == 3
+    # the test reads carry no NM tag
+    assert table.column("NM").null_count == len(records)
+
+# same columns as to_arrow_batches, but missing values are the getters' sentinels
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "unplaced.bam"
+    write_bam(
+        path,
+        [("chr1", 1000)],
+        [
+            encode_record("placed", rid=0, pos=9, mapq=30, cigar=[("M", 4)], seq="ACGT"),
+            encode_record("unplaced", flag=0x4, mapq=255),
+        ],
+    )
+    out_path = Path(tmp) / "unplaced.parquet"
+    lb.BamReader(str(path)).write_parquet(
+        str(out_path), fields=["rid", "rname", "pos", "mapq", "cigar_string", "seq"]
+    )
+    table = pq.read_table(out_path)
+    assert not any(field.nullable for field in table.schema)
+    assert table.to_pylist() == [
+        {"rid": 0, "rname": "chr1", "pos": 10, "mapq": 30, "cigar_string": "4M", "seq": "ACGT"},
+        {"rid": -1, "rname": "", "pos": -1, "mapq": 255, "cigar_string": "", "seq": ""},
+    ]
+
+# a tag changing type after the first row group fails without leaving a file
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "mixed.bam"
+    write_bam(
+        path,
+        [("chr1", 1000)],
+        [
+            encode_record("int", tags=[("XS", "i", 1)]),
+            encode_record("float", tags=[("XS", "f", 1.5)]),
+        ],
+    )
+    out_path = Path(tmp) / "mixed.parquet"
+    try:
+        lb.BamReader(str(path), chunk_size=1).write_parquet(str(out_path), tags=["XS"])
+    except ValueError as e:
+        print(e)
+    else:
+        raise AssertionError("a tag changing type was accepted")
+    assert list(Path(tmp).iterdir()) == [path]
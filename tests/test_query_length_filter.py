@@ -0,0 +1,18 @@
+from pathlib import Path
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+
+all_lengths = [
+    len(r.seq) for chunk in lb.BamReader(str(path_to_bam), chunk_size=1000) for r in chunk
+]
+
+f = lb.BamReader(
+    str(path_to_bam), chunk_size=3, min_query_length=180, max_query_length=200
+)
+kept = [len(r.seq) for chunk in f for r in chunk]
+print(kept)
+assert kept == [n for n in all_lengths if 180 <= n <= 200]
+assert kept  # the bounds keep some but not all test reads
+assert len(kept) < len(all_lengths)
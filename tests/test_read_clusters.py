@@ -0,0 +1,45 @@
+import tempfile
+from pathlib import Path
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+with tempfile.TemporaryDirectory() as tmp:
+    raw_path = Path(tmp) / "raw.bam"
+    write_bam(
+        raw_path,
+        [("chr1", 1000)],
+        [
+            # a [100, 150) and b [120, 170) overlap; c [200, 230) is 30 bp past b
+            encode_record("a", rid=0, pos=100, cigar=[("M", 50)], seq="A" * 50),
+            encode_record("b", rid=0, pos=120, cigar=[("M", 50)], seq="C" * 50),
+            encode_record("c", rid=0, pos=200, cigar=[("M", 30)], seq="G" * 30),
+        ],
+    )
+    raw = lb.BamReader(str(raw_path))
+    records = [r for chunk in raw for r in chunk]
+
+    # region queries need an index; merge_chunks_py writes one alongside
+    chunk_path = Path(tmp) / "chunk.bam"
+    indexed_path = Path(tmp) / "indexed.bam"
+    lb.write_chunk_py(raw._header, records, str(chunk_path), sort=True)
+    lb.merge_chunks_py(raw._header, [str(chunk_path)], str(indexed_path), sort=True)
+    reader = lb.BamReader(str(indexed_path))
+
+    def summary(clusters):
+        return [([r.qname for r in members], s, e) for members, s, e in clusters]
+
+    assert summary(reader.read_clusters("chr1", 0, 1000)) == [
+        (["a", "b"], 100, 170),
+        (["c"], 200, 230),
+    ]
+    assert summary(reader.read_clusters("chr1", 0, 1000, gap=29)) == [
+        (["a", "b"], 100, 170),
+        (["c"], 200, 230),
+    ]
+    # c starts exactly `gap` bp after the cluster end
+    assert summary(reader.read_clusters("chr1", 0, 1000, gap=30)) == [
+        (["a", "b", "c"], 100, 230)
+    ]
+    # only reads overlapping the region are clustered
+    assert summary(reader.read_clusters("chr1", 190, 1000)) == [(["c"], 200, 230)]
@@ -0,0 +1,65 @@
+import tempfile
+from pathlib import Path
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+
+f = lb.BamReader(str(path_to_bam), chunk_size=1000)
+records = next(f)
+
+
+def roundtrip(recs):
+    with tempfile.TemporaryDirectory() as tmp:
+        out = Path(tmp) / "out.bam"
+        lb.write_chunk_py(f._header, recs, str(out), sort=False)
+        return [r for chunk in lb.BamReader(str(out), chunk_size=1000) for r in chunk]
+
+
+# MAPQ recalibration: the getter reflects the new value, the writer emits it
+assert records[0].mapq == 0
+records[0].mapq = 42
+records[1].mapq = 255  # 255 = missing
+assert records[0].mapq == 42
+written = roundtrip(records[:3])
+assert [r.mapq for r in written] == [42, 255, records[2].mapq]
+
+# read names rewritten with a corrected UMI
+original = records[3].qname
+records[3].qname = original + "_ACGTACGT"
+assert records[3].qname == original + "_ACGTACGT"
+for bad in ("", "has space", "tab\tname"):
+    try:
+        records[4].qname = bad
+    except ValueError:
+        pass
+    else:
+        raise AssertionError(f"accepted read name {bad!r}")
+assert roundtrip(records[3:5])[0].qname == original + "_ACGTACGT"
+assert roundtrip(records[3:5])[1].qname == records[4].qname
+
+# typed tag values added before writing
+rec = records[5]
+rec.set_tag("NM", 3)
+rec.set_tag("NM", 4)  # overwrites
+rec.set_tag("RG", "grp1")
+rec.set_tag("XF", 0.5)
+rec.set_tag("XC", b"Q")
+rec.set_tag("XI", [1, -2, 3])
+rec.set_tag("XP", [0.25, 1.5])
+for bad_tag in ("N", "NMX", "Né"):
+    try:
+        rec.set_tag(bad_tag, 1)
+    except ValueError:
+        pass
+    else:
+        raise AssertionError(f"accepted tag {bad_tag!r}")
+
+out = roundtrip([rec])[0]
+assert out.get_field_by_tag("NM") == 4
+assert out.get_field_by_tag("RG") == "grp1"
+assert out.get_field_by_tag("XF") == 0.5
+assert out.get_field_by_tag("XC") == "Q"
+assert list(out.get_field_by_tag("XI")) == [1, -2, 3]
+assert list(out.get_field_by_tag("XP")) == [0.25, 1.5]
+assert [t for t, _ in out.tags].count("NM") == 1
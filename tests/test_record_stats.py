@@ -0,0 +1,157 @@
+from pathlib import Path
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+
+f = lb.BamReader(str(path_to_bam), chunk_size=1)
+record = next(f)[0]
+
+# 16S7M1I15M1D36M1D3M3D4M1D5M1D22M107S, no MD/NM tags
+stats = record.alignment_stats()
+print(stats)
+assert stats["matches"] == 92
+assert stats["mismatches"] == 0
+assert stats["insertions"] == 1
+assert stats["deletions"] == 7
+assert stats["soft_clips"] == 123
+assert stats["hard_clips"] == 0
+
+gaps = record.gap_summary
+assert gaps == {
+    "insertions": 1,
+    "inserted_bases": 1,
+    "deletions": 5,
+    "deleted_bases": 7,
+    "largest_gap": 3,
+}
+
+# aligned pairs agree with query_to_reference / reference_to_query
+pairs = record.aligned_pairs()
+assert pairs.dtype.name == "int64" and pairs.shape[1] == 2
+# 92 M + 1 I + 7 D; the soft clips are left out by default
+assert len(pairs) == 92 + 1 + 7
+for q, r in pairs.tolist():
+    if q >= 0 and r >= 0:
+        assert record.query_to_reference(q) == r
+    elif q >= 0:
+        assert record.query_to_reference(q) is None
+    else:
+        assert record.reference_to_query(r) is None
+with_clips = record.aligned_pairs(include_soft_clips=True)
+assert len(with_clips) == len(pairs) + 16 + 107
+assert with_clips[:16].tolist() == [[q, -1] for q in range(16)]
+
+# query offset 16 is the first aligned base, offset 23 the inserted base
+assert record.query_to_reference(0) is None
+assert record.query_to_reference(16) == record.pos - 1
+assert record.query_to_reference(22) == record.pos - 1 + 6
+assert record.query_to_reference(23) is None
+assert record.query_to_reference(24) == record.pos - 1 + 7
+
+# read base at a reference locus: 16S7M1I15M1D...
+start = record.pos - 1
+assert record.reference_to_query(start) == 16
+assert record.reference_to_query(start + 7) == 24
+assert record.seq[record.reference_to_query(start + 7)] == record.seq[24]
+# the first deletion sits right after 7M1I15M
+assert record.reference_to_query(start + 22) is None
+assert record.reference_to_query(start - 1) is None
+
+# three identical reads collapse to one read with boosted qualities
+consensus = lb.build_consensus_read([record, record, record])
+assert consensus.seq == record.seq
+assert all(
+    c == min(93, 3 * q) or (q == 0 and c == 2)
+    for c, q in zip(consensus.qual, record.qual)
+)
+assert consensus.get_field_by_tag("cD") == 3
+
+# 16S...107S splits into clip / aligned / clip
+segments = record.sequence_segments
+assert [s[0] for s in segments] == ["soft_clip", "aligned", "soft_clip"]
+assert [(s[1], s[2]) for s in segments] == [
+    (0, 16),
+    (16, len(record.seq) - 107),
+    (len(record.seq) - 107, len(record.seq)),
+]
+assert "".join(s[3] for s in segments) == record.seq
+
+# a mate overlapping itself completely merges back to its own read
+merged_seq, merged_qual = lb.merge_pair(record, record)
+assert merged_seq == record.seq
+assert merged_qual == record.qual
+
+# spliced copy of the read: 16S50M100N50M200N100M
+import tempfile
+
+with tempfile.TemporaryDirectory() as tmp:
+    spliced_path = Path(tmp) / "spliced.bam"
+    record.set_record_override(
+        lb.RecordOverride(cigar=[(4, 16), (0, 50), (3, 100), (0, 50), (3, 200), (0, 100)])
+    )
+    lb.write_chunk_py(f._header, [record], str(spliced_path), sort=False)
+    spliced = next(lb.BamReader(str(spliced_path)))[0]
+
+start = spliced.pos - 1
+known = {(spliced.rid, start + 50, start + 150)}
+assert spliced.novel_junctions(known) == [(start + 200, start + 400)]
+assert spliced.novel_junctions(set()) == [(start + 50, start + 150), (start + 200, start + 400)]
+assert spliced.exon_contributions == [
+    (start, start + 50, 50),
+    (start + 150, start + 200, 50),
+    (start + 400, start + 500, 100),
+]
+
+# the test reads are single-end
+assert record.fragment_midpoint is None
+assert record.pair_orientation is None
+
+# OQ holds the pre-recalibration qualities as phred+33 text
+assert list(record.original_quality) == record.qual
+with tempfile.TemporaryDirectory() as tmp:
+    oq_path = Path(tmp) / "oq.bam"
+    oq = "".join(chr(33 + min(q + 5, 93)) for q in record.qual)
+    record.set_record_override(lb.RecordOverride(tags=[("OQ", oq)]))
+    lb.write_chunk_py(f._header, [record], str(oq_path), sort=False)
+    recalibrated = next(lb.BamReader(str(oq_path)))[0]
+assert list(recalibrated.original_quality) == [min(q + 5, 93) for q in record.qual]
+
+# reference with an assembly gap of 12 Ns inside the aligned span
+with tempfile.TemporaryDirectory() as tmp:
+    fasta_path = Path(tmp) / "ref.fa"
+    ref_seq = "A" * 30 + "N" * 12 + "C" * 57
+    fasta_path.write_text(">Ala1B\n" + ref_seq + "\n")
+    Path(str(fasta_path) + ".fai").write_text(f"Ala1B\t{len(ref_seq)}\t7\t{len(ref_seq)}\t{len(ref_seq) + 1}\n")
+    plain = next(lb.BamReader(str(path_to_bam), chunk_size=1))[0]
+    assert plain.reference_n_count(str(fasta_path)) == 12
+
+# local GC: one value per window, whole read when the window is longer
+gc = record.local_gc(20)
+assert gc.dtype.name == "float64"
+assert len(gc) == len(record.seq) - 20 + 1
+assert abs(gc[5] - sum(b in "GC" for b in record.seq[5:25]) / 20) < 1e-12
+whole = record.local_gc(len(record.seq) + 10)
+assert len(whole) == 1
+assert abs(whole[0] - sum(b in "GC" for b in record.seq) / len(record.seq)) < 1e-12
+
+# aligned-region qualities skip the 16S / 107S soft clips
+aligned_quals = record.query_alignment_qualities
+assert aligned_quals.dtype.name == "uint8"
+assert len(aligned_quals) == len(record.seq) - 16 - 107
+assert list(aligned_quals) == record.qual[16 : len(record.qual) - 107]
+
+# qual_array holds the same values as qual, one byte per base
+qual_array = record.qual_array
+assert qual_array.dtype.name == "uint8"
+assert qual_array.tolist() == record.qual
+
+# seq_array holds the ASCII codes of seq
+seq_array = record.seq_array
+assert seq_array.dtype.name == "uint8"
+assert seq_array.tobytes().decode() == record.seq
+assert (seq_array == ord("A")).sum() == record.seq.count("A")
+
+# CIGAR-derived lengths: 92 M + 7 D on the reference, all bases on the query
+assert record.reference_length == 99
+assert record.query_length == len(record.seq)
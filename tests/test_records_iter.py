@@ -0,0 +1,21 @@
+from pathlib import Path
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+
+expected = [r.qname for chunk in lb.BamReader(str(path_to_bam), chunk_size=1000) for r in chunk]
+
+reader = lb.BamReader(str(path_to_bam))
+names = []
+for rec in reader.records():
+    assert isinstance(rec, lb.PyBamRecord)
+    names.append(rec.qname)
+assert names == expected
+
+# shares the reader's position with chunked iteration
+reader = lb.BamReader(str(path_to_bam), chunk_size=3)
+first_chunk = next(reader)
+rest = [rec.qname for rec in reader.records()]
+assert [r.qname for r in first_chunk] + rest == expected
+assert list(reader.records()) == []
@@ -0,0 +1,22 @@
+from pathlib import Path
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+
+f = lb.BamReader(str(path_to_bam), chunk_size=1000)
+counts, unplaced = f.reference_counts()
+print(counts, unplaced)
+
+expected = [0] * len(counts)
+expected_unplaced = 0
+for records in lb.BamReader(str(path_to_bam), chunk_size=1000):
+    for record in records:
+        if record.rid < 0:
+            expected_unplaced += 1
+        else:
+            expected[record.rid] += 1
+
+assert counts.dtype.name == "int64"
+assert list(counts) == expected
+assert unplaced == expected_unplaced
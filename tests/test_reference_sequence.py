@@ -0,0 +1,49 @@
+import tempfile
+from pathlib import Path
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+# 2S3M2I2D3M: read TT|ACG|AA|--|GTC against reference ATG CT GTC
+# (C/T mismatch at the second aligned base, CT deleted)
+cases = [
+    ("indel", [("S", 2), ("M", 3), ("I", 2), ("D", 2), ("M", 3)], "TTACGAAGTC", "1T1^CT3", "ATGCTGTC"),
+    ("exact", [("M", 6)], "ACGTAC", "6", "ACGTAC"),
+    # lowercase MD bases are reported uppercase; N skips add nothing
+    ("spliced", [("M", 3), ("N", 100), ("M", 3)], "ACGTAC", "0g5", "GCGTAC"),
+]
+
+with tempfile.TemporaryDirectory() as tmp:
+    src = Path(tmp) / "plain.bam"
+    write_bam(
+        src,
+        [("chr1", 1000)],
+        [
+            encode_record(name, rid=0, pos=10, cigar=cigar, seq=seq)
+            for name, cigar, seq, _, _ in cases
+        ]
+        + [encode_record("no_md", rid=0, pos=10, cigar=[("M", 4)], seq="ACGT")],
+    )
+    reader = lb.BamReader(str(src))
+    records = [r for chunk in reader for r in chunk]
+    for rec, (_, _, _, md, _) in zip(records, cases):
+        rec.set_tag("MD", md)
+    # MD that disagrees with the CIGAR
+    bad = [r for chunk in lb.BamReader(str(src)) for r in chunk][1]
+    bad.set_tag("MD", "10")
+
+    out = Path(tmp) / "md.bam"
+    lb.write_chunk_py(reader._header, records + [bad], str(out), sort=False)
+    written = [r for chunk in lb.BamReader(str(out)) for r in chunk]
+
+    for rec, (name, _, _, _, expected) in zip(written, cases):
+        assert rec.qname == name
+        assert rec.get_reference_sequence() == expected, name
+
+    for rec in written[len(cases) :]:
+        try:
+            rec.get_reference_sequence()
+        except ValueError:
+            pass
+        else:
+            raise AssertionError(f"{rec.qname}: missing / inconsistent MD accepted")
@@ -0,0 +1,191 @@
+import collections
+import struct
+import tempfile
+from pathlib import Path
+
+import numpy as np
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+
+f = lb.BamReader(str(path_to_bam), chunk_size=1000)
+records = [r for chunk in f for r in chunk]
+
+with tempfile.TemporaryDirectory() as tmp:
+    # region queries need an index; merge_chunks_py writes one alongside
+    chunk_path = Path(tmp) / "chunk.bam"
+    indexed_path = Path(tmp) / "indexed.bam"
+    lb.write_chunk_py(f._header, records, str(chunk_path), sort=True)
+    lb.merge_chunks_py(f._header, [str(chunk_path)], str(indexed_path), sort=True)
+
+    reader = lb.BamReader(str(indexed_path))
+
+    # fetch: 0-based half-open, like pysam
+    fetched = list(reader.fetch("Ala1B", 0, 100))
+    assert sorted(r.qname for r in fetched) == sorted(r.qname for r in records)
+    assert len(list(reader.fetch("Ala1B", 0, 1))) == len(records)
+    assert list(reader.fetch("Ala2", 0, 99)) == []
+    # fetch streams records rather than materialising a list
+    it = reader.fetch("Ala1B", 0, 100)
+    assert iter(it) is it
+    assert next(it).qname in {r.qname for r in records}
+    assert len(list(it)) == len(records) - 1
+    # the index is read once and kept on the reader
+    cached = lb.BamReader(str(indexed_path))
+    assert len(list(cached.fetch("Ala1B", 0, 100))) == len(records)
+    bai_path = Path(str(indexed_path) + ".bai")
+    bai_bytes = bai_path.read_bytes()
+    bai_path.unlink()
+    assert len(list(cached.fetch("Ala1B", 0, 100))) == len(records)
+    bai_path.write_bytes(bai_bytes)
+
+    # per-base coverage: reads with an aligned (not deleted) base at each position
+    cov = reader.coverage("Ala1B", 10, 60)
+    assert cov.dtype.name == "uint32"
+    assert len(cov) == 50
+    assert cov.tolist() == [
+        sum(r.reference_to_query(pos) is not None for r in records) for pos in range(10, 60)
+    ]
+    assert reader.coverage("Ala2", 0, 99).sum() == 0
+    # the reader's filters apply (every test read has MAPQ 0)
+    assert lb.BamReader(str(indexed_path), min_mapq=1).coverage("Ala1B", 10, 60).sum() == 0
+    try:
+        reader.fetch("chrUnknown", 0, 10)
+    except ValueError as e:
+        print(e)
+    else:
+        raise AssertionError("unknown contig was accepted")
+    try:
+        lb.BamReader(str(chunk_path)).fetch("Ala1B", 0, 100)
+    except FileNotFoundError as e:
+        assert ".bai" in str(e)
+    else:
+        raise AssertionError("missing index was not reported")
+    insertions, deletions = reader.indel_profile("Ala1B", 0, 100)
+    print(insertions, deletions)
+    assert len(insertions) == len(deletions) == 100
+
+    # four of the test reads share a deletion of reference base 66
+    assert deletions[66] == 4
+    assert deletions.argmax() == 66
+    assert insertions[1] == 2
+
+    # a sub-window sees the same counts at the same positions
+    _, window = reader.indel_profile("Ala1B", 60, 70)
+    assert list(window) == list(deletions[60:70])
+
+    # CPM divides by the 10 test reads: raw depth x 1e6 / 10
+    raw = reader.normalized_coverage("Ala1B", 0, 100, scale=1.0)
+    cpm = reader.normalized_coverage("Ala1B", 0, 100)
+    assert raw.dtype.name == "float64"
+    assert raw.max() > 0
+    assert all(abs(c - r * 1e6 / len(records)) < 1e-6 for c, r in zip(cpm, raw))
+    assert list(reader.normalized_coverage("Ala1B", 0, 100, scale=0.5)) == list(raw * 0.5)
+
+    # uniformity over two targets, checked against the raw depth
+    targets = [("Ala1B", 0, 40), ("Ala1B", 60, 100)]
+    depth = [int(d) for s, e in [(0, 40), (60, 100)] for d in raw[s:e]]
+    mean = sum(depth) / len(depth)
+    sd = (sum((d - mean) ** 2 for d in depth) / len(depth)) ** 0.5
+    covered = sorted(d for d in depth if d > 0)
+    p20 = covered[-(-len(covered) // 5) - 1]
+    uniformity = reader.coverage_uniformity(targets)
+    assert abs(uniformity["mean"] - mean) < 1e-9
+    assert abs(uniformity["cv"] - sd / mean) < 1e-9
+    assert abs(uniformity["fold_80_penalty"] - mean / p20) < 1e-9
+
+    # pileup tensor: one row per read, one-hot bases match the aligned query
+    tensor = reader.pileup_tensor("Ala1B", 10, 30, 8)
+    assert tensor.dtype.name == "uint8"
+    assert tensor.shape == (5, 8, 20)
+    expected_rows = []
+    for record in records:
+        row = np.zeros((5, 20), dtype=np.uint8)
+        for col, pos in enumerate(range(10, 30)):
+            q = record.reference_to_query(pos)
+            if q is not None:
+                if record.seq[q] in "ACGT":
+                    row["ACGT".index(record.seq[q]), col] = 1
+                row[4, col] = record.qual[q]
+        expected_rows.append(row)
+    for row in range(8):
+        assert any((tensor[:, row, :] == e).all() for e in expected_rows)
+
+    # region payload: decode the binary layout back into positions / CIGARs
+    payload = reader.region_payload("Ala1B", 0, 100, 1000)
+    (n_reads,) = struct.unpack_from("<I", payload, 0)
+    offset = 4
+    decoded = []
+    for _ in range(n_reads):
+        pos, flag, n_cigar = struct.unpack_from("<iHH", payload, offset)
+        offset += 8
+        ops = struct.unpack_from(f"<{n_cigar}I", payload, offset)
+        offset += 4 * n_cigar
+        (l_seq,) = struct.unpack_from("<I", payload, offset)
+        offset += 4
+        packed = payload[offset : offset + (l_seq + 1) // 2]
+        offset += (l_seq + 1) // 2
+        seq = "".join("=ACMGRSVTWYHKDBN"[b >> s & 0xF] for b in packed for s in (4, 0))
+        decoded.append((pos, flag, [(op & 0xF, op >> 4) for op in ops], seq[:l_seq]))
+    assert offset == len(payload)
+    assert sorted(decoded) == sorted(
+        (r.pos - 1, r.flag, [tuple(op) for op in r.cigar], r.seq) for r in records
+    )
+    assert len(reader.region_payload("Ala1B", 0, 100, 3)) < len(payload)
+
+    # allele depths at a few loci match a manual count over the reads
+    loci = [("Ala1B", 30), ("Ala1B", 66), ("Ala1B", 5), ("Ala2", 10)]
+    depths = reader.allele_depths(loci)
+    for (_, pos), got in zip(loci[:3], depths):
+        expected = collections.Counter(
+            r.seq[r.reference_to_query(pos)]
+            for r in records
+            if r.reference_to_query(pos) is not None
+        )
+        assert got == dict(expected)
+    assert depths[3] == {}
+    strict = reader.allele_depths(loci[:1], min_base_qual=94)
+    assert strict == [{}]
+
+    # every test read is soft-clipped right where its alignment starts
+    classes = reader.reads_at_breakpoint("Ala1B", 0, 50)
+    assert len(classes["split"]) == len(records)
+    assert classes["spanning"] == [] and classes["flanking"] == []
+    classes = reader.reads_at_breakpoint("Ala1B", 20, 5)
+    assert len(classes["spanning"]) == len(records)
+
+# 5mC calls: first aligned C of each read confidently methylated, second
+# confidently unmethylated
+import collections
+
+expected_meth = collections.Counter()
+expected_total = collections.Counter()
+modified = []
+for record in records:
+    aligned_cs = [
+        i
+        for i, base in enumerate(record.seq)
+        if base == "C" and record.query_to_reference(i) is not None
+    ][:2]
+    k0 = record.seq[: aligned_cs[0]].count("C")
+    k1 = record.seq[aligned_cs[0] + 1 : aligned_cs[1]].count("C")
+    record.set_record_override(
+        lb.RecordOverride(
+            tags=[("MM", f"C+m?,{k0},{k1};"), ("ML", np.array([230, 10], dtype=np.uint8))]
+        )
+    )
+    modified.append(record)
+    expected_meth[record.query_to_reference(aligned_cs[0])] += 1
+    expected_total[record.query_to_reference(aligned_cs[0])] += 1
+    expected_total[record.query_to_reference(aligned_cs[1])] += 1
+
+with tempfile.TemporaryDirectory() as tmp:
+    chunk_path = Path(tmp) / "chunk.bam"
+    indexed_path = Path(tmp) / "modified.bam"
+    lb.write_chunk_py(f._header, modified, str(chunk_path), sort=True)
+    lb.merge_chunks_py(f._header, [str(chunk_path)], str(indexed_path), sort=True)
+
+    methylated, total = lb.BamReader(str(indexed_path)).methylation_profile("Ala1B", 0, 100)
+    assert {i: n for i, n in enumerate(methylated) if n} == dict(expected_meth)
+    assert {i: n for i, n in enumerate(total) if n} == dict(expected_total)
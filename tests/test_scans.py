@@ -0,0 +1,123 @@
+import tempfile
+from pathlib import Path
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+f = lb.BamReader(str(path_to_bam), chunk_size=1000)
+
+# Every test read starts at the same base with a left soft clip.
+breakpoints = f.clip_breakpoints(min_clip_len=5, min_support=5)
+print(breakpoints)
+left = [b for b in breakpoints if b[2] == "left"]
+assert len(left) == 1
+name, pos, side, support = left[0]
+assert name == "Ala1B"
+assert pos == 0
+assert support == 10
+
+# the test BAM is coordinate sorted; moving one record before its
+# predecessor breaks the order at that record
+assert f.verify_sorted() is None
+records = [r for chunk in lb.BamReader(str(path_to_bam), chunk_size=1000) for r in chunk]
+for i, record in enumerate(records):
+    start = 15 if i == 6 else 10 * (i + 1)
+    record.set_record_override(lb.RecordOverride(alignment_start=start))
+shuffled_path = path_to_bam.parent / "test_reads_shuffled.bam"
+lb.write_chunk_py(f._header, records, str(shuffled_path), sort=False)
+shuffled = lb.BamReader(str(shuffled_path))
+assert shuffled.verify_sorted() == (6, records[6].qname)
+shuffled_path.unlink()
+
+# union of the aligned blocks of all test reads, counted by hand from the SAM
+assert f.covered_bases() == 99
+assert f.covered_bases("Ala1B") == 99
+for bad_depth in (0, -1):
+    try:
+        f.covered_bases(min_depth=bad_depth)
+    except ValueError:
+        pass
+    else:
+        raise AssertionError(f"accepted min_depth={bad_depth}")
+
+# every base lands in exactly one cell of the quality/base matrix
+matrix = f.quality_base_matrix(1000, 8)
+assert matrix.shape == (1000, 8, 5)
+total_bases = sum(
+    len(record.seq) for records in lb.BamReader(str(path_to_bam), chunk_size=1000) for record in records
+)
+assert matrix.sum() == total_bases
+
+# a locally shuffled file comes back in coordinate order
+positions = [1, 3, 2, 5, 4, 6, 8, 7, 10, 9]
+for record, p in zip(records, positions):
+    record.set_record_override(lb.RecordOverride(alignment_start=p))
+lb.write_chunk_py(f._header, records, str(shuffled_path), sort=False)
+emitted = [r.pos for r in lb.BamReader(str(shuffled_path)).sort_window(5)]
+assert emitted == sorted(positions)
+try:
+    list(lb.BamReader(str(shuffled_path)).sort_window(0))
+except ValueError as e:
+    print(e)
+else:
+    raise AssertionError("out-of-window record was not rejected")
+shuffled_path.unlink()
+
+# sliding yields each record with up to n_before preceding records
+names = [r.qname for records in lb.BamReader(str(path_to_bam)) for r in records]
+for i, (record, before) in enumerate(lb.BamReader(str(path_to_bam)).sliding(3)):
+    assert record.qname == names[i]
+    assert [r.qname for r in before] == names[max(0, i - 3) : i]
+assert all(before == [] for _, before in lb.BamReader(str(path_to_bam)).sliding(0))
+
+# sam_lines renders the header then one SAM line per record
+lines = list(lb.BamReader(str(path_to_bam)).sam_lines())
+header_lines = [l for l in lines if l.startswith("@")]
+assert any(l.startswith("@SQ\tSN:Ala1B") for l in header_lines)
+assert lines[: len(header_lines)] == header_lines
+body = lines[len(header_lines) :]
+originals = [r for records in lb.BamReader(str(path_to_bam)) for r in records]
+assert len(body) == len(originals)
+for line, record in zip("\n".join(body).split("\n"), originals):
+    qname, flag, rname, pos, mapq, cigar, _, _, _, seq, qual = line.split("\t")[:11]
+    assert qname == record.qname and int(flag) == record.flag
+    assert rname == "Ala1B" and int(pos) == record.pos
+    assert int(mapq) == record.mapq
+    assert cigar == "".join(f"{n}{'MIDNSHP=X'[op]}" for op, n in record.cigar)
+    assert seq == record.seq
+    assert qual == "".join(chr(q + 33) for q in record.qual)
+
+# to_sam_line renders a single record like sam_lines, overrides included
+assert [r.to_sam_line() for r in originals] == body
+renamed = originals[0]
+renamed.qname = "renamed"
+assert renamed.to_sam_line().split("\t")[0] == "renamed"
+assert renamed.to_sam_line().split("\t")[2] == "Ala1B"
+
+# mate fields and TLEN survive to_sam_line; every column matches sam_lines
+with tempfile.TemporaryDirectory() as tmp:
+    paired_path = Path(tmp) / "paired.bam"
+    common = dict(cigar=[("M", 4)], seq="ACGT", qual=[30, 31, 32, 33])
+    write_bam(
+        paired_path,
+        [("chr1", 1000), ("chr2", 1000)],
+        [
+            encode_record("p", flag=0x1 | 0x2 | 0x20 | 0x40, rid=0, pos=99, mapq=40,
+                          next_rid=0, next_pos=199, tlen=104, **common),
+            encode_record("p", flag=0x1 | 0x2 | 0x10 | 0x80, rid=0, pos=199, mapq=40,
+                          next_rid=0, next_pos=99, tlen=-104, **common),
+            encode_record("q", flag=0x1 | 0x40, rid=0, pos=299, mapq=20,
+                          next_rid=1, next_pos=49, **common),
+        ],
+    )
+    reader = lb.BamReader(str(paired_path))
+    body = [l for l in reader.sam_lines() if not l.startswith("@")]
+    paired = [r for records in lb.BamReader(str(paired_path)) for r in records]
+    assert [r.to_sam_line() for r in paired] == body
+    assert [l.split("\t") for l in body] == [
+        ["p", "99", "chr1", "100", "40", "4M", "=", "200", "104", "ACGT", "?@AB"],
+        ["p", "147", "chr1", "200", "40", "4M", "=", "100", "-104", "ACGT", "?@AB"],
+        ["q", "65", "chr1", "300", "20", "4M", "chr2", "50", "0", "ACGT", "?@AB"],
+    ]
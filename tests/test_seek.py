@@ -0,0 +1,35 @@
+import random
+import tempfile
+from pathlib import Path
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+# enough records to span several bgzf blocks
+n = 3000
+records = [
+    encode_record(f"read{i:05d}", rid=0, pos=i, cigar=[("M", 8)], seq="ACGTACGT")
+    for i in range(n)
+]
+
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "seek.bam"
+    write_bam(path, [("chr1", 100_000)], records)
+
+    for threads in (1, 2):
+        reader = lb.BamReader(str(path), chunk_size=1, threads=threads)
+        offsets = []
+        for _ in range(n):
+            offsets.append(reader.virtual_position)
+            next(reader)
+        assert len({off >> 16 for off in offsets}) > 1
+
+        # seek back to offsets in arbitrary order and re-read the same record
+        for i in random.Random(0).sample(range(n), 20) + [0, n - 1]:
+            reader.seek(offsets[i])
+            assert next(reader)[0].qname == f"read{i:05d}"
+
+        # iteration continues record by record after a seek
+        reader.seek(offsets[100])
+        it = reader.records()
+        assert [next(it).qname for _ in range(3)] == ["read00100", "read00101", "read00102"]
@@ -0,0 +1,25 @@
+import tempfile
+from pathlib import Path
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "strands.bam"
+    write_bam(
+        path,
+        [("chr1", 1000)],
+        [
+            encode_record("fwd", flag=0, rid=0, pos=0, cigar=[("M", 8)], seq="AACGTNRK"),
+            encode_record("rev", flag=0x10, rid=0, pos=0, cigar=[("M", 8)], seq="AACGTNRK"),
+            encode_record("iupac", flag=0x10, rid=0, pos=0, cigar=[("M", 8)], seq="SWBVDHMY"),
+            encode_record("noseq", flag=0x10, rid=0, pos=0, cigar=[("M", 8)]),
+        ],
+    )
+    fwd, rev, iupac, noseq = next(lb.BamReader(str(path), chunk_size=10))
+
+assert fwd.seq_forward == "AACGTNRK"
+# reverse complement: N stays N, R <-> Y, K <-> M
+assert rev.seq_forward == "MYNACGTT"
+assert iupac.seq_forward == "RKDHBVWS"
+assert noseq.seq_forward == ""
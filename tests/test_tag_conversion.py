@@ -0,0 +1,58 @@
+import io
+import tempfile
+from pathlib import Path
+
+import numpy as np
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+tags = [
+    ("XC", "c", -5),
+    ("XU", "C", 200),
+    ("XF", "f", 1.5),
+    ("XA", "A", "G"),
+    ("XZ", "Z", "hello"),
+]
+
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "tags.bam"
+    write_bam(path, [("chr1", 1000)], [encode_record("r", seq="ACGT", tags=tags)])
+
+    def first(mode):
+        return next(lb.BamReader(str(path), tag_conversion=mode))[0]
+
+    # python: plain ints / floats / strs
+    rec = first("python")
+    assert type(rec.get_tag("XC")) is int and rec.get_tag("XC") == -5
+    assert rec.get_tag("XU") == 200
+    assert rec.get_tag("XF") == 1.5
+    # A tags decode to the decimal character code, as before tag_conversion existed
+    assert rec.get_tag("XA") == str(ord("G"))
+    assert rec.get_tag("XZ") == "hello"
+
+    # numpy: scalars keep the BAM dtype
+    rec = first("numpy")
+    xc = rec.get_tag("XC")
+    assert isinstance(xc, np.int8) and xc == -5
+    xu = rec.get_tag("XU")
+    assert isinstance(xu, np.uint8) and xu == 200
+    assert isinstance(rec.get_tag("XF"), np.float32)
+    assert dict(rec.tags)["XC"].dtype == np.int8
+
+    # raw: (type code, little-endian payload)
+    rec = first("raw")
+    assert rec.get_tag("XC") == ("c", b"\xfb")
+    assert rec.get_tag("XA") == ("A", b"G")
+    assert rec.get_tag("XZ") == ("Z", b"hello")
+
+    # the option is honoured by the other entry points too
+    rec = next(lb.BamReader.from_fileobj(io.BytesIO(path.read_bytes()), tag_conversion="numpy"))[0]
+    assert isinstance(rec.get_tag("XC"), np.int8)
+
+    try:
+        lb.BamReader(str(path), tag_conversion="bogus")
+    except ValueError:
+        pass
+    else:
+        raise AssertionError("accepted an unknown tag_conversion")
@@ -0,0 +1,21 @@
+from pathlib import Path
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+
+
+def records(**kwargs):
+    f = lb.BamReader(str(path_to_bam), chunk_size=4, **kwargs)
+    return [(r.qname, r.pos, r.cigar, r.seq, r.qual) for chunk in f for r in chunk]
+
+
+expected = records()
+assert expected
+for threads in (0, 1, 2, 4):
+    assert records(threads=threads) == expected
+
+# full scans open their own reader with the same worker count
+counts, unmapped = lb.BamReader(str(path_to_bam), threads=4).reference_counts()
+expected_counts, expected_unmapped = lb.BamReader(str(path_to_bam)).reference_counts()
+assert list(counts) == list(expected_counts) and unmapped == expected_unmapped
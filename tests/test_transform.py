@@ -0,0 +1,69 @@
+import tempfile
+from pathlib import Path
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+original = [r for chunk in lb.BamReader(str(path_to_bam), chunk_size=1000) for r in chunk]
+
+
+def upper_qname(record):
+    record.set_record_override(lb.RecordOverride(qname=record.qname.upper()))
+    return record
+
+
+with tempfile.TemporaryDirectory() as tmp:
+    out_path = Path(tmp) / "upper.bam"
+    written = lb.transform(str(path_to_bam), str(out_path), upper_qname, chunk_size=3)
+    assert written == len(original)
+    out = [r for chunk in lb.BamReader(str(out_path), chunk_size=1000) for r in chunk]
+    assert [r.qname for r in out] == [r.qname.upper() for r in original]
+    assert [r.seq for r in out] == [r.seq for r in original]
+
+    # None drops a record, a list emits several
+    def drop_first_double_rest(record):
+        if record.qname == original[0].qname:
+            return None
+        return [record, record]
+
+    out_path = Path(tmp) / "dropped.bam"
+    written = lb.transform(str(path_to_bam), str(out_path), drop_first_double_rest)
+    assert written == 2 * (len(original) - 1)
+
+# the identity transform keeps mate coordinates and TLEN of a pair
+with tempfile.TemporaryDirectory() as tmp:
+    paired_path = Path(tmp) / "paired.bam"
+    common = dict(rid=0, cigar=[("M", 50)], next_rid=0, seq="A" * 50, qual=[30] * 50)
+    write_bam(
+        paired_path,
+        [("chr1", 1000)],
+        [
+            encode_record("p", flag=0x1 | 0x2 | 0x20 | 0x40, pos=99, next_pos=299, tlen=250, **common),
+            encode_record("p", flag=0x1 | 0x2 | 0x10 | 0x80, pos=299, next_pos=99, tlen=-250, **common),
+        ],
+    )
+    out_path = Path(tmp) / "identity.bam"
+    assert lb.transform(str(paired_path), str(out_path), lambda r: r) == 2
+    out = [r for chunk in lb.BamReader(str(out_path)) for r in chunk]
+    assert [r.mate_pos for r in out] == [300, 100]
+    assert [r.mate_rname for r in out] == ["chr1", "chr1"]
+    assert [r.template_length for r in out] == [250, -250]
+
+# tag_all stamps every record and records itself in @PG
+with tempfile.TemporaryDirectory() as tmp:
+    out_path = Path(tmp) / "stamped.bam"
+    assert lb.tag_all(str(path_to_bam), str(out_path), "XP", "step1") == len(original)
+    reader = lb.BamReader(str(out_path), chunk_size=1000)
+    assert any(
+        line.startswith(b"@PG") and b"PN:lazybam" in line for line in reader._header.splitlines()
+    )
+    stamped = [r for chunk in reader for r in chunk]
+    assert all(dict(r.tags)["XP"] == "step1" for r in stamped)
+
+    int_path = Path(tmp) / "stamped_int.bam"
+    lb.tag_all(str(path_to_bam), str(int_path), "XI", 7)
+    assert all(
+        dict(r.tags)["XI"] == 7 for chunk in lb.BamReader(str(int_path)) for r in chunk
+    )
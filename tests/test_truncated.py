@@ -0,0 +1,49 @@
+"""A corrupt or truncated BAM raises instead of ending iteration early."""
+
+import tempfile
+from pathlib import Path
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+records = [
+    encode_record(f"r{i}", rid=0, pos=i, cigar=[("M", 4)], seq="ACGT") for i in range(5)
+]
+
+
+def read_all(path, **kwargs):
+    return [r.qname for chunk in lb.BamReader(str(path), **kwargs) for r in chunk]
+
+
+with tempfile.TemporaryDirectory() as tmp:
+    # intact file reads to the end and stops cleanly
+    path = Path(tmp) / "ok.bam"
+    write_bam(path, [("chr1", 1000)], records)
+    assert read_all(path) == [f"r{i}" for i in range(5)]
+
+    # last record cut short inside a well-formed bgzf block
+    path = Path(tmp) / "short_record.bam"
+    write_bam(path, [("chr1", 1000)], records[:-1] + [records[-1][:-6]])
+    for chunk_size in (1, 100):
+        seen = []
+        try:
+            for chunk in lb.BamReader(str(path), chunk_size=chunk_size):
+                seen += [r.qname for r in chunk]
+        except OSError:
+            pass
+        else:
+            raise AssertionError("truncated record was treated as EOF")
+        # complete records before the damage are still delivered in small chunks
+        if chunk_size == 1:
+            assert seen == [f"r{i}" for i in range(4)]
+
+    # file cut in the middle of a compressed block
+    data = (Path(tmp) / "ok.bam").read_bytes()
+    path = Path(tmp) / "short_block.bam"
+    path.write_bytes(data[: len(data) // 2])
+    try:
+        read_all(path)
+    except OSError:
+        pass
+    else:
+        raise AssertionError("truncated bgzf block was treated as EOF")
@@ -0,0 +1,84 @@
+import tempfile
+from pathlib import Path
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+f = lb.BamReader(str(path_to_bam), chunk_size=1)
+
+with tempfile.TemporaryDirectory() as tmp:
+    # uBAM record synthesized without a reference
+    ubam_path = Path(tmp) / "unmapped.bam"
+    buf = lb.PyRecordBuf("read1", "ACGTACGT", [30] * 8)
+    lb.write_recordbuf_chunk_py(f._header, [buf], str(ubam_path), sort=False)
+    unmapped = next(lb.BamReader(str(ubam_path)))[0]
+    assert unmapped.flag & 0x4
+    assert unmapped.rid == -1
+
+    # PyBamRecord -> RecordBuf -> BAM keeps the unmapped sentinels
+    roundtrip_path = Path(tmp) / "roundtrip.bam"
+    lb.write_chunk_py(f._header, [unmapped], str(roundtrip_path), sort=False)
+    record = next(lb.BamReader(str(roundtrip_path)))[0]
+    assert record.flag & 0x4
+    assert record.rid == -1
+    assert record.pos == -1
+    assert record.mapq == 0
+    assert record.cigar == []
+    assert record.seq == "ACGTACGT"
+
+# an unmapped read placed at its mate keeps RNAME/POS, so coordinate order holds
+with tempfile.TemporaryDirectory() as tmp:
+    placed_path = Path(tmp) / "placed.bam"
+    write_bam(
+        placed_path,
+        [("chr1", 1000)],
+        [
+            encode_record("m", flag=0x1 | 0x8 | 0x40, rid=0, pos=99, cigar=[("M", 4)],
+                          next_rid=0, next_pos=99, seq="ACGT", qual=[30] * 4),
+            encode_record("m", flag=0x1 | 0x4 | 0x80, rid=0, pos=99, mapq=0,
+                          next_rid=0, next_pos=99, seq="TTTT", qual=[30] * 4),
+        ],
+    )
+    reader = lb.BamReader(str(placed_path))
+    out_path = Path(tmp) / "placed_out.bam"
+    lb.write_chunk_py(reader._header, [r for chunk in reader for r in chunk], str(out_path), sort=False)
+    mate, placed = [r for chunk in lb.BamReader(str(out_path)) for r in chunk]
+    assert placed.flag & 0x4
+    assert (placed.rid, placed.pos) == (0, 100)
+    assert placed.mapq == 0 and placed.cigar == []
+    assert (mate.rid, mate.pos) == (0, 100)
+
+# a mapped record without an alignment start is rejected, not written at 0
+with tempfile.TemporaryDirectory() as tmp:
+    broken_path = Path(tmp) / "no_start.bam"
+    write_bam(
+        broken_path,
+        [("chr1", 1000)],
+        [encode_record("nostart", rid=0, pos=-1, cigar=[("M", 4)], seq="ACGT", qual=[30] * 4)],
+    )
+    try:
+        lb.transform(str(broken_path), str(Path(tmp) / "out.bam"), lambda r: r)
+    except ValueError as e:
+        assert "no alignment start" in str(e)
+    else:
+        raise AssertionError("mapped record without a start was written")
+
+# rname resolves through the reader's header; unmapped records have none
+mapped = next(lb.BamReader(str(path_to_bam), chunk_size=1))[0]
+assert mapped.rname == "Ala1B"
+assert unmapped.rname is None
+assert record.rname is None
+
+# a record without a sequence gives an empty array
+with tempfile.TemporaryDirectory() as tmp:
+    empty_path = Path(tmp) / "empty_seq.bam"
+    write_bam(empty_path, [("chr1", 1000)], [encode_record("noseq", flag=0x4)])
+    noseq = next(lb.BamReader(str(empty_path)))[0]
+assert noseq.seq == ""
+assert len(noseq.seq_array) == 0
+
+# without a CIGAR, query_length falls back to the sequence length
+assert unmapped.reference_length == 0
+assert unmapped.query_length == len("ACGTACGT")
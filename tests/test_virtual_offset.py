@@ -0,0 +1,43 @@
+import tempfile
+from pathlib import Path
+
+import lazybam as lb
+from bam_builder import encode_header, encode_record, write_bam
+
+refs = [("chr1", 1000)]
+records = [
+    encode_record(f"r{i}", rid=0, pos=i, cigar=[("M", 4)], seq="ACGT") for i in range(5)
+]
+
+with tempfile.TemporaryDirectory() as tmp:
+    path = Path(tmp) / "offsets.bam"
+    write_bam(path, refs, records)
+
+    # everything sits in the first bgzf block (compressed offset 0), so the
+    # virtual offset of each record is its byte offset in the decompressed data
+    expected = []
+    offset = len(encode_header(refs))
+    for rec in records:
+        expected.append(offset)
+        offset += len(rec)
+
+    reader = lb.BamReader(str(path), chunk_size=1)
+    seen = []
+    while True:
+        vpos = reader.virtual_position
+        chunk = next(reader, None)
+        if chunk is None:
+            break
+        seen.append((vpos, chunk[0].qname))
+    assert seen == [(off, f"r{i}") for i, off in enumerate(expected)]
+    # at EOF the offset lies past the last record
+    assert reader.virtual_position > expected[-1]
+
+    # single-record iteration reports the same offsets
+    reader = lb.BamReader(str(path))
+    it = reader.records()
+    offsets = []
+    for _ in records:
+        offsets.append(reader.virtual_position)
+        next(it)
+    assert offsets == expected
@@ -0,0 +1,31 @@
+import tempfile
+from pathlib import Path
+
+path_to_bam = Path(__file__).parent / "data" / "test_reads.bam"
+
+import lazybam as lb
+from bam_builder import encode_record, write_bam
+
+records = [r for chunk in lb.BamReader(str(path_to_bam), chunk_size=1000) for r in chunk]
+primary = [r for r in records if not r.flag & (0x100 | 0x800)]
+
+with tempfile.TemporaryDirectory() as tmp:
+    out_path = Path(tmp) / "reads.fa"
+    n = lb.BamReader(str(path_to_bam)).write_fasta(str(out_path))
+    lines = out_path.read_text().splitlines()
+
+assert n == len(primary)
+assert sum(line.startswith(">") for line in lines) == len(primary)
+# forward-strand test reads are written as stored
+assert lines[0] == ">" + primary[0].qname
+assert lines[1] == primary[0].seq
+
+# reverse-strand reads are complemented like seq_forward, IUPAC codes included
+with tempfile.TemporaryDirectory() as tmp:
+    bam_path = Path(tmp) / "iupac.bam"
+    write_bam(bam_path, [("chr1", 1000)], [encode_record("rev", flag=0x10, seq="AACGTNRK")])
+    out_path = Path(tmp) / "iupac.fa"
+    lb.BamReader(str(bam_path)).write_fasta(str(out_path))
+    rev = next(lb.BamReader(str(bam_path)))[0]
+    assert out_path.read_text().splitlines() == [">rev", "MYNACGTT"]
+    assert rev.seq_forward == "MYNACGTT"